@@ -0,0 +1,231 @@
+//! # Amiwô derive
+//!
+//! Custom derive for the [Amiwô](https://crates.io/crates/amiwo) crate, re-exported by amiwo behind the `amiwo_derive` feature.
+//!
+//! `#[derive(IntoResponseJSON)]` implements `amiwo::traits::IntoResponseJSON` & Rocket's `Responder` for a `Serialize` type,
+//! so it can be returned directly from a Rocket route, wrapped into an OK `ResponseJSON`.
+//! The HTTP code of the envelope defaults to 200 and can be set with the `response_json` attribute:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, IntoResponseJSON)]
+//! #[response_json(http_code = 201)]
+//! struct CreatedUser {
+//!     id: u32,
+//! }
+//!
+//! #[post("/users")]
+//! fn create() -> CreatedUser {
+//!     CreatedUser { id: 17 }
+//! }
+//! ```
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+extern crate proc_macro;
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use proc_macro::TokenStream;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+// What the derive needs to know about the annotated type
+#[derive(Debug, PartialEq)]
+struct DeriveInput {
+    name: String,
+    http_code: u16,
+}
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+#[proc_macro_derive(IntoResponseJSON, attributes(response_json))]
+pub fn derive_into_response_json(input: TokenStream) -> TokenStream {
+    let input = parse_input(&input.to_string()).unwrap_or_else(|msg| panic!("#[derive(IntoResponseJSON)] {}", msg));
+    generate(&input).parse().unwrap()
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Generate the trait implementations for `input`
+fn generate(input: &DeriveInput) -> String {
+    format!(r#"
+        impl ::amiwo::traits::IntoResponseJSON for {name} {{
+            fn http_code() -> u16 {{
+                {http_code}
+            }}
+        }}
+
+        impl<'r> ::rocket::response::Responder<'r> for {name} {{
+            fn respond(self) -> ::std::result::Result<::rocket::Response<'r>, ::rocket::http::Status> {{
+                ::rocket::response::Responder::respond(::amiwo::traits::IntoResponseJSON::into_response_json(self))
+            }}
+        }}
+    "#, name = input.name, http_code = input.http_code)
+}
+
+// Extract the name & the `response_json` attribute of the struct / enum described by `source`
+fn parse_input(source: &str) -> Result<DeriveInput, String> {
+    let chars : Vec<char> = source.chars().collect();
+    let mut attributes = Vec::new();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        if c == '#' {
+            // attribute => keep its content, between the brackets
+            let start = chars[idx..].iter().position(|&c| c == '[').map_or(chars.len(), |pos| idx + pos + 1);
+            let end = skip_group(&chars, start, '[', ']');
+            attributes.push(chars[start..end].iter().collect::<String>());
+            idx = end + 1;
+        } else if c == '"' {
+            idx = skip_string(&chars, idx + 1) + 1;
+        } else if c == '/' && chars.get(idx + 1) == Some(&'/') {
+            // doc comment => skip the line
+            idx = chars[idx..].iter().position(|&c| c == '\n').map_or(chars.len(), |pos| idx + pos + 1);
+        } else if c == '/' && chars.get(idx + 1) == Some(&'*') {
+            // block doc comment => skip until its end
+            idx = chars[idx..].windows(2).position(|w| w == ['*', '/']).map_or(chars.len(), |pos| idx + pos + 2);
+        } else if c.is_alphabetic() || c == '_' {
+            let start = idx;
+            while idx < chars.len() && (chars[idx].is_alphanumeric() || chars[idx] == '_') {
+                idx += 1;
+            }
+            tokens.push(chars[start..idx].iter().collect::<String>());
+        } else {
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+            idx += 1;
+        }
+
+        // stop after the token following the type name
+        if let Some(pos) = tokens.iter().position(|token| token == "struct" || token == "enum") {
+            if tokens.len() > pos + 2 { break; }
+        }
+    }
+
+    let pos = match tokens.iter().position(|token| token == "struct" || token == "enum") {
+        Some(pos) if tokens.len() > pos + 1 => pos,
+        _ => return Err("can only be used on structs & enums".to_string()),
+    };
+    if tokens.get(pos + 2).map_or(false, |token| token == "<") {
+        return Err("doesn't support generic types".to_string());
+    }
+
+    let mut http_code = 200;
+    for attribute in attributes.iter().map(|attribute| attribute.trim()).filter(|attribute| attribute.starts_with("response_json")) {
+        http_code = parse_attribute(&attribute["response_json".len()..])?;
+    }
+
+    Ok(DeriveInput {
+        name: tokens[pos + 1].clone(),
+        http_code: http_code,
+    })
+}
+
+// Parse the arguments of `#[response_json(http_code = <code>)]`, returning the HTTP code
+fn parse_attribute(args: &str) -> Result<u16, String> {
+    let args = args.trim();
+    if !args.starts_with('(') || !args.ends_with(')') {
+        return Err("expects an attribute like #[response_json(http_code = 201)]".to_string());
+    }
+
+    let mut http_code = 200;
+    for arg in args[1..args.len() - 1].split(',').map(|arg| arg.trim()).filter(|arg| !arg.is_empty()) {
+        let parts : Vec<&str> = arg.splitn(2, '=').map(|part| part.trim()).collect();
+        match (parts[0], parts.get(1)) {
+            ("http_code", Some(value)) => {
+                http_code = match value.parse::<u16>() {
+                    Ok(code) if code >= 100 && code <= 999 => code,
+                    _ => return Err(format!("`http_code` must be an integer between 100 and 999, found `{}`", value)),
+                };
+            },
+            _ => return Err(format!("unknown argument `{}` in #[response_json(...)]", arg)),
+        }
+    }
+
+    Ok(http_code)
+}
+
+// Returns the index of the `close` char matching the group opened just before `start`
+fn skip_group(chars: &[char], start: usize, open: char, close: char) -> usize {
+    let mut depth = 1;
+    let mut idx = start;
+    while idx < chars.len() {
+        if chars[idx] == '"' {
+            idx = skip_string(chars, idx + 1);
+        } else if chars[idx] == open {
+            depth += 1;
+        } else if chars[idx] == close {
+            depth -= 1;
+            if depth == 0 { return idx; }
+        }
+        idx += 1;
+    }
+    chars.len()
+}
+
+// Returns the index of the quote closing the string literal starting at `start`
+fn skip_string(chars: &[char], start: usize) -> usize {
+    let mut idx = start;
+    while idx < chars.len() && chars[idx] != '"' {
+        if chars[idx] == '\\' { idx += 1; }
+        idx += 1;
+    }
+    idx
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ parse_input, DeriveInput };
+
+    #[test]
+    fn parse_input_test_default() {
+        assert_eq!(
+            parse_input("pub struct User { id : u32 , name : String , }"),
+            Ok(DeriveInput { name: "User".to_string(), http_code: 200 })
+        );
+        assert_eq!(
+            parse_input("# [ doc = \"A struct ] with [ brackets\" ] # [ serde ( rename_all = \"camelCase\" ) ] enum Status { Active , }"),
+            Ok(DeriveInput { name: "Status".to_string(), http_code: 200 })
+        );
+        assert_eq!(
+            parse_input("/// The struct of a user\n/** Also an enum */ struct User { id : u32 }"),
+            Ok(DeriveInput { name: "User".to_string(), http_code: 200 })
+        );
+    }
+
+    #[test]
+    fn parse_input_test_http_code() {
+        assert_eq!(
+            parse_input("#[response_json(http_code = 201)] pub struct CreatedUser { id: u32 }"),
+            Ok(DeriveInput { name: "CreatedUser".to_string(), http_code: 201 })
+        );
+        assert_eq!(
+            parse_input("# [ response_json ( http_code = 202 ) ] struct Accepted ;"),
+            Ok(DeriveInput { name: "Accepted".to_string(), http_code: 202 })
+        );
+    }
+
+    #[test]
+    fn parse_input_test_errors() {
+        assert!(parse_input("#[response_json(http_code = 2000)] struct User;").is_err());
+        assert!(parse_input("#[response_json(code = 201)] struct User;").is_err());
+        assert!(parse_input("#[response_json = 201] struct User;").is_err());
+        assert!(parse_input("struct Page<T> { items: Vec<T> }").is_err());
+    }
+}
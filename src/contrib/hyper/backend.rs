@@ -0,0 +1,202 @@
+//! File holding the HttpBackend trait, implemented by AmiwoClient, and the MockBackend used to test the code sending requests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use contrib::hyper::{ AmiwoClient, RequestSpec };
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT & TRAIT DEFINITION
+// =======================================================================
+/// Backend sending the outbound requests, to be taken as a generic parameter by the code sending requests
+/// so that it can be unit tested with a `MockBackend`
+///
+/// ```rust,ignore
+/// fn user_name<B: HttpBackend>(backend: &B, id: u64) -> Result<String, GenericError> {
+///     let user : User = backend.request_as("GET", &format!("http://users.internal/users/{}", id))?;
+///     Ok(user.name)
+/// }
+///
+/// let backend = MockBackend::new().on("GET", "http://users.internal/users/17", ResponseJSON::ok_with_data(json!({ "name": "Boris" })));
+/// assert_eq!(user_name(&backend, 17).unwrap(), "Boris");
+/// ```
+pub trait HttpBackend {
+    /// Send the request described by `spec` and pre-process the response to try to build a `ResponseJSON` from it
+    fn send(&self, spec: RequestSpec) -> Result<ResponseJSON, GenericError>;
+
+    /// Send a simple `method` request to `url`
+    fn request(&self, method: &str, url: &str) -> Result<ResponseJSON, GenericError> {
+        self.send(RequestSpec::new(method, url))
+    }
+
+    /// Send a `method` request with `body` to `url`
+    fn request_with_body(&self, method: &str, url: &str, body: &str) -> Result<ResponseJSON, GenericError> {
+        self.send(RequestSpec::new(method, url).body(body))
+    }
+
+    /// Send a simple `method` request to `url` and deserialize the response into `T` (see `contrib::hyper::request_as()`)
+    fn request_as<T: DeserializeOwned>(&self, method: &str, url: &str) -> Result<T, GenericError> where Self: Sized {
+        self.request(method, url).and_then(ResponseJSON::into_typed)
+    }
+
+    /// Send a `POST` request to `url` with `body` serialized as JSON
+    fn post_json<T: Serialize>(&self, url: &str, body: &T) -> Result<ResponseJSON, GenericError> where Self: Sized {
+        self.send(RequestSpec::new("POST", url).body_json(body)?)
+    }
+}
+
+/// `HttpBackend` answering the requests with canned `ResponseJSON`s and recording them, to unit test the code sending requests without a network
+///
+/// A request is answered by the first mock matching its method (case insensitive) & its url, an url ending with `*`
+/// matching all the urls starting with it. An unmatched request fails with an error
+///
+/// ```rust,ignore
+/// let backend = MockBackend::new()
+///     .on("GET", "http://users.internal/users/17", ResponseJSON::ok_with_data(json!({ "name": "Boris" })))
+///     .on("DELETE", "http://users.internal/*", ResponseJSON::error_with_message(403, "Forbidden"));
+/// ...
+/// assert_eq!(backend.requests()[0].url, "http://users.internal/users/17");
+/// ```
+#[derive(Debug)]
+pub struct MockBackend {
+    mocks: Vec<(String, String, ResponseJSON)>,
+    requests: Mutex<Vec<RequestSpec>>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl MockBackend {
+    /// Create a backend with no mock
+    pub fn new() -> MockBackend {
+        MockBackend {
+            mocks: Vec::new(),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Answer the `method` requests to `url` (`*` suffix matching any end of url) with `response`
+    pub fn on(mut self, method: &str, url: &str, response: ResponseJSON) -> MockBackend {
+        self.mocks.push((method.to_uppercase(), url.to_string(), response));
+        self
+    }
+
+    /// Requests received so far, in order
+    pub fn requests(&self) -> Vec<RequestSpec> {
+        match self.requests.lock() {
+            Ok(requests) => requests.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Check if the mocked `pattern` matches `url`
+fn matches(pattern: &str, url: &str) -> bool {
+    if pattern.ends_with('*') {
+        url.starts_with(&pattern[..pattern.len() - 1])
+    } else {
+        pattern == url
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+/// Send the request with the connections, retry & redirect policies of the client
+impl HttpBackend for AmiwoClient {
+    fn send(&self, spec: RequestSpec) -> Result<ResponseJSON, GenericError> {
+        self.send_spec(spec)
+    }
+}
+
+impl HttpBackend for MockBackend {
+    fn send(&self, spec: RequestSpec) -> Result<ResponseJSON, GenericError> {
+        let method = spec.method.to_uppercase();
+        let response = self.mocks.iter()
+            .find(|&&(ref mock_method, ref mock_url, _)| *mock_method == method && matches(mock_url, &spec.url))
+            .map(|&(_, _, ref response)| response.clone());
+        let url = spec.url.clone();
+
+        match self.requests.lock() {
+            Ok(mut requests) => requests.push(spec),
+            Err(poisoned) => poisoned.into_inner().push(spec),
+        }
+        match response {
+            Some(response) => Ok(response),
+            None => amiwo_error!(format!("::AMIWO::CONTRIB::HYPER::MOCK_BACKEND::SEND::ERROR No mock for {} {}", method, url)),
+        }
+    }
+}
+
+impl Default for MockBackend {
+    fn default() -> MockBackend {
+        MockBackend::new()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ HttpBackend, MockBackend };
+    use contrib::hyper::AmiwoClient;
+    use contrib::hyper::tests::{ json_response, serve };
+    use error::GenericError;
+    use types::ResponseJSON;
+
+    #[derive(Deserialize)]
+    struct User {
+        name: String,
+    }
+
+    fn user_name<B: HttpBackend>(backend: &B, id: u64) -> Result<String, GenericError> {
+        let user : User = backend.request_as("GET", &format!("http://users.internal/users/{}", id))?;
+        Ok(user.name)
+    }
+
+    #[test]
+    fn MockBackend_test_send() {
+        let backend = MockBackend::new()
+            .on("get", "http://users.internal/users/17", ResponseJSON::ok_with_data(json!({ "name": "Boris" })))
+            .on("GET", "http://users.internal/*", ResponseJSON::error_with_message(404, "Not found"))
+            .on("POST", "http://users.internal/users", ResponseJSON::ok().http_code(201));
+
+        assert_eq!(user_name(&backend, 17).unwrap(), "Boris");
+        assert!(user_name(&backend, 18).is_err());
+        assert_eq!(backend.post_json("http://users.internal/users", &json!({ "name": "Bob" })).unwrap().http_code, 201);
+        assert!(backend.request("DELETE", "http://users.internal/users/17").is_err());
+
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[1].url, "http://users.internal/users/18");
+        assert_eq!(requests[2].body, Some(br#"{"name":"Bob"}"#.to_vec()));
+        assert_eq!(requests[3].method, "DELETE");
+    }
+
+    #[test]
+    fn HttpBackend_test_client() {
+        let (url, handle) = serve(vec![json_response("200 OK", r#"{"success":true,"http_code":200,"data":{"name":"Boris"}}"#)]);
+        let backend : &HttpBackend = &AmiwoClient::new();
+        assert_eq!(backend.request_with_body("PUT", &format!("{}/users/17", url), "name=Boris").unwrap().data, json!({ "name": "Boris" }));
+        assert!(handle.join().unwrap()[0].ends_with("\r\n\r\nname=Boris"));
+    }
+}
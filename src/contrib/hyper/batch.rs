@@ -0,0 +1,201 @@
+//! File holding the RequestSpec type & the batch() function, sending several requests in parallel
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.1
+//!
+//! ## Release notes
+//! - v1.1 : the requests are sent by a closure, allowing `AmiwoClient` to apply its interceptors
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::cmp;
+use std::sync::{ mpsc, Arc, Mutex };
+use std::thread;
+
+use hyper::client::Client;
+use hyper::header::{ ContentType, Headers };
+use serde::Serialize;
+use serde_json;
+
+use contrib::hyper::{ parse_target, RedirectPolicy, RetryPolicy };
+use contrib::hyper::retry::send_with_retry;
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Description of an outbound request, sent by `batch()`
+///
+/// ```rust,ignore
+/// let results = amiwo::contrib::hyper::batch(vec![
+///     RequestSpec::new("GET", "http://users.internal/users/17"),
+///     RequestSpec::new("GET", "http://orders.internal/orders?user=17").header("X-Api-Key", "secret"),
+/// ], 4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RequestSpec {
+    pub method: String,
+    pub url: String,
+    pub headers: Headers,
+    pub body: Option<Vec<u8>>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl RequestSpec {
+    /// Describe a `method` request to `url` with no headers & no body
+    pub fn new(method: &str, url: &str) -> RequestSpec {
+        RequestSpec {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: Headers::new(),
+            body: None,
+        }
+    }
+
+    /// Add a header to the request, a name repeated adding a value to the header
+    pub fn header(mut self, name: &str, value: &str) -> RequestSpec {
+        self.headers.append_raw(name.to_string(), value.as_bytes().to_vec());
+        self
+    }
+
+    /// Set the body of the request
+    pub fn body(mut self, body: &str) -> RequestSpec {
+        self.body = Some(body.as_bytes().to_vec());
+        self
+    }
+
+    /// Set the body of the request to `body` serialized as JSON, with `Content-Type: application/json`
+    pub fn body_json<T: Serialize>(mut self, body: &T) -> Result<RequestSpec, GenericError> {
+        self.body = Some(serde_json::to_vec(body)?);
+        self.headers.set(ContentType::json());
+        Ok(self)
+    }
+}
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Send `requests` in parallel, at most `concurrency` of them at a time, and pre-process their responses
+/// to try to build `ResponseJSON`s from them.
+///
+/// The results are returned in the order of `requests`, a failing request not stopping the others
+///
+/// A new `hyper::Client` is created for each call (shared by the requests), use `AmiwoClient::batch()` to reuse the connections
+pub fn batch(requests: Vec<RequestSpec>, concurrency: usize) -> Vec<Result<ResponseJSON, GenericError>> {
+    let client = Client::new();
+    batch_with(move |spec| send_spec_with(&client, None, None, spec), requests, concurrency)
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Send `requests` with `send` on `concurrency` threads (at least one, at most one per request)
+pub(crate) fn batch_with<F>(send: F, requests: Vec<RequestSpec>, concurrency: usize) -> Vec<Result<ResponseJSON, GenericError>>
+    where F: Fn(RequestSpec) -> Result<ResponseJSON, GenericError> + Send + Sync + 'static
+{
+    let send = Arc::new(send);
+    let count = requests.len();
+    let queue = Arc::new(Mutex::new(requests.into_iter().enumerate()));
+    let (sender, receiver) = mpsc::channel();
+
+    let workers : Vec<_> = (0..cmp::max(1, cmp::min(concurrency, count)))
+        .map(|_| {
+            let send = send.clone();
+            let queue = queue.clone();
+            let sender = sender.clone();
+            thread::spawn(move || loop {
+                let next = queue.lock().ok().and_then(|mut queue| queue.next());
+                let (index, spec) = match next {
+                    Some(next) => next,
+                    None => return,
+                };
+                if sender.send((index, send(spec))).is_err() {
+                    return;
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let mut results : Vec<Option<Result<ResponseJSON, GenericError>>> = (0..count).map(|_| None).collect();
+    for (index, result) in receiver {
+        results[index] = Some(result);
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+
+    results.into_iter()
+        .map(|result| result.unwrap_or_else(|| amiwo_error!("::AMIWO::CONTRIB::HYPER::BATCH::ERROR The request was aborted by a panicking thread".to_string())))
+        .collect()
+}
+
+// Send the request described by `spec` with `client`
+pub(crate) fn send_spec_with(client: &Client, retry: Option<&RetryPolicy>, redirect: Option<&RedirectPolicy>, spec: RequestSpec) -> Result<ResponseJSON, GenericError> {
+    let (method, url) = parse_target(&spec.method, &spec.url)?;
+    send_with_retry(client, retry, redirect, method, url, spec.headers, spec.body.as_ref().map(|body| body.as_slice()))
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::io::{ Read, Write };
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::{ batch, RequestSpec };
+    use contrib::hyper::AmiwoClient;
+
+    // Answer `count` requests with their path as `data`
+    fn serve_paths(count: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || for _ in 0..count {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 1024];
+            let length = stream.read(&mut buffer).unwrap();
+            let request = String::from_utf8_lossy(&buffer[..length]).into_owned();
+            let path = request.split(' ').nth(1).unwrap_or("").to_string();
+            let body = format!(r#"{{"success":true,"http_code":200,"data":"{}"}}"#, path);
+            let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn batch_test_order() {
+        let url = serve_paths(5);
+        let mut requests : Vec<RequestSpec> = (0..5).map(|i| RequestSpec::new("GET", &format!("{}/items/{}", url, i))).collect();
+        requests.insert(2, RequestSpec::new("GET", "not an url"));
+
+        let results = batch(requests, 3);
+        assert_eq!(results.len(), 6);
+        assert!(results[2].is_err());
+        let paths : Vec<_> = results.into_iter().filter_map(|result| result.ok()).map(|rjson| rjson.data).collect();
+        assert_eq!(paths, vec![json!("/items/0"), json!("/items/1"), json!("/items/2"), json!("/items/3"), json!("/items/4")]);
+
+        assert!(batch(Vec::new(), 4).is_empty());
+    }
+
+    #[test]
+    fn batch_test_client() {
+        let url = serve_paths(2);
+        let requests = vec![
+            RequestSpec::new("POST", &format!("{}/users", url)).body_json(&json!({ "name": "Boris" })).unwrap(),
+            RequestSpec::new("PUT", &format!("{}/users/17", url)).header("X-Api-Key", "secret").body("name=Boris"),
+        ];
+        let results = AmiwoClient::new().batch(requests, 0);
+        assert_eq!(results[0].as_ref().unwrap().data, json!("/users"));
+        assert_eq!(results[1].as_ref().unwrap().data, json!("/users/17"));
+    }
+}
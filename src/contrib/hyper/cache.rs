@@ -0,0 +1,341 @@
+//! File holding the ResponseCache trait & the MemoryCache type, caching the responses of the `GET` requests of an AmiwoClient
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.6
+//!
+//! ## Release notes
+//! - v1.6 : `send_cached()` also returns the HTTP status of the response (200 for a validated cached response)
+//! - v1.5 : `send_cached()` sends & stores the cookies of a CookieJar, the requests with cookies of the jar not being cached
+//! - v1.4 : the requests with credentials aren't cached, a cached response is only used for the same values of its `Vary` headers
+//! - v1.3 : the compressed responses are decoded
+//! - v1.2 : the metrics of the requests are recorded
+//! - v1.1 : `send_cached()` calls `on_response` with the headers of the responses
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{ Duration, Instant };
+
+use hyper::client::Client;
+use hyper::header::Headers;
+use hyper::method::Method;
+use hyper::Url;
+
+use contrib::hyper::{ send_raw, CookieJar, RedirectPolicy, RetryPolicy };
+use contrib::hyper::decode::read_json;
+use contrib::hyper::metrics;
+use contrib::hyper::retry::retry_with;
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT & TRAIT DEFINITION
+// =======================================================================
+/// Response stored in a `ResponseCache` with its validators
+#[derive(Clone, Debug)]
+pub struct CachedResponse {
+    pub rjson: ResponseJSON,
+    /// Value of the `ETag` header of the response
+    pub etag: Option<String>,
+    /// Value of the `Last-Modified` header of the response
+    pub last_modified: Option<String>,
+    /// Headers named by the `Vary` header of the response & their values in the request (`None` if absent)
+    pub vary: Vec<(String, Option<String>)>,
+}
+
+/// Store of the responses of the `GET` requests sent by an `AmiwoClient`, keyed by URL
+///
+/// The successful responses having an `ETag` or a `Last-Modified` header are stored. A cached URL is requested again
+/// with `If-None-Match` / `If-Modified-Since`, an upstream service answering `304 Not Modified` being answered with
+/// the cached ResponseJSON.
+///
+/// The requests carrying credentials (`Authorization`, `Proxy-Authorization` or `Cookie` header) are never cached,
+/// their responses may be private to the caller. A cached response with a `Vary` header is only used by the requests
+/// having the same values for the headers it names (`Vary: *` not being cached)
+///
+/// ```rust,ignore
+/// let client = AmiwoClient::new().cache(MemoryCache::new().ttl(Duration::from_secs(3600)).max_entries(100));
+/// ```
+pub trait ResponseCache: Send + Sync {
+    /// Cached response of `url`, if any
+    fn get(&self, url: &str) -> Option<CachedResponse>;
+
+    /// Store `response` as the response of `url`
+    fn put(&self, url: &str, response: CachedResponse);
+}
+
+/// In-memory `ResponseCache`, an entry expiring `ttl` after being stored (or validated), the oldest entry being evicted
+/// when more than `max_entries` responses are stored
+#[derive(Debug)]
+pub struct MemoryCache {
+    ttl: Option<Duration>,
+    max_entries: usize,
+    entries: Mutex<HashMap<String, (Instant, CachedResponse)>>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl MemoryCache {
+    /// Default cache: up to 1000 entries never expiring
+    pub fn new() -> MemoryCache {
+        MemoryCache {
+            ttl: None,
+            max_entries: 1000,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the time after which an entry expires
+    pub fn ttl(mut self, ttl: Duration) -> MemoryCache {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Set the maximum number of entries
+    pub fn max_entries(mut self, max_entries: usize) -> MemoryCache {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Number of entries (expired or not)
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|entries| entries.len()).unwrap_or(0)
+    }
+
+    /// Remove all the entries
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Send a `GET` request to `url` with `client`, validating the response cached in `cache` (if any) & caching the new responses,
+// the cookies of `cookies` (if any) being sent & stored on each request. Returns the HTTP status of the response with its ResponseJSON
+pub(crate) fn send_cached(client: &Client, cache: &ResponseCache, retry: Option<&RetryPolicy>, redirect: Option<&RedirectPolicy>, url: Url, headers: Headers, cookies: Option<&CookieJar>) -> Result<(u16, ResponseJSON), GenericError> {
+    // the responses to requests with credentials may be private to the caller
+    let with_cookies = cookies.map_or(false, |jar| !jar.cookies(url.host_str().unwrap_or("")).is_empty());
+    let shared = !with_cookies && !has_credentials(&headers);
+    let key = url.to_string();
+    let cached = match cache.get(&key) {
+        Some(cached) => if shared && vary_matches(&cached, &headers) { Some(cached) } else { None },
+        None => None,
+    };
+
+    let request_headers = headers.clone();
+    let mut headers = headers;
+    if let Some(ref cached) = cached {
+        if let Some(ref etag) = cached.etag {
+            headers.set_raw("If-None-Match", vec![etag.clone().into_bytes()]);
+        }
+        if let Some(ref last_modified) = cached.last_modified {
+            headers.set_raw("If-Modified-Since", vec![last_modified.clone().into_bytes()]);
+        }
+    }
+
+    retry_with(retry, &Method::Get, &url, || {
+        let started = Instant::now();
+        let (response, redirected) = match send_raw(client, redirect, Method::Get, url.clone(), headers.clone(), None, cookies) {
+            Ok(sent) => sent,
+            Err(err) => {
+                metrics::record(&Method::Get, &url, None, 0, 0, started);
+                return Err(err);
+            },
+        };
+        let status = response.status.to_u16();
+        if let (304, Some(cached)) = (status, cached.clone()) {
+            metrics::record(&Method::Get, &url, Some(status), 0, 0, started);
+            let rjson = cached.rjson.clone();
+            cache.put(&key, cached);
+            return Ok((200, rjson));
+        }
+
+        let etag = header_value(&response.headers, "ETag");
+        let last_modified = header_value(&response.headers, "Last-Modified");
+        let vary = vary_names(&response.headers);
+        let (result, response_bytes) = read_json(response);
+        metrics::record(&Method::Get, &url, Some(status), 0, response_bytes, started);
+        let mut rjson = result?;
+        if let Some(url) = redirected {
+            rjson.resource = Some(url.to_string());
+        }
+        match vary {
+            Some(vary) if shared && status >= 200 && status < 300 && (etag.is_some() || last_modified.is_some()) => {
+                let vary = vary.into_iter().map(|name| { let value = header_value(&request_headers, &name); (name, value) }).collect();
+                cache.put(&key, CachedResponse { rjson: rjson.clone(), etag: etag, last_modified: last_modified, vary: vary });
+            },
+            _ => (),
+        }
+        Ok((status, rjson))
+    })
+}
+
+// First value of the header `name`, if any
+fn header_value(headers: &Headers, name: &str) -> Option<String> {
+    headers.get_raw(name)
+        .and_then(|values| values.first())
+        .map(|value| String::from_utf8_lossy(value).into_owned())
+}
+
+// `true` if the request `headers` carry credentials
+fn has_credentials(headers: &Headers) -> bool {
+    ["Authorization", "Proxy-Authorization", "Cookie"].iter().any(|name| headers.get_raw(name).is_some())
+}
+
+// Names of the headers listed by the `Vary` header of the response `headers`, `None` for `Vary: *`
+fn vary_names(headers: &Headers) -> Option<Vec<String>> {
+    let names : Vec<String> = headers.get_raw("Vary")
+        .map(|values| values.iter()
+            .flat_map(|value| String::from_utf8_lossy(value).split(',').map(|name| name.trim().to_string()).collect::<Vec<_>>())
+            .filter(|name| !name.is_empty())
+            .collect())
+        .unwrap_or(Vec::new());
+    if names.iter().any(|name| name == "*") { None } else { Some(names) }
+}
+
+// `true` if the request `headers` have the values of the `Vary` headers of `cached`
+fn vary_matches(cached: &CachedResponse, headers: &Headers) -> bool {
+    cached.vary.iter().all(|&(ref name, ref value)| header_value(headers, name) == *value)
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl ResponseCache for MemoryCache {
+    fn get(&self, url: &str) -> Option<CachedResponse> {
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(_) => return None,
+        };
+        let expired = match entries.get(url) {
+            Some(&(stored_at, _)) => self.ttl.map_or(false, |ttl| stored_at.elapsed() >= ttl),
+            None => return None,
+        };
+        if expired {
+            entries.remove(url);
+            return None;
+        }
+        entries.get(url).map(|&(_, ref response)| response.clone())
+    }
+
+    fn put(&self, url: &str, response: CachedResponse) {
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        if !entries.contains_key(url) && entries.len() >= self.max_entries {
+            let oldest = entries.iter().min_by_key(|&(_, &(stored_at, _))| stored_at).map(|(url, _)| url.clone());
+            match oldest {
+                Some(oldest) => { entries.remove(&oldest); },
+                None => return,
+            }
+        }
+        entries.insert(url.to_string(), (Instant::now(), response));
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> MemoryCache {
+        MemoryCache::new()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{ CachedResponse, MemoryCache, ResponseCache };
+    use contrib::hyper::{ build_headers, AmiwoClient };
+    use contrib::hyper::tests::serve;
+    use types::ResponseJSON;
+
+    fn cached(etag: &str) -> CachedResponse {
+        CachedResponse { rjson: ResponseJSON::ok(), etag: Some(etag.to_string()), last_modified: None, vary: Vec::new() }
+    }
+
+    #[test]
+    fn MemoryCache_test_entries() {
+        let cache = MemoryCache::new().max_entries(2);
+        cache.put("http://a", cached("a"));
+        thread::sleep(Duration::from_millis(2));
+        cache.put("http://b", cached("b"));
+        cache.put("http://a", cached("a2"));
+        assert_eq!(cache.len(), 2);
+        cache.put("http://c", cached("c"));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("http://b").is_none());
+        assert_eq!(cache.get("http://a").unwrap().etag, Some("a2".to_string()));
+
+        let cache = MemoryCache::new().ttl(Duration::from_millis(20));
+        cache.put("http://a", cached("a"));
+        assert!(cache.get("http://a").is_some());
+        thread::sleep(Duration::from_millis(30));
+        assert!(cache.get("http://a").is_none());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn MemoryCache_test_client() {
+        let body = r#"{"success":true,"http_code":200,"data":["XOF","EUR"]}"#;
+        let (url, handle) = serve(vec![
+            format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body),
+            "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        ]);
+
+        let client = AmiwoClient::new().cache(MemoryCache::new());
+        let url = format!("{}/currencies", url);
+        assert_eq!(client.request("GET", &url).unwrap().data, json!(["XOF", "EUR"]));
+        let rjson = client.request("GET", &url).unwrap();
+        assert_eq!(rjson.http_code, 200);
+        assert_eq!(rjson.data, json!(["XOF", "EUR"]));
+
+        let requests = handle.join().unwrap();
+        assert!(!requests[0].contains("If-None-Match"));
+        assert!(requests[1].contains("If-None-Match: \"v1\"\r\n"));
+    }
+
+    #[test]
+    fn MemoryCache_test_private() {
+        let body = r#"{"success":true,"http_code":200,"data":{"name":"Boris"}}"#;
+        let response = |vary: &str| format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nETag: \"v1\"\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}", vary, body.len(), body);
+        let (url, handle) = serve(vec![
+            response(""),
+            response(""),
+            response("Vary: Accept-Language\r\n"),
+            response("Vary: Accept-Language\r\n"),
+        ]);
+
+        let client = AmiwoClient::new().cache(MemoryCache::new());
+        let me = format!("{}/me", url);
+        assert!(client.request_with_headers("GET", &me, build_headers(&[("Authorization", "Bearer alice")]), None).unwrap().success);
+        assert!(client.request_with_headers("GET", &me, build_headers(&[("Authorization", "Bearer bob")]), None).unwrap().success);
+
+        let greeting = format!("{}/greeting", url);
+        assert!(client.request_with_headers("GET", &greeting, build_headers(&[("Accept-Language", "fr")]), None).unwrap().success);
+        assert!(client.request_with_headers("GET", &greeting, build_headers(&[("Accept-Language", "en")]), None).unwrap().success);
+
+        let requests = handle.join().unwrap();
+        assert!(!requests[1].contains("If-None-Match"));
+        assert!(!requests[3].contains("If-None-Match"));
+
+        let cache = MemoryCache::new();
+        let mut vary = cached("v1");
+        vary.vary = vec![("Accept-Language".to_string(), Some("fr".to_string()))];
+        cache.put("http://a", vary);
+        assert!(super::vary_matches(&cache.get("http://a").unwrap(), &build_headers(&[("Accept-Language", "fr")])));
+        assert!(!super::vary_matches(&cache.get("http://a").unwrap(), &build_headers(&[])));
+    }
+}
@@ -0,0 +1,277 @@
+//! File holding the CircuitBreaker type, failing fast the outbound requests to an upstream service failing repeatedly
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.2
+//!
+//! ## Release notes
+//! - v1.2 : the `AmiwoClient` requests are classified from the HTTP status of the response instead of the `http_code` of its body
+//! - v1.1 : a non retryable error doesn't close a half-open circuit nor reset the consecutive failures
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{ Duration, Instant };
+
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// State of the circuit of an upstream host
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CircuitState {
+    /// The requests are sent, the consecutive failures being counted
+    Closed,
+    /// The requests fail with `GenericError::CircuitOpen` without being sent
+    Open,
+    /// A trial request is being sent, the other requests failing with `GenericError::CircuitOpen`
+    HalfOpen,
+}
+
+/// Circuit breaker of the outbound requests, keeping a circuit per upstream host (& port)
+///
+/// - after `failure_threshold` consecutive failures (transient or retryable errors, responses with a 5xx status even if their
+///   body isn't a ResponseJSON)
+///   the circuit opens: the requests fail with `GenericError::CircuitOpen` without being sent
+/// - `reset_timeout` after opening, a single trial request is sent (half-open circuit): the circuit closes if it succeeds
+///   & opens again otherwise
+/// - the other errors (e.g. `GenericError::Serde`) are neither failures nor successes
+///
+/// ```rust,ignore
+/// let client = AmiwoClient::new().circuit_breaker(CircuitBreaker::new().failure_threshold(3).reset_timeout(Duration::from_secs(10)));
+/// ```
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    reset_timeout: Duration,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+// Circuit of a host: state, consecutive failures & time of the last change to `Open` or `HalfOpen`
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    failures: u32,
+    changed_at: Instant,
+}
+
+// Outcome of a request let through by a circuit
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Outcome {
+    Success,
+    Failure,
+    // non retryable error: the upstream host may or may not be healthy
+    Inconclusive,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl CircuitBreaker {
+    /// Default circuit breaker: opening after 5 consecutive failures, sending a trial request after 30 seconds
+    pub fn new() -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(30),
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the number of consecutive failures opening the circuit
+    pub fn failure_threshold(mut self, failure_threshold: u32) -> CircuitBreaker {
+        self.failure_threshold = failure_threshold;
+        self
+    }
+
+    /// Set the time after which an open circuit lets a trial request through
+    pub fn reset_timeout(mut self, reset_timeout: Duration) -> CircuitBreaker {
+        self.reset_timeout = reset_timeout;
+        self
+    }
+
+    /// State of the circuit of `host`
+    pub fn state(&self, host: &str) -> CircuitState {
+        match self.circuits.lock() {
+            Ok(circuits) => circuits.get(host).map_or(CircuitState::Closed, |circuit| circuit.state),
+            Err(_) => CircuitState::Closed,
+        }
+    }
+
+    /// Call `send` (sending a request to `host`) if the circuit of `host` lets it through, recording its outcome,
+    /// a ResponseJSON with a 5xx `http_code` being a failure
+    pub fn call<F: FnOnce() -> Result<ResponseJSON, GenericError>>(&self, host: &str, send: F) -> Result<ResponseJSON, GenericError> {
+        self.call_with_status(host, || send().map(|rjson| (rjson.http_code, rjson))).map(|(_, rjson)| rjson)
+    }
+
+    // Same as `call()` with `send` returning the HTTP status of the response, a 5xx status being a failure
+    // whatever the body of the response
+    pub(crate) fn call_with_status<T, F: FnOnce() -> Result<(u16, T), GenericError>>(&self, host: &str, send: F) -> Result<(u16, T), GenericError> {
+        self.before(host)?;
+        let result = send();
+        let outcome = match result {
+            Ok((status, _)) if status >= 500 => Outcome::Failure,
+            Ok(_) => Outcome::Success,
+            Err(ref err) if err.is_retryable() => Outcome::Failure,
+            Err(_) => Outcome::Inconclusive,
+        };
+        self.after(host, outcome);
+        result
+    }
+
+    // Check the circuit of `host` lets a request through, switching an open circuit to half-open after `reset_timeout`
+    fn before(&self, host: &str) -> Result<(), GenericError> {
+        let mut circuits = match self.circuits.lock() {
+            Ok(circuits) => circuits,
+            Err(_) => return Ok(()),
+        };
+        let circuit = match circuits.get_mut(host) {
+            Some(circuit) => circuit,
+            None => return Ok(()),
+        };
+
+        // a half-open circuit whose trial request never completed lets another trial through
+        if circuit.state != CircuitState::Closed && circuit.changed_at.elapsed() >= self.reset_timeout {
+            circuit.state = CircuitState::HalfOpen;
+            circuit.changed_at = Instant::now();
+            return Ok(());
+        }
+        match circuit.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open | CircuitState::HalfOpen => Err(GenericError::CircuitOpen(
+                format!("::AMIWO::CONTRIB::HYPER::CIRCUIT_BREAKER::ERROR Circuit open for {} after {} consecutive failures", host, circuit.failures))),
+        }
+    }
+
+    // Record the outcome of a request to `host`
+    fn after(&self, host: &str, outcome: Outcome) {
+        let mut circuits = match self.circuits.lock() {
+            Ok(circuits) => circuits,
+            Err(_) => return,
+        };
+        match outcome {
+            Outcome::Success => {
+                circuits.remove(host);
+                return;
+            },
+            Outcome::Inconclusive => {
+                // only a successful trial request closes a half-open circuit
+                if let Some(circuit) = circuits.get_mut(host) {
+                    if circuit.state == CircuitState::HalfOpen {
+                        circuit.state = CircuitState::Open;
+                        circuit.changed_at = Instant::now();
+                    }
+                }
+                return;
+            },
+            Outcome::Failure => (),
+        }
+
+        let threshold = self.failure_threshold;
+        let circuit = circuits.entry(host.to_string()).or_insert(Circuit { state: CircuitState::Closed, failures: 0, changed_at: Instant::now() });
+        circuit.failures += 1;
+        if circuit.state == CircuitState::HalfOpen || circuit.failures >= threshold {
+            if circuit.state != CircuitState::Open {
+                warn!("::AMIWO::CONTRIB::HYPER::CIRCUIT_BREAKER::WARNING Opening the circuit of {} after {} consecutive failures", host, circuit.failures);
+            }
+            circuit.state = CircuitState::Open;
+            circuit.changed_at = Instant::now();
+        }
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Default for CircuitBreaker {
+    fn default() -> CircuitBreaker {
+        CircuitBreaker::new()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::cell::Cell;
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{ CircuitBreaker, CircuitState };
+    use contrib::hyper::AmiwoClient;
+    use contrib::hyper::tests::{ json_response, serve };
+    use error::{ ErrorKind, GenericError };
+    use types::ResponseJSON;
+
+    #[test]
+    fn CircuitBreaker_test_states() {
+        let breaker = CircuitBreaker::new().failure_threshold(2).reset_timeout(Duration::from_millis(30));
+        let calls = Cell::new(0);
+        let fail = || { calls.set(calls.get() + 1); Err(GenericError::Upstream((503, "Service Unavailable".to_string()))) };
+        let succeed = || { calls.set(calls.get() + 1); Ok(ResponseJSON::ok()) };
+
+        let not_retryable = || { calls.set(calls.get() + 1); Err(GenericError::Basic("not counted".to_string())) };
+
+        assert!(breaker.call("users.internal", &fail).is_err());
+        assert!(breaker.call("users.internal", &succeed).is_ok());
+        assert!(breaker.call("users.internal", &fail).is_err());
+        assert_eq!(breaker.state("users.internal"), CircuitState::Closed);
+        // neither a failure nor a success: the consecutive failure isn't forgotten
+        assert!(breaker.call("users.internal", &not_retryable).is_err());
+        assert_eq!(breaker.state("users.internal"), CircuitState::Closed);
+        assert!(breaker.call("users.internal", &fail).is_err());
+        assert_eq!(breaker.state("users.internal"), CircuitState::Open);
+
+        assert_eq!(breaker.call("users.internal", &succeed).unwrap_err().kind(), ErrorKind::CircuitOpen);
+        assert_eq!(calls.get(), 5);
+        assert!(breaker.call("orders.internal", || Ok(ResponseJSON::error().http_code(404))).is_ok());
+
+        thread::sleep(Duration::from_millis(40));
+        assert!(breaker.call("users.internal", &fail).is_err());
+        assert_eq!(breaker.state("users.internal"), CircuitState::Open);
+        assert_eq!(calls.get(), 6);
+
+        // a trial request failing with a non retryable error doesn't close the circuit
+        thread::sleep(Duration::from_millis(40));
+        assert!(breaker.call("users.internal", &not_retryable).is_err());
+        assert_eq!(breaker.state("users.internal"), CircuitState::Open);
+        assert_eq!(calls.get(), 7);
+
+        thread::sleep(Duration::from_millis(40));
+        assert!(breaker.call("users.internal", &succeed).is_ok());
+        assert_eq!(breaker.state("users.internal"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn CircuitBreaker_test_client() {
+        // nothing listens on the port once the listener is dropped
+        let url = format!("http://{}", TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap());
+        let client = AmiwoClient::new().circuit_breaker(CircuitBreaker::new().failure_threshold(1));
+        assert_eq!(client.request("GET", &url).unwrap_err().kind(), ErrorKind::Hyper);
+        assert_eq!(client.request("GET", &url).unwrap_err().kind(), ErrorKind::CircuitOpen);
+    }
+
+    #[test]
+    fn CircuitBreaker_test_status() {
+        let html = "<html><body>Bad Gateway</body></html>";
+        let (url, handle) = serve(vec![format!("HTTP/1.1 502 Bad Gateway\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", html.len(), html)]);
+        let client = AmiwoClient::new().circuit_breaker(CircuitBreaker::new().failure_threshold(1));
+        assert_eq!(client.request("GET", &url).unwrap_err().kind(), ErrorKind::Upstream);
+        assert_eq!(client.request("GET", &url).unwrap_err().kind(), ErrorKind::CircuitOpen);
+        handle.join().unwrap();
+
+        // a body which isn't a ResponseJSON envelope is the `data` of a successful ResponseJSON
+        let (url, handle) = serve(vec![json_response("503 Service Unavailable", r#"{"error":"maintenance"}"#)]);
+        let client = AmiwoClient::new().circuit_breaker(CircuitBreaker::new().failure_threshold(1));
+        assert_eq!(client.request("GET", &url).unwrap().data, json!({ "error": "maintenance" }));
+        assert_eq!(client.request("GET", &url).unwrap_err().kind(), ErrorKind::CircuitOpen);
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,604 @@
+//! File holding the AmiwoClient type, a reusable client keeping its connections alive between requests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.19
+//!
+//! ## Release notes
+//! - v1.19 : `download()`, `stream()`, `request_raw()`, `head()`, `options()` & `paginate()` go through the request & response interceptors
+//! - v1.18 : `download()`, `stream()`, `request_raw()`, `head()`, `options()` & `paginate()` send the cookies of the jar
+//!           & go through the circuit breaker & the retry policy
+//! - v1.17 : the circuit breaker counts the failures from the HTTP status of the responses
+//! - v1.16 : the cookies of the jar are sent & stored on each redirect followed by the redirect policy
+//! - v1.15 : `download()` & `stream()` follow the redirects according to the redirect policy
+//! - v1.14 : added `request_raw()`
+//! - v1.13 : added `head()`, `options()` & `patch_json()`
+//! - v1.12 : added the cookie jar
+//! - v1.11 : added the circuit breaker
+//! - v1.10 : added `paginate()`
+//! - v1.9 : added the response cache
+//! - v1.8 : added the request & response interceptors
+//! - v1.7 : implements `HttpBackend`
+//! - v1.6 : added `batch()`
+//! - v1.5 : added `download()` & `stream()`
+//! - v1.4 : added the redirect policy
+//! - v1.3 : added `request_as()`
+//! - v1.2 : added `retry()`
+//! - v1.1 : added the connect timeout, timeouts being reported as `GenericError::Timeout`
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::fmt;
+use std::io;
+use std::net::{ TcpStream, ToSocketAddrs };
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper;
+use hyper::client::{ Client, RedirectPolicy as HyperRedirectPolicy, Response };
+use hyper::client::pool::{ Config as PoolConfig, Pool };
+use hyper::header::Headers;
+use hyper::method::Method;
+use hyper::net::{ HttpStream, NetworkConnector };
+use hyper::Url;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use contrib::hyper::{ parse_target, send_raw, send_response_with, AllowedOptions, CircuitBreaker, CookieJar, Downloaded, PageStrategy, Pages, RawResponse, RedirectPolicy, RequestSpec, ResponseCache, ResponseHead, RetryPolicy };
+use contrib::hyper::batch::batch_with;
+use contrib::hyper::cache::send_cached;
+use contrib::hyper::decode::request_raw_with;
+use contrib::hyper::paginate::pages_with;
+use contrib::hyper::retry::{ retry_raw, retry_with };
+use contrib::hyper::download::{ download_with, stream_with };
+use contrib::hyper::head::{ head_with, options_with };
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Configuration of an `AmiwoClient`
+///
+/// ```rust,ignore
+/// let client = AmiwoClient::with_config(ClientConfig::new().max_idle(10).timeout(Duration::from_secs(5)));
+/// ```
+/// A request timing out fails with `GenericError::Timeout`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientConfig {
+    max_idle: usize,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    pub(crate) redirect: Option<RedirectPolicy>,
+}
+
+/// Client owning a pool of connections kept alive between requests, exposing the same helpers as the `contrib::hyper` functions.
+///
+/// It can be shared between the routes as Rocket managed state
+///
+/// ```rust,ignore
+/// #[get("/users")]
+/// fn users(client: State<AmiwoClient>) -> Result<ResponseJSON, GenericError> {
+///     client.request("GET", "http://users.internal/users")
+/// }
+///
+/// rocket::ignite()
+///     .manage(AmiwoClient::new())
+///     .mount("/", routes![users])
+/// ```
+///
+/// Interceptors can be registered to handle all the requests answered with a `ResponseJSON` uniformly
+/// (e.g. adding tracing headers or logging the responses)
+///
+/// ```rust,ignore
+/// fn add_request_id(spec: &mut RequestSpec) {
+///     spec.headers.set_raw("X-Request-Id", vec![new_request_id().into_bytes()]);
+/// }
+///
+/// fn log_response(rjson: &ResponseJSON) {
+///     info!("upstream answered {}", rjson.http_code);
+/// }
+///
+/// let client = AmiwoClient::new().intercept_request(add_request_id).intercept_response(log_response);
+/// ```
+#[derive(Clone)]
+pub struct AmiwoClient {
+    client: Arc<Client>,
+    redirect: Option<RedirectPolicy>,
+    retry: Option<RetryPolicy>,
+    request_interceptors: Vec<fn(&mut RequestSpec)>,
+    response_interceptors: Vec<fn(&ResponseJSON)>,
+    cache: Option<Arc<ResponseCache>>,
+    breaker: Option<Arc<CircuitBreaker>>,
+    cookies: Option<Arc<CookieJar>>,
+}
+
+// Connector of plain HTTP streams, the connection to the host timing out after `timeout` (if any)
+struct TimeoutConnector {
+    timeout: Option<Duration>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl ClientConfig {
+    /// Default configuration: 5 idle connections per host & no timeouts
+    pub fn new() -> ClientConfig {
+        ClientConfig {
+            max_idle: PoolConfig::default().max_idle,
+            connect_timeout: None,
+            read_timeout: None,
+            write_timeout: None,
+            redirect: None,
+        }
+    }
+
+    /// Set the maximum number of idle connections kept per host
+    pub fn max_idle(mut self, max_idle: usize) -> ClientConfig {
+        self.max_idle = max_idle;
+        self
+    }
+
+    /// Set the timeout of the connection to the host
+    pub fn connect_timeout(mut self, timeout: Duration) -> ClientConfig {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the read timeout of the requests
+    pub fn read_timeout(mut self, timeout: Duration) -> ClientConfig {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the write timeout of the requests
+    pub fn write_timeout(mut self, timeout: Duration) -> ClientConfig {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Follow the redirects according to `policy` (see `RedirectPolicy`) instead of letting hyper follow them all
+    pub fn redirect(mut self, policy: RedirectPolicy) -> ClientConfig {
+        self.redirect = Some(policy);
+        self
+    }
+
+    /// Set the connect, read & write timeouts of the requests
+    pub fn timeout(self, timeout: Duration) -> ClientConfig {
+        self.connect_timeout(timeout).read_timeout(timeout).write_timeout(timeout)
+    }
+}
+
+impl AmiwoClient {
+    /// Create a client with the default configuration
+    pub fn new() -> AmiwoClient {
+        AmiwoClient::with_config(ClientConfig::new())
+    }
+
+    /// Create a client with the given configuration
+    pub fn with_config(config: ClientConfig) -> AmiwoClient {
+        AmiwoClient {
+            client: Arc::new(build_client(&config)),
+            redirect: config.redirect,
+            retry: None,
+            request_interceptors: Vec::new(),
+            response_interceptors: Vec::new(),
+            cache: None,
+            breaker: None,
+            cookies: None,
+        }
+    }
+
+    /// Retry the idempotent requests according to `policy` (see `RetryPolicy`)
+    pub fn retry(mut self, policy: RetryPolicy) -> AmiwoClient {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Cache the responses of the `GET` requests without body in `cache` (see `ResponseCache`)
+    pub fn cache<C: ResponseCache + 'static>(mut self, cache: C) -> AmiwoClient {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Fail fast the requests to the hosts failing repeatedly according to `breaker` (see `CircuitBreaker`)
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> AmiwoClient {
+        self.breaker = Some(Arc::new(breaker));
+        self
+    }
+
+    /// Store the cookies set by the upstream services in `jar` & send them back to the same hosts (see `CookieJar`)
+    pub fn cookie_jar(mut self, jar: CookieJar) -> AmiwoClient {
+        self.cookies = Some(Arc::new(jar));
+        self
+    }
+
+    /// Cookie jar of the client, if any
+    pub fn cookies(&self) -> Option<&CookieJar> {
+        self.cookies.as_ref().map(|jar| &**jar)
+    }
+
+    /// Call `interceptor` on each request before sending it, in the order of registration
+    pub fn intercept_request(mut self, interceptor: fn(&mut RequestSpec)) -> AmiwoClient {
+        self.request_interceptors.push(interceptor);
+        self
+    }
+
+    /// Call `interceptor` on each `ResponseJSON` received (after the retries & redirects), in the order of registration.
+    ///
+    /// The responses without JSON body (`head()`, `options()`, `request_raw()`, `download()` & `stream()`) are passed as a ResponseJSON holding only their status
+    pub fn intercept_response(mut self, interceptor: fn(&ResponseJSON)) -> AmiwoClient {
+        self.response_interceptors.push(interceptor);
+        self
+    }
+
+    /// Send a simple `method` request to `url` (see `contrib::hyper::request()`)
+    pub fn request(&self, method: &str, url: &str) -> Result<ResponseJSON, GenericError> {
+        self.request_with_headers(method, url, Headers::new(), None)
+    }
+
+    /// Send a simple `method` request to `url` and deserialize the response into `T` (see `contrib::hyper::request_as()`)
+    pub fn request_as<T: DeserializeOwned>(&self, method: &str, url: &str) -> Result<T, GenericError> {
+        self.request(method, url).and_then(ResponseJSON::into_typed)
+    }
+
+    /// Send a `method` request with `body` to `url` (see `contrib::hyper::request_with_body()`)
+    pub fn request_with_body(&self, method: &str, url: &str, body: &str) -> Result<ResponseJSON, GenericError> {
+        self.request_with_headers(method, url, Headers::new(), Some(body))
+    }
+
+    /// Send a `method` request with `headers` (and `body` if any) to `url` (see `contrib::hyper::request_with_headers()`)
+    pub fn request_with_headers(&self, method: &str, url: &str, headers: Headers, body: Option<&str>) -> Result<ResponseJSON, GenericError> {
+        self.send_spec(RequestSpec {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: headers,
+            body: body.map(|body| body.as_bytes().to_vec()),
+        })
+    }
+
+    /// Send a `POST` request to `url` with `body` serialized as JSON (see `contrib::hyper::post_json()`)
+    pub fn post_json<T: Serialize>(&self, url: &str, body: &T) -> Result<ResponseJSON, GenericError> {
+        self.post_json_with_headers(url, body, Headers::new())
+    }
+
+    /// Same as `post_json()` with additional `headers` (see `contrib::hyper::post_json_with_headers()`)
+    pub fn post_json_with_headers<T: Serialize>(&self, url: &str, body: &T, headers: Headers) -> Result<ResponseJSON, GenericError> {
+        let spec = RequestSpec { method: "POST".to_string(), url: url.to_string(), headers: headers, body: None };
+        self.send_spec(spec.body_json(body)?)
+    }
+
+    /// Send a `PATCH` request to `url` with `body` serialized as JSON (see `contrib::hyper::patch_json()`)
+    pub fn patch_json<T: Serialize>(&self, url: &str, body: &T) -> Result<ResponseJSON, GenericError> {
+        let spec = RequestSpec::new("PATCH", url);
+        self.send_spec(spec.body_json(body)?)
+    }
+
+    /// Send a simple `method` request to `url` and return the response with its raw (decoded) body (see `contrib::hyper::request_raw()`)
+    pub fn request_raw(&self, method: &str, url: &str) -> Result<RawResponse, GenericError> {
+        request_raw_with(self, method, url)
+    }
+
+    /// Send a `HEAD` request to `url` (see `contrib::hyper::head()`)
+    pub fn head(&self, url: &str) -> Result<ResponseHead, GenericError> {
+        head_with(self, url)
+    }
+
+    /// Send an `OPTIONS` request to `url` (see `contrib::hyper::options()`)
+    pub fn options(&self, url: &str) -> Result<AllowedOptions, GenericError> {
+        options_with(self, url)
+    }
+
+    /// Send `requests` in parallel, at most `concurrency` of them at a time (see `contrib::hyper::batch()`)
+    pub fn batch(&self, requests: Vec<RequestSpec>, concurrency: usize) -> Vec<Result<ResponseJSON, GenericError>> {
+        let client = self.clone();
+        batch_with(move |spec| client.send_spec(spec), requests, concurrency)
+    }
+
+    /// Iterate over the pages of the upstream API at `url` (see `contrib::hyper::paginate()`)
+    pub fn paginate(&self, url: &str, strategy: PageStrategy) -> Pages {
+        pages_with(self.clone(), url, strategy)
+    }
+
+    /// Stream the body of a `GET` request to `url` into the file at `path` (see `contrib::hyper::download()`)
+    pub fn download<P: AsRef<Path>, F: FnMut(u64, Option<u64>)>(&self, url: &str, path: P, progress: F) -> Result<Downloaded, GenericError> {
+        download_with(self, url, path.as_ref(), progress)
+    }
+
+    /// Send a `GET` request to `url` and return the response, to be read as a stream of bytes (see `contrib::hyper::stream()`)
+    pub fn stream(&self, url: &str) -> Result<Response, GenericError> {
+        stream_with(self, url)
+    }
+
+    /// Send the request described by `spec` through the interceptors & the circuit breaker (see `HttpBackend`)
+    pub(crate) fn send_spec(&self, mut spec: RequestSpec) -> Result<ResponseJSON, GenericError> {
+        for interceptor in &self.request_interceptors {
+            interceptor(&mut spec);
+        }
+        let rjson = match self.breaker {
+            Some(ref breaker) => breaker.call_with_status(&circuit_host(&spec.url), || self.dispatch(spec))?.1,
+            None => self.dispatch(spec)?.1,
+        };
+        self.intercept(&rjson);
+        Ok(rjson)
+    }
+
+    /// Call the response interceptors on `rjson`
+    pub(crate) fn intercept(&self, rjson: &ResponseJSON) {
+        for interceptor in &self.response_interceptors {
+            interceptor(rjson);
+        }
+    }
+
+    /// Call the response interceptors on a response without JSON body, passed as a ResponseJSON holding only its `status`
+    pub(crate) fn intercept_status(&self, status: u16) {
+        if !self.response_interceptors.is_empty() {
+            let rjson = if status < 400 {
+                ResponseJSON::ok().http_code(status)
+            } else {
+                ResponseJSON::error_with_message(status, format!("Upstream service answered with status {}", status))
+            };
+            self.intercept(&rjson);
+        }
+    }
+
+    // Send the request described by `spec` with the cookies of the jar, through the response cache for the `GET` requests without body,
+    // returns the HTTP status of the response with its ResponseJSON
+    fn dispatch(&self, spec: RequestSpec) -> Result<(u16, ResponseJSON), GenericError> {
+        let cookies = self.cookies();
+        let (method, url) = parse_target(&spec.method, &spec.url)?;
+        match self.cache {
+            Some(ref cache) if spec.body.is_none() && method == Method::Get => {
+                send_cached(&self.client, &**cache, self.retry.as_ref(), self.redirect.as_ref(), url, spec.headers, cookies)
+            },
+            _ => retry_with(self.retry.as_ref(), &method, &url, || {
+                let body = spec.body.as_ref().map(|body| body.as_slice());
+                send_response_with(&self.client, self.redirect.as_ref(), method.clone(), url.clone(), spec.headers.clone(), body, cookies)
+            }),
+        }
+    }
+
+    // Send the request described by `spec` through the request interceptors, with the cookies of the jar, through the circuit breaker
+    // & the retry policy, returns the response (& its URL if it was redirected by the redirect policy) without reading its body.
+    // The caller calls the response interceptors (see `intercept()` & `intercept_status()`)
+    pub(crate) fn send_raw_spec(&self, mut spec: RequestSpec) -> Result<(Response, Option<Url>), GenericError> {
+        for interceptor in &self.request_interceptors {
+            interceptor(&mut spec);
+        }
+        let (method, url) = parse_target(&spec.method, &spec.url)?;
+        let send = || retry_raw(self.retry.as_ref(), &method, &url, || {
+            let body = spec.body.as_ref().map(|body| body.as_slice());
+            send_raw(&self.client, self.redirect.as_ref(), method.clone(), url.clone(), spec.headers.clone(), body, self.cookies())
+        });
+        match self.breaker {
+            Some(ref breaker) => breaker.call_with_status(&circuit_host(&spec.url), || send().map(|sent| (sent.0.status.to_u16(), sent)))
+                .map(|(_, sent)| sent),
+            None => send(),
+        }
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Key of the circuit of the host (& port) of `url`
+fn circuit_host(url: &str) -> String {
+    Url::parse(url).ok()
+        .and_then(|url| url.host_str().map(|host| format!("{}:{}", host, url.port_or_known_default().unwrap_or(0))))
+        .unwrap_or(url.to_string())
+}
+
+// Create a `hyper::Client` configured by `config`
+pub(crate) fn build_client(config: &ClientConfig) -> Client {
+    let connector = TimeoutConnector { timeout: config.connect_timeout };
+    let mut client = Client::with_connector(Pool::with_connector(PoolConfig { max_idle: config.max_idle }, connector));
+    client.set_read_timeout(config.read_timeout);
+    client.set_write_timeout(config.write_timeout);
+    if config.redirect.is_some() {
+        client.set_redirect_policy(HyperRedirectPolicy::FollowNone);
+    }
+    client
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Default for ClientConfig {
+    fn default() -> ClientConfig {
+        ClientConfig::new()
+    }
+}
+
+impl NetworkConnector for TimeoutConnector {
+    type Stream = HttpStream;
+
+    fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<HttpStream> {
+        if scheme != "http" {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid scheme for Http").into());
+        }
+
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return Ok(HttpStream(TcpStream::connect((host, port))?)),
+        };
+        let mut last_error = io::Error::new(io::ErrorKind::InvalidInput, format!("Unable to resolve {}", host));
+        for address in (host, port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(stream) => return Ok(HttpStream(stream)),
+                Err(err) => last_error = err,
+            }
+        }
+        Err(last_error.into())
+    }
+}
+
+impl fmt::Debug for AmiwoClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AmiwoClient")
+            .field("client", &self.client)
+            .field("redirect", &self.redirect)
+            .field("retry", &self.retry)
+            .field("request_interceptors", &self.request_interceptors.len())
+            .field("response_interceptors", &self.response_interceptors.len())
+            .field("cache", &self.cache.is_some())
+            .field("breaker", &self.breaker)
+            .field("cookies", &self.cookies)
+            .finish()
+    }
+}
+
+impl Default for AmiwoClient {
+    fn default() -> AmiwoClient {
+        AmiwoClient::new()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::net::TcpListener;
+    use std::sync::atomic::{ AtomicUsize, Ordering, ATOMIC_USIZE_INIT };
+    use std::time::Duration;
+
+    use super::{ AmiwoClient, ClientConfig };
+    use error::ErrorKind;
+    use contrib::hyper::{ build_headers, CircuitBreaker, CookieJar, PageStrategy, RequestSpec, RetryPolicy };
+    use types::ResponseJSON;
+    use contrib::hyper::tests::{ json_response, serve };
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn AmiwoClient_test_requests() {
+        assert_send_sync::<AmiwoClient>();
+
+        let (url, handle) = serve(vec![
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1]}"#),
+            json_response("201 Created", r#"{"success":true,"http_code":201}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200}"#),
+        ]);
+
+        let client = AmiwoClient::with_config(ClientConfig::new().max_idle(2).timeout(Duration::from_secs(5)));
+        assert_eq!(client.request("GET", &format!("{}/users", url)).unwrap().data, json!([1]));
+        assert_eq!(client.post_json(&format!("{}/users", url), &json!({ "name": "Boris" })).unwrap().http_code, 201);
+        let headers = build_headers(&[("X-Api-Key", "secret")]);
+        assert!(client.request_with_headers("DELETE", &format!("{}/users/17", url), headers, None).unwrap().success);
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("GET /users HTTP/1.1\r\n"));
+        assert!(requests[1].starts_with("POST /users HTTP/1.1\r\n"));
+        assert!(requests[1].ends_with(r#"{"name":"Boris"}"#));
+        assert!(requests[2].starts_with("DELETE /users/17 HTTP/1.1\r\n"));
+        assert!(requests[2].contains("X-Api-Key: secret\r\n"));
+    }
+
+    #[test]
+    fn AmiwoClient_test_timeout() {
+        let second = Duration::from_secs(1);
+        assert_eq!(ClientConfig::new().timeout(second),
+            ClientConfig::default().connect_timeout(second).read_timeout(second).write_timeout(second));
+
+        // the listener accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = AmiwoClient::with_config(ClientConfig::new().read_timeout(Duration::from_millis(50)));
+        let err = client.request("GET", &format!("http://{}", listener.local_addr().unwrap())).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Timeout);
+
+        let (url, handle) = serve(vec![json_response("200 OK", r#"{"success":true,"http_code":200}"#)]);
+        let client = AmiwoClient::with_config(ClientConfig::new().connect_timeout(Duration::from_secs(1)));
+        assert!(client.request("GET", &url).unwrap().success);
+        assert!(client.request("GET", "https://localhost").is_err());
+        handle.join().unwrap();
+    }
+
+    static RESPONSES: AtomicUsize = ATOMIC_USIZE_INIT;
+
+    fn add_request_id(spec: &mut RequestSpec) {
+        spec.headers.set_raw("X-Request-Id", vec![b"17".to_vec()]);
+    }
+
+    fn add_api_key(spec: &mut RequestSpec) {
+        spec.url.push_str("?api_key=secret");
+    }
+
+    fn count_response(rjson: &ResponseJSON) {
+        RESPONSES.fetch_add(rjson.http_code as usize, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn AmiwoClient_test_interceptors() {
+        let (url, handle) = serve(vec![
+            json_response("200 OK", r#"{"success":true,"http_code":200}"#),
+            json_response("201 Created", r#"{"success":true,"http_code":201}"#),
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: 7\r\nConnection: close\r\n\r\nmissing".to_string(),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1]}"#),
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 8\r\nConnection: close\r\n\r\nstreamed".to_string(),
+        ]);
+
+        let client = AmiwoClient::new().intercept_request(add_request_id).intercept_request(add_api_key).intercept_response(count_response);
+        assert!(client.request("GET", &format!("{}/users", url)).unwrap().success);
+        assert!(client.post_json(&format!("{}/users", url), &json!({ "name": "Boris" })).unwrap().success);
+        assert!(client.request("GET", "not an url").is_err());
+        assert_eq!(RESPONSES.load(Ordering::SeqCst), 401);
+
+        // the responses without JSON body are intercepted with their status
+        assert_eq!(client.head(&format!("{}/reports/17.pdf", url)).unwrap().status, 200);
+        assert_eq!(client.request_raw("GET", &format!("{}/reports/18.pdf", url)).unwrap().status, 404);
+        assert_eq!(client.paginate(&format!("{}/users", url), PageStrategy::NextUrl).count(), 1);
+        assert!(client.stream(&format!("{}/exports/users.csv", url)).is_ok());
+        assert_eq!(RESPONSES.load(Ordering::SeqCst), 1405);
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("GET /users?api_key=secret HTTP/1.1\r\n"));
+        assert!(requests[0].contains("X-Request-Id: 17\r\n"));
+        assert!(requests[1].starts_with("POST /users?api_key=secret HTTP/1.1\r\n"));
+        assert!(requests[1].contains("Content-Type: application/json\r\n"));
+        assert!(requests[2].starts_with("HEAD /reports/17.pdf?api_key=secret HTTP/1.1\r\n"));
+        assert!(requests[3].starts_with("GET /reports/18.pdf?api_key=secret HTTP/1.1\r\n"));
+        assert!(requests[4].starts_with("GET /users?api_key=secret HTTP/1.1\r\n"));
+        assert!(requests[5].starts_with("GET /exports/users.csv?api_key=secret HTTP/1.1\r\n"));
+        assert!(requests[2..].iter().all(|request| request.contains("X-Request-Id: 17\r\n")));
+    }
+
+    #[test]
+    fn AmiwoClient_test_raw_requests() {
+        let (url, handle) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nSet-Cookie: session=abc; Path=/\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_string(),
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        ]);
+        let client = AmiwoClient::new().cookie_jar(CookieJar::new()).circuit_breaker(CircuitBreaker::new().failure_threshold(1));
+        assert_eq!(client.request_raw("GET", &format!("{}/login", url)).unwrap().text(), "ok");
+        assert_eq!(client.head(&format!("{}/reports/17.pdf", url)).unwrap().status, 200);
+        assert_eq!(client.stream(&format!("{}/exports/users.csv", url)).unwrap_err().kind(), ErrorKind::Upstream);
+        assert_eq!(client.options(&url).unwrap_err().kind(), ErrorKind::CircuitOpen);
+
+        let requests = handle.join().unwrap();
+        assert!(!requests[0].contains("Cookie:"));
+        assert!(requests[1].starts_with("HEAD /reports/17.pdf HTTP/1.1\r\n"));
+        assert!(requests[1].contains("Cookie: session=abc\r\n"));
+        assert!(requests[2].contains("Cookie: session=abc\r\n"));
+
+        let (url, handle) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_string(),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1]}"#),
+        ]);
+        let jar = CookieJar::new();
+        jar.set("127.0.0.1", "session", "abc");
+        let client = AmiwoClient::new().retry(RetryPolicy::new().backoff(Duration::from_millis(1))).cookie_jar(jar);
+        assert_eq!(client.request_raw("GET", &url).unwrap().text(), "ok");
+        let pages : Vec<_> = client.paginate(&url, PageStrategy::NextUrl).collect();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].as_ref().unwrap().data, json!([1]));
+        assert!(handle.join().unwrap()[2].contains("Cookie: session=abc\r\n"));
+    }
+}
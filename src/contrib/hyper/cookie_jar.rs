@@ -0,0 +1,220 @@
+//! File holding the CookieJar type, storing the cookies set by the upstream services to send them back
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.1
+//!
+//! ## Release notes
+//! - v1.1 : the cookies are sent & stored on each redirect followed by the redirect policy of the client
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use hyper::header::Headers;
+use hyper::Url;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Store of the cookies of an `AmiwoClient`, keyed by host
+///
+/// The cookies set by the `Set-Cookie` headers of the responses are sent back in the `Cookie` header of the following
+/// requests to the same host, a cookie set with `Max-Age=0` (or an empty value) being removed.
+/// With a RedirectPolicy, the cookies are sent & stored on each redirect (e.g. the session cookie set by the `302` of a login).
+/// The other attributes (`Domain`, `Path`, `Expires`, `Secure`...) are ignored
+///
+/// ```rust,ignore
+/// let client = AmiwoClient::new().cookie_jar(CookieJar::new());
+/// client.post_json("http://legacy.partner.com/login", &json!({ "user": "amiwo", "password": "secret" }))?;
+/// let orders = client.request("GET", "http://legacy.partner.com/orders")?; // sent with the session cookie
+/// ```
+#[derive(Debug)]
+pub struct CookieJar {
+    cookies: Mutex<HashMap<String, Vec<(String, String)>>>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl CookieJar {
+    /// Create an empty jar
+    pub fn new() -> CookieJar {
+        CookieJar {
+            cookies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cookies stored for `host`, as `(name, value)` pairs in the order they were set
+    pub fn cookies(&self, host: &str) -> Vec<(String, String)> {
+        match self.cookies.lock() {
+            Ok(cookies) => cookies.get(host).cloned().unwrap_or(Vec::new()),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Store the cookie `name` with `value` for `host`, replacing the previous value if any
+    pub fn set(&self, host: &str, name: &str, value: &str) {
+        if let Ok(mut cookies) = self.cookies.lock() {
+            let cookies = cookies.entry(host.to_string()).or_insert(Vec::new());
+            match cookies.iter().position(|&(ref cookie, _)| cookie == name) {
+                Some(index) => cookies[index].1 = value.to_string(),
+                None => cookies.push((name.to_string(), value.to_string())),
+            }
+        }
+    }
+
+    /// Remove the cookie `name` of `host`
+    pub fn remove(&self, host: &str, name: &str) {
+        if let Ok(mut cookies) = self.cookies.lock() {
+            if let Some(cookies) = cookies.get_mut(host) {
+                cookies.retain(|&(ref cookie, _)| cookie != name);
+            }
+        }
+    }
+
+    /// Remove all the cookies
+    pub fn clear(&self) {
+        if let Ok(mut cookies) = self.cookies.lock() {
+            cookies.clear();
+        }
+    }
+
+    // Add the cookies of the host of `url` to the `Cookie` header of `headers`
+    pub(crate) fn add_to(&self, url: &str, headers: &mut Headers) {
+        let host = match Url::parse(url) {
+            Ok(url) => url.host_str().unwrap_or("").to_string(),
+            Err(_) => return,
+        };
+        let cookies = self.cookies(&host);
+        if cookies.is_empty() {
+            return;
+        }
+
+        let mut pairs : Vec<String> = headers.get_raw("Cookie")
+            .map(|values| values.iter().map(|value| String::from_utf8_lossy(value).into_owned()).collect())
+            .unwrap_or(Vec::new());
+        pairs.extend(cookies.into_iter().map(|(name, value)| format!("{}={}", name, value)));
+        headers.set_raw("Cookie", vec![pairs.join("; ").into_bytes()]);
+    }
+
+    // Store the cookies set by the `Set-Cookie` headers of the response of `url`
+    pub(crate) fn store(&self, url: &Url, headers: &Headers) {
+        let host = url.host_str().unwrap_or("");
+        let values = match headers.get_raw("Set-Cookie") {
+            Some(values) => values,
+            None => return,
+        };
+
+        for value in values {
+            let value = String::from_utf8_lossy(value);
+            let mut attributes = value.split(';');
+            let mut pair = attributes.next().unwrap_or("").splitn(2, '=');
+            let name = pair.next().unwrap_or("").trim();
+            let cookie = pair.next().unwrap_or("").trim().trim_matches('"');
+            if name.is_empty() {
+                continue;
+            }
+
+            let expired = attributes.any(|attribute| {
+                let mut pair = attribute.splitn(2, '=');
+                pair.next().map(|key| key.trim().eq_ignore_ascii_case("max-age")).unwrap_or(false)
+                    && pair.next().and_then(|max_age| max_age.trim().parse::<i64>().ok()).map_or(false, |max_age| max_age <= 0)
+            });
+            if expired || cookie.is_empty() {
+                self.remove(host, name);
+            } else {
+                self.set(host, name, cookie);
+            }
+        }
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Default for CookieJar {
+    fn default() -> CookieJar {
+        CookieJar::new()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use hyper::header::Headers;
+    use hyper::Url;
+
+    use super::CookieJar;
+    use contrib::hyper::{ AmiwoClient, ClientConfig, RedirectPolicy };
+    use contrib::hyper::tests::{ json_response, serve };
+
+    #[test]
+    fn CookieJar_test_store() {
+        let jar = CookieJar::new();
+        let url = Url::parse("http://partner.com/login").unwrap();
+        let mut headers = Headers::new();
+        headers.append_raw("Set-Cookie", b"session=abc; Path=/; HttpOnly".to_vec());
+        headers.append_raw("Set-Cookie", b"lang=\"fr\"".to_vec());
+        jar.store(&url, &headers);
+        assert_eq!(jar.cookies("partner.com"), vec![("session".to_string(), "abc".to_string()), ("lang".to_string(), "fr".to_string())]);
+        assert!(jar.cookies("other.com").is_empty());
+
+        let mut headers = Headers::new();
+        headers.set_raw("Set-Cookie", vec![b"session=def".to_vec(), b"lang=; Max-Age=0".to_vec()]);
+        jar.store(&url, &headers);
+        assert_eq!(jar.cookies("partner.com"), vec![("session".to_string(), "def".to_string())]);
+
+        let mut headers = Headers::new();
+        headers.set_raw("Cookie", vec![b"debug=1".to_vec()]);
+        jar.add_to("http://partner.com/orders", &mut headers);
+        assert_eq!(headers.get_raw("Cookie").unwrap(), &[b"debug=1; session=def".to_vec()][..]);
+
+        jar.clear();
+        assert!(jar.cookies("partner.com").is_empty());
+    }
+
+    #[test]
+    fn CookieJar_test_client() {
+        let body = r#"{"success":true,"http_code":200}"#;
+        let (url, handle) = serve(vec![
+            format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nSet-Cookie: session=abc; Path=/\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body),
+            json_response("200 OK", body),
+        ]);
+
+        let client = AmiwoClient::new().cookie_jar(CookieJar::new());
+        assert!(client.post_json(&format!("{}/login", url), &json!({ "user": "amiwo" })).unwrap().success);
+        assert!(client.request("GET", &format!("{}/orders", url)).unwrap().success);
+        assert_eq!(client.cookies().unwrap().cookies("127.0.0.1"), vec![("session".to_string(), "abc".to_string())]);
+
+        let requests = handle.join().unwrap();
+        assert!(!requests[0].contains("Cookie:"));
+        assert!(requests[1].contains("Cookie: session=abc\r\n"));
+    }
+
+    #[test]
+    fn CookieJar_test_redirect() {
+        let body = r#"{"success":true,"http_code":200}"#;
+        let (url, handle) = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /home\r\nSet-Cookie: session=abc; Path=/\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            json_response("200 OK", body),
+        ]);
+
+        let client = AmiwoClient::with_config(ClientConfig::new().redirect(RedirectPolicy::new())).cookie_jar(CookieJar::new());
+        let rjson = client.post_json(&format!("{}/login", url), &json!({ "user": "amiwo" })).unwrap();
+        assert!(rjson.success);
+        assert_eq!(rjson.resource, Some(format!("{}/home", url)));
+        assert_eq!(client.cookies().unwrap().cookies("127.0.0.1"), vec![("session".to_string(), "abc".to_string())]);
+
+        let requests = handle.join().unwrap();
+        assert!(!requests[0].contains("Cookie:"));
+        assert!(requests[1].starts_with("GET /home"));
+        assert!(requests[1].contains("Cookie: session=abc\r\n"));
+    }
+}
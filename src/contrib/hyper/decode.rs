@@ -0,0 +1,276 @@
+//! File holding the decoding of compressed (gzip / deflate) responses & the RawResponse type
+//!
+//! The requests advertise `Accept-Encoding: gzip, deflate` and the responses are decoded according to their
+//! `Content-Encoding` header. A response with another encoding can't be read as a `ResponseJSON` but its bytes
+//! can still be fetched with `request_raw()`. A compressed body decoding to more than `MAX_DECODED_SIZE` bytes fails
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.5
+//!
+//! ## Release notes
+//! - v1.5 : `request_raw()` calls the interceptors of an `AmiwoClient`
+//! - v1.4 : the requests of an `AmiwoClient` send the cookies of its jar & go through its circuit breaker & retry policy
+//! - v1.3 : a response with a 5xx status & a body which isn't a ResponseJSON fails with `GenericError::Upstream`
+//! - v1.2 : the decoded bodies are limited to `MAX_DECODED_SIZE` bytes
+//! - v1.1 : decoding doesn't require the `amiwo_gzip` feature anymore
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::cmp;
+use std::io::{ self, Read };
+use std::time::Instant;
+
+use flate2::read::{ GzDecoder, ZlibDecoder };
+use hyper::client::Response;
+use hyper::header::Headers;
+
+use contrib::hyper::{ parse_target, AmiwoClient, RequestSpec };
+use contrib::hyper::metrics::{ self, CountingReader };
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// CONSTANTS
+// =======================================================================
+/// Maximum number of bytes a compressed (gzip / deflate) body may decode to
+pub const MAX_DECODED_SIZE: u64 = 64 * 1024 * 1024;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Status, headers & body of a response, returned by `request_raw()` for the bodies that aren't ResponseJSON
+#[derive(Clone, Debug)]
+pub struct RawResponse {
+    pub status: u16,
+    /// Headers of the response, without `Content-Encoding` once the body has been decoded
+    pub headers: Headers,
+    /// Decoded body of the response, or the bytes received if its `Content-Encoding` isn't supported
+    pub body: Vec<u8>,
+}
+
+// Reader failing once more than `remaining` bytes have been read from `inner`
+struct BoundedReader<R> {
+    inner: R,
+    remaining: u64,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl RawResponse {
+    /// Value of the `Content-Encoding` header, i.e. the encoding of `body` if it couldn't be decoded
+    pub fn content_encoding(&self) -> Option<String> {
+        self.headers.get_raw("Content-Encoding")
+            .and_then(|values| values.first())
+            .map(|value| String::from_utf8_lossy(value).into_owned())
+    }
+
+    /// The (lossy) UTF-8 text of the body
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Try to build a `ResponseJSON` from the body
+    pub fn json(&self) -> Result<ResponseJSON, GenericError> {
+        ResponseJSON::from_reader(&self.body[..])
+    }
+}
+
+impl<R: Read> Read for BoundedReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        // read at most one byte past the limit to detect that it's exceeded
+        let max = cmp::min(buffer.len() as u64, self.remaining + 1) as usize;
+        let count = self.inner.read(&mut buffer[..max])?;
+        if count as u64 > self.remaining {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("::AMIWO::CONTRIB::HYPER::DECODE::ERROR Decoded body larger than {} bytes", MAX_DECODED_SIZE)));
+        }
+        self.remaining -= count as u64;
+        Ok(count)
+    }
+}
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Send a simple `method` request to `url` and return the response with its body as bytes, decoded if it's compressed,
+/// e.g. to read a body that isn't a ResponseJSON
+///
+/// ```rust,ignore
+/// let response = amiwo::contrib::hyper::request_raw("GET", "http://partner.com/export.csv")?;
+/// println!("{} => {}", response.status, response.text());
+/// ```
+pub fn request_raw(method: &str, url: &str) -> Result<RawResponse, GenericError> {
+    request_raw_with(&AmiwoClient::new(), method, url)
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// `request_raw()` with `client`
+pub(crate) fn request_raw_with(client: &AmiwoClient, method: &str, url: &str) -> Result<RawResponse, GenericError> {
+    let spec = RequestSpec::new(method, url);
+    let (method, url) = parse_target(method, url)?;
+    let started = Instant::now();
+    let (response, _) = match client.send_raw_spec(spec) {
+        Ok(sent) => sent,
+        Err(err) => {
+            metrics::record(&method, &url, None, 0, 0, started);
+            return Err(err);
+        },
+    };
+    let status = response.status.to_u16();
+    client.intercept_status(status);
+    let mut headers = response.headers.clone();
+
+    // an unsupported encoding is returned as is, for the caller to decode it
+    let decodable = encodings(&headers).iter().all(|encoding| is_supported(encoding));
+    let mut reader = CountingReader::new(response);
+    let mut body = Vec::new();
+    let result = if decodable {
+        decoder(&headers, &mut reader).and_then(|mut decoded| decoded.read_to_end(&mut body).map_err(GenericError::from))
+    } else {
+        reader.read_to_end(&mut body).map_err(GenericError::from)
+    };
+    metrics::record(&method, &url, Some(status), 0, reader.count(), started);
+    result?;
+    if decodable {
+        headers.remove_raw("Content-Encoding");
+    }
+    Ok(RawResponse { status: status, headers: headers, body: body })
+}
+
+// Read the (decoded) body of `response` as a ResponseJSON, returns it with the number of bytes received.
+// A body which can't be read fails with `GenericError::Upstream` if the status of the response is 5xx
+pub(crate) fn read_json(response: Response) -> (Result<ResponseJSON, GenericError>, u64) {
+    let status = response.status.to_u16();
+    let headers = response.headers.clone();
+    let mut reader = CountingReader::new(response);
+    let result = decoder(&headers, &mut reader).and_then(ResponseJSON::from_reader).map_err(|err| match status {
+        500 ... 599 => GenericError::Upstream((status, format!("::AMIWO::CONTRIB::HYPER::DECODE::ERROR Upstream service answered with status {} & an invalid body: {}", status, err))),
+        _ => err,
+    });
+    (result, reader.count())
+}
+
+// Advertise the encodings that can be decoded, unless `headers` already has an `Accept-Encoding` header
+pub(crate) fn accept_encoding(headers: &mut Headers) {
+    if headers.get_raw("Accept-Encoding").is_none() {
+        headers.set_raw("Accept-Encoding", vec![b"gzip, deflate".to_vec()]);
+    }
+}
+
+// Wrap `reader` to decode the body of a response according to its `Content-Encoding` header (`headers`),
+// fails if the encoding isn't supported. The decoded body fails to be read past `MAX_DECODED_SIZE` bytes
+pub(crate) fn decoder<'a, R: Read + 'a>(headers: &Headers, reader: R) -> Result<Box<Read + 'a>, GenericError> {
+    let encodings = encodings(headers);
+    if encodings.is_empty() {
+        return Ok(Box::new(reader));
+    }
+
+    // the encodings are listed in the order they were applied
+    let mut decoded : Box<Read + 'a> = Box::new(reader);
+    for encoding in encodings.iter().rev() {
+        decoded = decode(encoding, decoded)?;
+    }
+    Ok(Box::new(BoundedReader { inner: decoded, remaining: MAX_DECODED_SIZE }))
+}
+
+// Encodings (lowercase, `identity` excluded) listed by the `Content-Encoding` header of `headers`
+fn encodings(headers: &Headers) -> Vec<String> {
+    headers.get_raw("Content-Encoding")
+        .map(|values| values.iter()
+            .flat_map(|value| String::from_utf8_lossy(value).split(',').map(|item| item.trim().to_lowercase()).collect::<Vec<_>>())
+            .filter(|item| !item.is_empty() && item != "identity")
+            .collect())
+        .unwrap_or(Vec::new())
+}
+
+fn is_supported(encoding: &str) -> bool {
+    encoding == "gzip" || encoding == "x-gzip" || encoding == "deflate"
+}
+
+fn decode<'a>(encoding: &str, reader: Box<Read + 'a>) -> Result<Box<Read + 'a>, GenericError> {
+    match encoding {
+        "gzip" | "x-gzip" => Ok(Box::new(GzDecoder::new(reader)?)),
+        "deflate" => Ok(Box::new(ZlibDecoder::new(reader))),
+        _ => amiwo_error!(format!("::AMIWO::CONTRIB::HYPER::DECODE::ERROR Unsupported Content-Encoding `{}`", encoding)),
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ request_raw, MAX_DECODED_SIZE };
+    use contrib::hyper::request;
+    use contrib::hyper::tests::{ json_response, serve, serve_bytes };
+
+    // HTTP response with a JSON `body` encoded as `encoding`
+    fn encoded_response(encoding: &str, body: &[u8]) -> Vec<u8> {
+        let mut response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", encoding, body.len()).into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    #[test]
+    fn decode_test_compressed() {
+        use std::io::Write;
+        use flate2::Compression;
+        use flate2::write::{ GzEncoder, ZlibEncoder };
+
+        let body = br#"{"success":true,"http_code":200,"data":{"name":"Boris"}}"#;
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::Default);
+        gzip.write_all(body).unwrap();
+        let mut deflate = ZlibEncoder::new(Vec::new(), Compression::Default);
+        deflate.write_all(body).unwrap();
+        let (gzip, deflate) = (gzip.finish().unwrap(), deflate.finish().unwrap());
+
+        let (url, handle) = serve_bytes(vec![encoded_response("gzip", &gzip), encoded_response("deflate", &deflate), encoded_response("GZIP", &gzip)]);
+        assert_eq!(request("GET", &url).unwrap().data, json!({ "name": "Boris" }));
+        assert_eq!(request("GET", &url).unwrap().data, json!({ "name": "Boris" }));
+        let raw = request_raw("GET", &url).unwrap();
+        assert_eq!(raw.body, body.to_vec());
+        assert_eq!(raw.content_encoding(), None);
+        assert_eq!(raw.json().unwrap().data, json!({ "name": "Boris" }));
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].to_lowercase().contains("accept-encoding: gzip, deflate\r\n"));
+    }
+
+    #[test]
+    fn decode_test_unsupported() {
+        let (url, handle) = serve_bytes(vec![encoded_response("br", b"\x0b\x02\x80"), encoded_response("br", b"\x0b\x02\x80")]);
+        assert!(request("GET", &url).is_err());
+        let raw = request_raw("GET", &url).unwrap();
+        assert_eq!(raw.status, 200);
+        assert_eq!(raw.content_encoding(), Some("br".to_string()));
+        assert_eq!(raw.body, b"\x0b\x02\x80".to_vec());
+        assert!(raw.json().is_err());
+        handle.join().unwrap();
+
+        let (url, handle) = serve(vec![json_response("200 OK", "id,name\n17,Boris\n")]);
+        assert_eq!(request_raw("GET", &url).unwrap().text(), "id,name\n17,Boris\n");
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn decode_test_too_large() {
+        use std::io::{ self, Read };
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::Best);
+        io::copy(&mut io::repeat(b' ').take(MAX_DECODED_SIZE + 1), &mut gzip).unwrap();
+        let gzip = gzip.finish().unwrap();
+
+        let (url, handle) = serve_bytes(vec![encoded_response("gzip", &gzip), encoded_response("gzip", &gzip)]);
+        assert!(request_raw("GET", &url).unwrap_err().to_string().contains("Decoded body larger than"));
+        assert!(request("GET", &url).is_err());
+        handle.join().unwrap();
+    }
+}
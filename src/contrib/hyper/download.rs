@@ -0,0 +1,194 @@
+//! File holding the download helpers, streaming the body of a response to disk or to a reader without buffering it
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.4
+//!
+//! ## Release notes
+//! - v1.4 : the interceptors of an `AmiwoClient` are called
+//! - v1.3 : the requests of an `AmiwoClient` send the cookies of its jar & go through its circuit breaker & retry policy
+//! - v1.2 : the CRC-32 is computed with the table-driven `flate2::Crc`
+//! - v1.1 : the redirect policy of the `AmiwoClient` is applied
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::fs::{ self, File };
+use std::io::{ Read, Write };
+use std::path::Path;
+
+use flate2::Crc;
+use hyper::client::Response;
+use hyper::header::ContentLength;
+
+use contrib::hyper::{ AmiwoClient, RequestSpec };
+use error::GenericError;
+
+// =======================================================================
+// STATIC VARIABLES
+// =======================================================================
+const BUFFER_SIZE: usize = 64 * 1024;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Summary of a file downloaded with `contrib::hyper::download()`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Downloaded {
+    /// Number of bytes written
+    pub size: u64,
+    /// CRC-32 (IEEE, as used by gzip & zip) of the bytes written
+    pub crc32: u32,
+}
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Stream the body of a `GET` request to `url` into the file at `path` (created or truncated) without buffering it in memory.
+///
+/// `progress` is called after each chunk written with the number of bytes written so far & the `Content-Length` of the response (if any).
+/// A response with a non 2xx status fails with `GenericError::Upstream`; the file is removed if the download fails after its creation
+///
+/// ```rust,ignore
+/// let downloaded = amiwo::contrib::hyper::download("http://exports.internal/users.csv", "/var/tmp/users.csv", |done, total| {
+///     info!("{} / {:?} bytes", done, total);
+/// })?;
+/// ```
+pub fn download<P: AsRef<Path>, F: FnMut(u64, Option<u64>)>(url: &str, path: P, progress: F) -> Result<Downloaded, GenericError> {
+    download_with(&AmiwoClient::new(), url, path.as_ref(), progress)
+}
+
+/// Send a `GET` request to `url` and return the response, to be read as a stream of bytes (`hyper::client::Response` implements `Read`).
+///
+/// A response with a non 2xx status fails with `GenericError::Upstream`
+pub fn stream(url: &str) -> Result<Response, GenericError> {
+    stream_with(&AmiwoClient::new(), url)
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// `stream()` with `client`
+pub(crate) fn stream_with(client: &AmiwoClient, url: &str) -> Result<Response, GenericError> {
+    // the body is returned as received
+    let (response, _) = client.send_raw_spec(RequestSpec::new("GET", url).header("Accept-Encoding", "identity"))?;
+    client.intercept_status(response.status.to_u16());
+    if !response.status.is_success() {
+        let status = response.status.to_u16();
+        return Err(GenericError::Upstream((status, format!("::AMIWO::CONTRIB::HYPER::STREAM::ERROR {} answered with status {}", url, response.status))));
+    }
+    Ok(response)
+}
+
+// `download()` with `client`
+pub(crate) fn download_with<F: FnMut(u64, Option<u64>)>(client: &AmiwoClient, url: &str, path: &Path, mut progress: F) -> Result<Downloaded, GenericError> {
+    let mut response = stream_with(client, url)?;
+    let total = response.headers.get::<ContentLength>().map(|&ContentLength(length)| length);
+
+    let mut file = File::create(path)?;
+    let result = copy(&mut response, &mut file, |size| progress(size, total));
+    if result.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+// Copy `reader` into `writer` computing the size & CRC-32 of the bytes copied
+fn copy<R: Read, W: Write, F: FnMut(u64)>(reader: &mut R, writer: &mut W, mut progress: F) -> Result<Downloaded, GenericError> {
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut size = 0u64;
+    let mut crc = Crc::new();
+    loop {
+        let count = reader.read(&mut buffer)?;
+        if count == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..count])?;
+        crc.update(&buffer[..count]);
+        size += count as u64;
+        progress(size);
+    }
+    writer.flush()?;
+    Ok(Downloaded { size: size, crc32: crc.sum() })
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::env;
+    use std::fs::File;
+    use std::io::Read;
+
+    use super::{ copy, download, stream, Downloaded };
+    use contrib::hyper::{ AmiwoClient, ClientConfig, RedirectPolicy };
+    use contrib::hyper::tests::{ json_response, serve };
+    use error::GenericError;
+
+    fn text_response(status: &str, body: &str) -> String {
+        format!("HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body)
+    }
+
+    #[test]
+    fn download_test_copy() {
+        let mut output = Vec::new();
+        let downloaded = copy(&mut "The quick brown fox jumps over the lazy dog".as_bytes(), &mut output, |_| ()).unwrap();
+        assert_eq!(downloaded, Downloaded { size: 43, crc32: 0x414f_a339 });
+        assert_eq!(output.len(), 43);
+    }
+
+    #[test]
+    fn download_test_download() {
+        let (url, handle) = serve(vec![
+            text_response("200 OK", "id,name\n17,Boris\n"),
+            text_response("200 OK", "streamed"),
+            json_response("404 Not Found", r#"{"success":false,"http_code":404}"#),
+        ]);
+        let path = env::temp_dir().join("amiwo_download_test.csv");
+
+        let mut calls = Vec::new();
+        let downloaded = download(&format!("{}/users.csv", url), &path, |done, total| calls.push((done, total))).unwrap();
+        assert_eq!(downloaded.size, 17);
+        assert_eq!(calls.last(), Some(&(17, Some(17))));
+        let mut content = String::new();
+        File::open(&path).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "id,name\n17,Boris\n");
+
+        let mut body = String::new();
+        stream(&url).unwrap().read_to_string(&mut body).unwrap();
+        assert_eq!(body, "streamed");
+
+        match download(&url, &path, |_, _| ()) {
+            Err(GenericError::Upstream((404, _))) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+        handle.join().unwrap();
+        let _ = ::std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn download_test_redirect() {
+        let redirect = |location: &str| format!("HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", location);
+        let (url, handle) = serve(vec![
+            redirect("/exports/users.csv"),
+            text_response("200 OK", "id,name\n17,Boris\n"),
+            redirect("/exports/stream"),
+            text_response("200 OK", "streamed"),
+        ]);
+        let path = env::temp_dir().join("amiwo_download_redirect_test.csv");
+
+        let client = AmiwoClient::with_config(ClientConfig::new().redirect(RedirectPolicy::new()));
+        assert_eq!(client.download(&format!("{}/users.csv", url), &path, |_, _| ()).unwrap().size, 17);
+        let mut body = String::new();
+        client.stream(&url).unwrap().read_to_string(&mut body).unwrap();
+        assert_eq!(body, "streamed");
+
+        let requests = handle.join().unwrap();
+        assert!(requests[1].starts_with("GET /exports/users.csv HTTP/1.1\r\n"));
+        assert!(requests[3].starts_with("GET /exports/stream HTTP/1.1\r\n"));
+        let _ = ::std::fs::remove_file(&path);
+    }
+}
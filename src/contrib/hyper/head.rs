@@ -0,0 +1,177 @@
+//! File holding the head() & options() functions, requesting the metadata of a resource without its body
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.2
+//!
+//! ## Release notes
+//! - v1.2 : the interceptors of an `AmiwoClient` are called
+//! - v1.1 : the requests of an `AmiwoClient` send the cookies of its jar & go through its circuit breaker & retry policy
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::time::Instant;
+
+use hyper::header::Headers;
+use hyper::method::Method;
+use hyper::Url;
+
+use contrib::hyper::{ AmiwoClient, RequestSpec };
+use contrib::hyper::metrics;
+use error::GenericError;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Status & headers of the response to a `HEAD` request
+#[derive(Clone, Debug)]
+pub struct ResponseHead {
+    pub status: u16,
+    pub headers: Headers,
+}
+
+/// Methods & headers allowed by a resource, as answered to an `OPTIONS` request
+#[derive(Clone, Debug, PartialEq)]
+pub struct AllowedOptions {
+    pub status: u16,
+    /// Methods (uppercase) listed by the `Allow` & `Access-Control-Allow-Methods` headers
+    pub methods: Vec<String>,
+    /// Headers (lowercase) listed by the `Access-Control-Allow-Headers` header
+    pub headers: Vec<String>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl ResponseHead {
+    /// First value of the header `name`, if any
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.headers.get_raw(name)
+            .and_then(|values| values.first())
+            .map(|value| String::from_utf8_lossy(value).into_owned())
+    }
+
+    /// Value of the `Content-Length` header, if any
+    pub fn content_length(&self) -> Option<u64> {
+        self.header("Content-Length").and_then(|length| length.trim().parse().ok())
+    }
+}
+
+impl AllowedOptions {
+    /// `true` if `method` (case insensitive) is allowed
+    pub fn allows(&self, method: &str) -> bool {
+        self.methods.contains(&method.to_uppercase())
+    }
+}
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Send a `HEAD` request to `url`, returns the status & the headers of the response
+///
+/// ```rust,ignore
+/// let head = amiwo::contrib::hyper::head("http://files.internal/reports/17.pdf")?;
+/// if head.status == 200 { println!("{:?} bytes", head.content_length()); }
+/// ```
+pub fn head(url: &str) -> Result<ResponseHead, GenericError> {
+    head_with(&AmiwoClient::new(), url)
+}
+
+/// Send an `OPTIONS` request to `url`, returns the methods & headers allowed by the resource
+///
+/// ```rust,ignore
+/// if amiwo::contrib::hyper::options("http://users.internal/users/17")?.allows("PATCH") { ... }
+/// ```
+pub fn options(url: &str) -> Result<AllowedOptions, GenericError> {
+    options_with(&AmiwoClient::new(), url)
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Send a `HEAD` request to `url` with `client`
+pub(crate) fn head_with(client: &AmiwoClient, url: &str) -> Result<ResponseHead, GenericError> {
+    let (status, headers) = send_without_body(client, Method::Head, url)?;
+    Ok(ResponseHead { status: status, headers: headers })
+}
+
+// Send an `OPTIONS` request to `url` with `client`
+pub(crate) fn options_with(client: &AmiwoClient, url: &str) -> Result<AllowedOptions, GenericError> {
+    let (status, headers) = send_without_body(client, Method::Options, url)?;
+
+    let mut methods = Vec::new();
+    for method in list_values(&headers, "Allow").into_iter().chain(list_values(&headers, "Access-Control-Allow-Methods")) {
+        let method = method.to_uppercase();
+        if !methods.contains(&method) {
+            methods.push(method);
+        }
+    }
+    let headers = list_values(&headers, "Access-Control-Allow-Headers").into_iter().map(|header| header.to_lowercase()).collect();
+    Ok(AllowedOptions { status: status, methods: methods, headers: headers })
+}
+
+// Send a `method` request to `url` & returns the status & headers of the response, its body being ignored
+fn send_without_body(client: &AmiwoClient, method: Method, url: &str) -> Result<(u16, Headers), GenericError> {
+    let url = Url::parse(url)?;
+    let started = Instant::now();
+    let result = client.send_raw_spec(RequestSpec::new(method.as_ref(), url.as_str()));
+    let status = result.as_ref().ok().map(|&(ref response, _)| response.status.to_u16());
+    metrics::record(&method, &url, status, 0, 0, started);
+
+    let (response, _) = result?;
+    client.intercept_status(response.status.to_u16());
+    Ok((response.status.to_u16(), response.headers.clone()))
+}
+
+// Values of the comma separated list header `name`, e.g. `Allow: GET, HEAD` => `["GET", "HEAD"]`
+fn list_values(headers: &Headers, name: &str) -> Vec<String> {
+    headers.get_raw(name)
+        .map(|values| values.iter()
+            .flat_map(|value| String::from_utf8_lossy(value).split(',').map(|item| item.trim().to_string()).collect::<Vec<_>>())
+            .filter(|item| !item.is_empty())
+            .collect())
+        .unwrap_or(Vec::new())
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ head, options };
+    use contrib::hyper::AmiwoClient;
+    use contrib::hyper::tests::serve;
+
+    #[test]
+    fn head_test_head() {
+        let (url, handle) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: application/pdf\r\nContent-Length: 1024\r\nConnection: close\r\n\r\n".to_string(),
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        ]);
+        let response = head(&format!("{}/reports/17.pdf", url)).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_length(), Some(1024));
+        assert_eq!(response.header("content-type"), Some("application/pdf".to_string()));
+        assert_eq!(AmiwoClient::new().head(&format!("{}/reports/18.pdf", url)).unwrap().status, 404);
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("HEAD /reports/17.pdf HTTP/1.1\r\n"));
+        assert!(head("not an url").is_err());
+    }
+
+    #[test]
+    fn head_test_options() {
+        let (url, handle) = serve(vec![
+            "HTTP/1.1 204 No Content\r\nAllow: GET, HEAD,patch\r\nAccess-Control-Allow-Methods: GET, DELETE\r\nAccess-Control-Allow-Headers: Content-Type, X-Api-Key\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        ]);
+        let allowed = options(&format!("{}/users/17", url)).unwrap();
+        assert_eq!(allowed.status, 204);
+        assert_eq!(allowed.methods, vec!["GET", "HEAD", "PATCH", "DELETE"]);
+        assert_eq!(allowed.headers, vec!["content-type", "x-api-key"]);
+        assert!(allowed.allows("patch") && !allowed.allows("PUT"));
+        assert!(handle.join().unwrap()[0].starts_with("OPTIONS /users/17 HTTP/1.1\r\n"));
+    }
+}
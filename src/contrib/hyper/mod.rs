@@ -4,15 +4,76 @@
 //! 
 //! Also implements `Into<Result<ResponseJSON, GenericError>>` for `Result<hyper::client::response::Response>` and `hyper::client::response::Response` to allow simple chaining 
 //!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.19
+//!
+//! ## Release notes
+//! - v1.19 : the responses compressed with gzip or deflate are decoded (up to `MAX_DECODED_SIZE` bytes), added `request_raw()`
+//! - v1.18 : added `head()`, `options()` & `patch_json()`
+//! - v1.17 : added `set_metrics_hook()`, the outbound requests being logged with their metrics
+//! - v1.16 : added `CookieJar`
+//! - v1.15 : added `CircuitBreaker`
+//! - v1.14 : added `paginate()`
+//! - v1.13 : added `ResponseCache` & `MemoryCache`
+//! - v1.12 : added `HttpBackend` & `MockBackend`
+//! - v1.11 : added `batch()` & `RequestSpec`
+//! - v1.10 : added `MultipartBuilder`
+//! - v1.9 : added `download()` & `stream()`
+//! - v1.8 : added `RedirectPolicy`
+//! - v1.7 : added `request_as()`
+//! - v1.6 : added `RetryPolicy`
+//! - v1.5 : a request timing out fails with `GenericError::Timeout`
+//! - v1.4 : added `AmiwoClient` & `ClientConfig`
+//! - v1.3 : added `RequestBuilder`
+//! - v1.2 : added `request_with_headers()`, `post_json_with_headers()` & `build_headers()`
+//! - v1.1 : added `request_with_body()` & `post_json()`
+//! - v1.0 : creation
+
+mod backend;
+mod batch;
+mod cache;
+mod circuit_breaker;
+mod client;
+mod cookie_jar;
+mod decode;
+mod download;
+mod head;
+mod metrics;
+mod multipart;
+mod paginate;
+mod redirect;
+mod request_builder;
+mod retry;
+
+pub use self::backend::{ HttpBackend, MockBackend };
+pub use self::batch::{ batch, RequestSpec };
+pub use self::cache::{ CachedResponse, MemoryCache, ResponseCache };
+pub use self::circuit_breaker::{ CircuitBreaker, CircuitState };
+pub use self::client::{ AmiwoClient, ClientConfig };
+pub use self::cookie_jar::CookieJar;
+pub use self::decode::{ request_raw, RawResponse, MAX_DECODED_SIZE };
+pub use self::download::{ download, stream, Downloaded };
+pub use self::head::{ head, options, AllowedOptions, ResponseHead };
+pub use self::metrics::{ clear_metrics_hook, set_metrics_hook, RequestMetrics };
+pub use self::multipart::MultipartBuilder;
+pub use self::paginate::{ paginate, PageStrategy, Pages };
+pub use self::redirect::RedirectPolicy;
+pub use self::request_builder::RequestBuilder;
+pub use self::retry::RetryPolicy;
 
 // =======================================================================
 // LIBRARY IMPORTS
 // =======================================================================
 use std::str::FromStr;
+use std::time::Instant;
 
-use hyper::client::Client;
+use hyper::client::{ Client, Response };
+use hyper::header::{ ContentType, Headers };
 use hyper::method::Method;
 use hyper::Url;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
 
 use error::GenericError;
 use types::ResponseJSON;
@@ -21,18 +82,299 @@ use types::ResponseJSON;
 // PUBLIC FUNCTIONS
 // =======================================================================
 /// Send a simple `method` request to `url` and pre-process the response to try to build a `ResponseJSON` from it 
+///
+/// A new `hyper::Client` is created for each call, use an `AmiwoClient` to reuse the connections
 pub fn request(method: &str, url: &str) -> Result<ResponseJSON, GenericError> {
-    let hyper_method = Method::from_str(method.to_uppercase().as_str());
-    let hyper_url = Url::parse(url);
+    request_with_headers(method, url, Headers::new(), None)
+}
+
+/// Send a simple `method` request to `url` and deserialize the response into `T`:
+/// the `data` of a successful ResponseJSON envelope, or the whole body if it isn't an envelope.
+///
+/// An error envelope fails with `GenericError::Upstream`
+///
+/// ```rust,ignore
+/// let users : Vec<User> = amiwo::contrib::hyper::request_as("GET", "http://users.internal/users")?;
+/// ```
+pub fn request_as<T: DeserializeOwned>(method: &str, url: &str) -> Result<T, GenericError> {
+    request(method, url).and_then(ResponseJSON::into_typed)
+}
+
+/// Send a `method` request with `body` to `url` and pre-process the response to try to build a `ResponseJSON` from it
+pub fn request_with_body(method: &str, url: &str, body: &str) -> Result<ResponseJSON, GenericError> {
+    request_with_headers(method, url, Headers::new(), Some(body))
+}
+
+/// Send a `method` request with `headers` (and `body` if any) to `url` and pre-process the response to try to build a `ResponseJSON` from it
+///
+/// ```rust,ignore
+/// let headers = amiwo::contrib::hyper::build_headers(&[("Authorization", "Bearer abc"), ("Accept", "application/json")]);
+/// let rjson = amiwo::contrib::hyper::request_with_headers("GET", "http://localhost:8000/users", headers, None)?;
+/// ```
+pub fn request_with_headers(method: &str, url: &str, headers: Headers, body: Option<&str>) -> Result<ResponseJSON, GenericError> {
+    let (hyper_method, hyper_url) = parse_target(method, url)?;
+    send_request(&Client::new(), None, hyper_method, hyper_url, headers, body.map(|body| body.as_bytes()))
+}
+
+/// Send a `POST` request to `url` with `body` serialized as JSON (`Content-Type: application/json`)
+/// and pre-process the response to try to build a `ResponseJSON` from it
+///
+/// ```rust,ignore
+/// let rjson = amiwo::contrib::hyper::post_json("http://localhost:8000/users", &json!({ "name": "Boris" }))?;
+/// ```
+pub fn post_json<T: Serialize>(url: &str, body: &T) -> Result<ResponseJSON, GenericError> {
+    post_json_with_headers(url, body, Headers::new())
+}
+
+/// Same as `post_json()` with additional `headers` (`Content-Type` being always set to `application/json`)
+pub fn post_json_with_headers<T: Serialize>(url: &str, body: &T, headers: Headers) -> Result<ResponseJSON, GenericError> {
+    send_json(&Client::new(), None, Method::Post, url, body, headers)
+}
+
+/// Send a `PATCH` request to `url` with `body` serialized as JSON (`Content-Type: application/json`)
+/// and pre-process the response to try to build a `ResponseJSON` from it
+///
+/// ```rust,ignore
+/// let rjson = amiwo::contrib::hyper::patch_json("http://localhost:8000/users/17", &json!({ "email": "boris@humanenginuity.com" }))?;
+/// ```
+pub fn patch_json<T: Serialize>(url: &str, body: &T) -> Result<ResponseJSON, GenericError> {
+    send_json(&Client::new(), None, Method::Patch, url, body, Headers::new())
+}
+
+/// Build `Headers` from `(name, value)` pairs, a name repeated adding a value to the header
+pub fn build_headers(pairs: &[(&str, &str)]) -> Headers {
+    let mut headers = Headers::new();
+    for &(name, value) in pairs {
+        headers.append_raw(name.to_string(), value.as_bytes().to_vec());
+    }
+    headers
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Parse the method & url of a request
+pub(crate) fn parse_target(method: &str, url: &str) -> Result<(Method, Url), GenericError> {
+    let hyper_method = Method::from_str(method.to_uppercase().as_str())?;
+    let hyper_url = Url::parse(url)?;
+    Ok((hyper_method, hyper_url))
+}
+
+// Send a request with `client` and pre-process the response to try to build a `ResponseJSON` from it
+pub(crate) fn send_request(client: &Client, redirect: Option<&RedirectPolicy>, method: Method, url: Url, headers: Headers, body: Option<&[u8]>) -> Result<ResponseJSON, GenericError> {
+    send_response(client, redirect, method, url, headers, body).map(|(_, rjson)| rjson)
+}
+
+// Same as `send_request()` also returning the HTTP status of the response
+pub(crate) fn send_response(client: &Client, redirect: Option<&RedirectPolicy>, method: Method, url: Url, headers: Headers, body: Option<&[u8]>) -> Result<(u16, ResponseJSON), GenericError> {
+    send_response_with(client, redirect, method, url, headers, body, None)
+}
+
+// Same as `send_response()` sending & storing the cookies of `cookies` (if any)
+pub(crate) fn send_response_with(client: &Client, redirect: Option<&RedirectPolicy>, method: Method, url: Url, headers: Headers, body: Option<&[u8]>, cookies: Option<&CookieJar>) -> Result<(u16, ResponseJSON), GenericError> {
+    let started = Instant::now();
+    let request_bytes = body.map_or(0, |body| body.len() as u64);
+    let (response, redirected) = match send_raw(client, redirect, method.clone(), url.clone(), headers, body, cookies) {
+        Ok(sent) => sent,
+        Err(err) => {
+            metrics::record(&method, &url, None, request_bytes, 0, started);
+            return Err(err);
+        },
+    };
+    let status = response.status.to_u16();
+
+    let (result, response_bytes) = decode::read_json(response);
+    metrics::record(&method, &url, Some(status), request_bytes, response_bytes, started);
+    result.map(|mut rjson| {
+        if let Some(url) = redirected {
+            rjson.resource = Some(url.to_string());
+        }
+        (status, rjson)
+    })
+}
+
+// Send a request with `client` following the redirects according to `redirect` (if any), returns the final response
+// & its URL if the request was redirected by the policy.
+// The cookies of `cookies` (if any) are sent & stored on each redirect followed by the policy, only on the first request &
+// the final response if the redirects are followed by hyper
+pub(crate) fn send_raw(client: &Client, redirect: Option<&RedirectPolicy>, method: Method, url: Url, mut headers: Headers, body: Option<&[u8]>, cookies: Option<&CookieJar>) -> Result<(Response, Option<Url>), GenericError> {
+    decode::accept_encoding(&mut headers);
+    if let Some(policy) = redirect {
+        return redirect::send_following(client, policy, method, url, headers, body, cookies);
+    }
+
+    if let Some(jar) = cookies {
+        jar.add_to(url.as_str(), &mut headers);
+    }
+    let builder = client.request(method, url).headers(headers);
+    let builder = match body {
+        Some(body) => builder.body(body),
+        None => builder,
+    };
+    let response = builder.send().map_err(|hyper_error| GenericError::from(hyper_error))?;
+    if let Some(jar) = cookies {
+        jar.store(&response.url, &response.headers);
+    }
+    Ok((response, None))
+}
+
+// Send a `method` request with `body` serialized as JSON with `client`
+pub(crate) fn send_json<T: Serialize>(client: &Client, redirect: Option<&RedirectPolicy>, method: Method, url: &str, body: &T, mut headers: Headers) -> Result<ResponseJSON, GenericError> {
+    let hyper_url = Url::parse(url)?;
+    let body = serde_json::to_string(body)?;
+    headers.set(ContentType::json());
+    send_request(client, redirect, method, hyper_url, headers, Some(body.as_bytes()))
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+pub mod tests {
+    #![allow(non_snake_case)]
+
+    use std::io::{ Read, Write };
+    use std::net::TcpListener;
+    use std::thread;
+    use std::thread::JoinHandle;
+
+    use super::{ build_headers, post_json, post_json_with_headers, request, request_as, request_with_body, request_with_headers };
+    use error::GenericError;
+
+    // Serve `responses` (one per connection) on a local port, returns the base url of the server
+    // & a handle joining on the raw requests received
+    pub fn serve(responses: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+        serve_bytes(responses.into_iter().map(String::into_bytes).collect())
+    }
+
+    // Same as `serve()` with binary `responses`, e.g. compressed bodies
+    pub fn serve_bytes(responses: Vec<Vec<u8>>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                requests.push(read_request(&mut stream));
+                stream.write_all(&response).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    // HTTP response with a JSON `body`
+    pub fn json_response(status: &str, body: &str) -> String {
+        format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body)
+    }
+
+    // Read the head of the request & the `Content-Length` bytes of its body
+    fn read_request<R: Read>(stream: &mut R) -> String {
+        let mut data = Vec::new();
+        let mut buffer = [0u8; 1024];
+        loop {
+            let count = stream.read(&mut buffer).unwrap();
+            data.extend_from_slice(&buffer[..count]);
+
+            let text = String::from_utf8_lossy(&data).into_owned();
+            if let Some(head_end) = text.find("\r\n\r\n") {
+                let length = text[..head_end].lines()
+                    .filter_map(|line| {
+                        let lower = line.to_lowercase();
+                        if lower.starts_with("content-length:") { lower[15..].trim().parse::<usize>().ok() } else { None }
+                    })
+                    .next()
+                    .unwrap_or(0);
+                if count == 0 || data.len() >= head_end + 4 + length {
+                    return text;
+                }
+            } else if count == 0 {
+                return text;
+            }
+        }
+    }
 
-    if hyper_method.is_err() {
-        return Err(GenericError::Hyper(hyper_method.unwrap_err()));
+    #[test]
+    fn hyper_test_request() {
+        let (url, handle) = serve(vec![
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1,2]}"#),
+            json_response("201 Created", r#"{"success":true,"http_code":201}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200}"#),
+        ]);
+
+        let rjson = request("get", &format!("{}/users", url)).unwrap();
+        assert_eq!(rjson.data, json!([1, 2]));
+        assert_eq!(request_with_body("put", &format!("{}/users/17", url), "name=Boris").unwrap().http_code, 201);
+        assert!(post_json(&format!("{}/users", url), &json!({ "name": "Boris" })).unwrap().success);
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("GET /users HTTP/1.1\r\n"));
+        assert!(requests[1].starts_with("PUT /users/17 HTTP/1.1\r\n"));
+        assert!(requests[1].ends_with("\r\n\r\nname=Boris"));
+        assert!(requests[2].starts_with("POST /users HTTP/1.1\r\n"));
+        assert!(requests[2].contains("Content-Type: application/json\r\n"));
+        assert!(requests[2].ends_with(r#"{"name":"Boris"}"#));
     }
-    if hyper_url.is_err() {
-        return Err(GenericError::Hyper(hyper_method.unwrap_err()));                
+
+    #[test]
+    fn hyper_test_request_with_headers() {
+        let (url, handle) = serve(vec![
+            json_response("200 OK", r#"{"success":true,"http_code":200}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200}"#),
+        ]);
+
+        let headers = build_headers(&[("X-Api-Key", "secret"), ("Accept", "application/json")]);
+        assert!(request_with_headers("GET", &format!("{}/users", url), headers, None).unwrap().success);
+        let headers = build_headers(&[("Authorization", "Bearer abc"), ("Content-Type", "text/plain")]);
+        assert!(post_json_with_headers(&format!("{}/users", url), &json!({}), headers).unwrap().success);
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].contains("X-Api-Key: secret\r\n"));
+        assert!(requests[0].contains("Accept: application/json\r\n"));
+        assert!(requests[1].contains("Authorization: Bearer abc\r\n"));
+        assert!(requests[1].contains("Content-Type: application/json\r\n"));
+        assert!(!requests[1].contains("text/plain"));
+
+        let headers = build_headers(&[("Accept", "text/csv"), ("Accept", "application/json")]);
+        assert_eq!(headers.get_raw("Accept"), Some(&[b"text/csv".to_vec(), b"application/json".to_vec()][..]));
     }
 
-    Client::new().request(hyper_method.unwrap(), hyper_url.unwrap()).send()
-    .map_err(|hyper_error| GenericError::Hyper(hyper_error))
-    .and_then(|response| ResponseJSON::from_reader(response))
-}
\ No newline at end of file
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User {
+        name: String,
+    }
+
+    #[test]
+    fn hyper_test_request_as() {
+        let (url, handle) = serve(vec![
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[{"name":"Boris"}]}"#),
+            json_response("200 OK", r#"[{"name":"Boris"},{"name":"Bob"}]"#),
+            json_response("404 Not Found", r#"{"success":false,"http_code":404,"message":"No such user"}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":{"id":17}}"#),
+        ]);
+
+        let users : Vec<User> = request_as("GET", &url).unwrap();
+        assert_eq!(users, vec![User { name: "Boris".to_string() }]);
+        let users : Vec<User> = request_as("GET", &url).unwrap();
+        assert_eq!(users.len(), 2);
+
+        match request_as::<User>("GET", &url) {
+            Err(GenericError::Upstream((404, ref message))) => assert_eq!(message, "No such user"),
+            other => panic!("unexpected result {:?}", other),
+        }
+        match request_as::<User>("GET", &url) {
+            Err(GenericError::Serde(_)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn hyper_test_request_invalid_url() {
+        match request("GET", "not a url") {
+            Err(GenericError::UrlParse(_)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
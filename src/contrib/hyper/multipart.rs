@@ -0,0 +1,228 @@
+//! File holding the MultipartBuilder type, building the `multipart/form-data` body of an outbound request
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{ AtomicUsize, Ordering, ATOMIC_USIZE_INIT };
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+use serde::Serialize;
+use serde_json;
+
+use error::GenericError;
+
+// =======================================================================
+// STATIC VARIABLES
+// =======================================================================
+static BOUNDARY_COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Builder of a `multipart/form-data` body, to be sent with `RequestBuilder::multipart()`
+///
+/// The errors of the builder methods (e.g. `part_file()` failing to read the file) are reported by `build()`
+///
+/// ```rust,ignore
+/// let rjson = RequestBuilder::new()
+///     .method("POST")
+///     .url("https://partner.example.com/documents")
+///     .multipart(MultipartBuilder::new()
+///         .part_text("title", "Invoice 17")
+///         .part_json("meta", &json!({ "customer": 42 }))
+///         .part_file("document", "/var/tmp/invoice-17.pdf"))
+///     .send()?;
+/// ```
+#[derive(Debug)]
+pub struct MultipartBuilder {
+    boundary: String,
+    body: Vec<u8>,
+    error: Option<GenericError>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl MultipartBuilder {
+    /// Create an empty body with a unique boundary
+    pub fn new() -> MultipartBuilder {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.subsec_nanos()).unwrap_or(0);
+        MultipartBuilder {
+            boundary: format!("amiwo-boundary-{:08x}{:08x}", nanos, BOUNDARY_COUNTER.fetch_add(1, Ordering::SeqCst)),
+            body: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Boundary separating the parts
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Value of the `Content-Type` header of the request (`multipart/form-data; boundary=...`)
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Add a text field
+    pub fn part_text(self, name: &str, value: &str) -> MultipartBuilder {
+        self.part(name, None, None, value.as_bytes())
+    }
+
+    /// Add a field holding `value` serialized as JSON (`Content-Type: application/json`)
+    pub fn part_json<T: Serialize>(mut self, name: &str, value: &T) -> MultipartBuilder {
+        match serde_json::to_vec(value) {
+            Ok(json) => self.part(name, None, Some("application/json"), &json),
+            Err(err) => {
+                self.set_error(GenericError::new_compound(&format!("::AMIWO::CONTRIB::HYPER::MULTIPART::PART_JSON::ERROR Unable to serialize part `{}`", name), GenericError::from(err)));
+                self
+            },
+        }
+    }
+
+    /// Add the file at `path` (`Content-Type: application/octet-stream`, the file name being the last component of `path`)
+    pub fn part_file<P: AsRef<Path>>(mut self, name: &str, path: P) -> MultipartBuilder {
+        let path = path.as_ref();
+        let filename = path.file_name().map_or(String::new(), |filename| filename.to_string_lossy().into_owned());
+
+        let mut content = Vec::new();
+        match File::open(path).and_then(|mut file| file.read_to_end(&mut content)) {
+            Ok(_) => self.part(name, Some(&filename), Some("application/octet-stream"), &content),
+            Err(err) => {
+                self.set_error(GenericError::new_compound(&format!("::AMIWO::CONTRIB::HYPER::MULTIPART::PART_FILE::ERROR Unable to read {} for part `{}`", path.display(), name), GenericError::from(err)));
+                self
+            },
+        }
+    }
+
+    /// Add a file named `filename` holding `content` of type `content_type`
+    pub fn part_bytes(self, name: &str, filename: &str, content_type: &str, content: &[u8]) -> MultipartBuilder {
+        self.part(name, Some(filename), Some(content_type), content)
+    }
+
+    /// Consumes the builder, returns the value of the `Content-Type` header & the body of the request
+    pub fn build(mut self) -> Result<(String, Vec<u8>), GenericError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let content_type = self.content_type();
+        self.body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        Ok((content_type, self.body))
+    }
+
+    // Append a part to the body
+    fn part(mut self, name: &str, filename: Option<&str>, content_type: Option<&str>, content: &[u8]) -> MultipartBuilder {
+        let mut head = format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"", self.boundary, escape(name));
+        if let Some(filename) = filename {
+            head.push_str(&format!("; filename=\"{}\"", escape(filename)));
+        }
+        if let Some(content_type) = content_type {
+            head.push_str(&format!("\r\nContent-Type: {}", content_type));
+        }
+        head.push_str("\r\n\r\n");
+
+        self.body.extend_from_slice(head.as_bytes());
+        self.body.extend_from_slice(content);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    // Keep the first error
+    fn set_error(&mut self, err: GenericError) {
+        if self.error.is_none() {
+            self.error = Some(err);
+        }
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Escape a name or file name for a `Content-Disposition` header
+fn escape(value: &str) -> String {
+    value.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Default for MultipartBuilder {
+    fn default() -> MultipartBuilder {
+        MultipartBuilder::new()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::MultipartBuilder;
+    use contrib::hyper::RequestBuilder;
+    use contrib::hyper::tests::{ json_response, serve };
+
+    #[test]
+    fn MultipartBuilder_test_build() {
+        let path = env::temp_dir().join("amiwo_multipart_test.txt");
+        File::create(&path).unwrap().write_all(b"file content").unwrap();
+
+        let builder = MultipartBuilder::new()
+            .part_text("title", "Invoice \"17\"")
+            .part_json("meta", &json!({ "customer": 42 }))
+            .part_file("document", &path)
+            .part_bytes("logo", "lo\"go.png", "image/png", &[0x89, b'P', b'N', b'G']);
+        let boundary = builder.boundary().to_string();
+        let (content_type, body) = builder.build().unwrap();
+        let _ = ::std::fs::remove_file(&path);
+
+        assert_eq!(content_type, format!("multipart/form-data; boundary={}", boundary));
+        let mut expected = format!("--{b}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nInvoice \"17\"\r\n\
+            --{b}\r\nContent-Disposition: form-data; name=\"meta\"\r\nContent-Type: application/json\r\n\r\n{{\"customer\":42}}\r\n\
+            --{b}\r\nContent-Disposition: form-data; name=\"document\"; filename=\"amiwo_multipart_test.txt\"\r\nContent-Type: application/octet-stream\r\n\r\nfile content\r\n\
+            --{b}\r\nContent-Disposition: form-data; name=\"logo\"; filename=\"lo%22go.png\"\r\nContent-Type: image/png\r\n\r\n", b = boundary).into_bytes();
+        expected.extend_from_slice(&[0x89, b'P', b'N', b'G']);
+        expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+        assert_eq!(body, expected);
+        assert!(MultipartBuilder::new().boundary() != boundary);
+    }
+
+    #[test]
+    fn MultipartBuilder_test_errors() {
+        assert!(MultipartBuilder::new().part_file("document", "/non/existent/file").build().is_err());
+
+        let mut map = BTreeMap::new();
+        map.insert(vec![1], 1);
+        let err = MultipartBuilder::new().part_json("meta", &map).part_file("document", "/non/existent/file").build().unwrap_err();
+        assert!(err.to_string().contains("PART_JSON"));
+    }
+
+    #[test]
+    fn MultipartBuilder_test_send() {
+        let (url, handle) = serve(vec![json_response("201 Created", r#"{"success":true,"http_code":201}"#)]);
+
+        let multipart = MultipartBuilder::new().part_text("title", "Invoice 17");
+        let boundary = multipart.boundary().to_string();
+        let rjson = RequestBuilder::new().method("POST").url(&url).multipart(multipart).send().unwrap();
+        assert_eq!(rjson.http_code, 201);
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].contains(&format!("Content-Type: multipart/form-data; boundary={}\r\n", boundary)));
+        assert!(requests[0].ends_with(&format!("Invoice 17\r\n--{}--\r\n", boundary)));
+    }
+}
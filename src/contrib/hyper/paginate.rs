@@ -0,0 +1,312 @@
+//! File holding the paginate() function & the Pages iterator, following the pages of an upstream API
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.5
+//!
+//! ## Release notes
+//! - v1.5 : the pages of an `AmiwoClient` go through its request & response interceptors
+//! - v1.4 : the pages of an `AmiwoClient` are requested with the cookies of its jar & through its circuit breaker
+//! - v1.3 : `OffsetLimit(0)` is rejected, the iteration stops on a next page already fetched & after `max_pages()` pages
+//! - v1.2 : the compressed pages are decoded
+//! - v1.1 : the metrics of the requests are recorded
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::collections::HashSet;
+use std::time::Instant;
+
+use hyper::method::Method;
+use hyper::Url;
+use serde_json::Value;
+
+use contrib::hyper::{ AmiwoClient, RequestSpec };
+use contrib::hyper::decode::read_json;
+use contrib::hyper::metrics;
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// STATIC VARIABLES
+// =======================================================================
+const DEFAULT_MAX_PAGES: usize = 1000;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// How `paginate()` finds the next page
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PageStrategy {
+    /// Follow the `rel="next"` URL of the `Link` header of the response ([RFC 5988](https://tools.ietf.org/html/rfc5988))
+    LinkHeader,
+    /// Follow the `next` URL of the ResponseJSON
+    NextUrl,
+    /// Request the pages with the `offset` & `limit` query parameters, `limit` being the size of a page (a `limit` of 0 failing).
+    /// The last page is the one holding less than `limit` items in its `data` (or reaching the `total` of the ResponseJSON)
+    OffsetLimit(u64),
+}
+
+/// Iterator over the pages of an upstream API, see `paginate()`
+///
+/// It stops after the last page or after the first error, an error ResponseJSON being returned as `GenericError::Upstream`.
+/// A next page already fetched (e.g. a `next` URL pointing to the current page) ends the iteration, fetching more than
+/// `max_pages()` pages (1000 by default) fails
+#[derive(Debug)]
+pub struct Pages {
+    client: AmiwoClient,
+    strategy: PageStrategy,
+    next: Option<Result<Url, GenericError>>,
+    offset: u64,
+    fetched: HashSet<Url>,
+    max_pages: usize,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl Pages {
+    /// Set the maximum number of pages fetched, the following page failing with an error
+    pub fn max_pages(mut self, max_pages: usize) -> Pages {
+        self.max_pages = max_pages;
+        self
+    }
+
+    // Send the request of the page at `url`, returns its ResponseJSON & the URL of the next page (if any)
+    fn fetch(&mut self, url: Url) -> Result<(ResponseJSON, Option<Url>), GenericError> {
+        let started = Instant::now();
+        let (response, redirected) = match self.client.send_raw_spec(RequestSpec::new("GET", url.as_str())) {
+            Ok(sent) => sent,
+            Err(err) => {
+                metrics::record(&Method::Get, &url, None, 0, 0, started);
+                return Err(err);
+            },
+        };
+        let status = response.status.to_u16();
+        let link = response.headers.get_raw("Link")
+            .and_then(|values| values.iter().filter_map(|value| next_link(&String::from_utf8_lossy(value))).next());
+        let (result, response_bytes) = read_json(response);
+        metrics::record(&Method::Get, &url, Some(status), 0, response_bytes, started);
+        let mut rjson = result?;
+        if let Some(url) = redirected {
+            rjson.resource = Some(url.to_string());
+        }
+        self.client.intercept(&rjson);
+
+        if !rjson.success {
+            let description = rjson.message.clone().unwrap_or(format!("Upstream service answered with status {}", rjson.http_code));
+            return Err(GenericError::Upstream((rjson.http_code, description)));
+        }
+
+        let next = match self.strategy {
+            PageStrategy::LinkHeader => link,
+            PageStrategy::NextUrl => rjson.next.clone(),
+            PageStrategy::OffsetLimit(limit) => {
+                let count = match rjson.data {
+                    Value::Array(ref items) => items.len() as u64,
+                    _ => 0,
+                };
+                self.offset += count;
+                if count < limit || rjson.total.map_or(false, |total| self.offset >= total) {
+                    None
+                } else {
+                    return Ok((rjson, Some(offset_url(&url, self.offset, limit))));
+                }
+            },
+        };
+        match next {
+            Some(next) => Ok((rjson, Some(url.join(&next)?))),
+            None => Ok((rjson, None)),
+        }
+    }
+}
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Iterate over the pages of the upstream API at `url`, the next page being found according to `strategy`
+///
+/// A new `hyper::Client` is created for each call (shared by the pages), use `AmiwoClient::paginate()` to reuse the connections
+///
+/// ```rust,ignore
+/// let mut users = Vec::new();
+/// for page in amiwo::contrib::hyper::paginate("http://users.internal/users", PageStrategy::OffsetLimit(100)) {
+///     users.extend(page?.into_data_as::<Vec<User>>()?);
+/// }
+/// ```
+pub fn paginate(url: &str, strategy: PageStrategy) -> Pages {
+    pages_with(AmiwoClient::new(), url, strategy)
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Iterate over the pages at `url` with `client`
+pub(crate) fn pages_with(client: AmiwoClient, url: &str, strategy: PageStrategy) -> Pages {
+    let first = Url::parse(url).map_err(GenericError::from).and_then(|url| match strategy {
+        PageStrategy::OffsetLimit(0) => amiwo_error!("::AMIWO::CONTRIB::HYPER::PAGINATE::ERROR The limit of PageStrategy::OffsetLimit must be at least 1".to_string()),
+        PageStrategy::OffsetLimit(limit) => Ok(offset_url(&url, 0, limit)),
+        _ => Ok(url),
+    });
+    Pages {
+        client: client,
+        strategy: strategy,
+        next: Some(first),
+        offset: 0,
+        fetched: HashSet::new(),
+        max_pages: DEFAULT_MAX_PAGES,
+    }
+}
+
+// `base` with the `offset` & `limit` query parameters
+fn offset_url(base: &Url, offset: u64, limit: u64) -> Url {
+    let pairs : Vec<(String, String)> = base.query_pairs()
+        .filter(|&(ref key, _)| key != "offset" && key != "limit")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    let mut url = base.clone();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair("offset", &offset.to_string())
+        .append_pair("limit", &limit.to_string());
+    url
+}
+
+// URL of the `rel="next"` link of the `Link` header `value`, if any
+fn next_link(value: &str) -> Option<String> {
+    value.split(',')
+        .filter_map(|link| {
+            let mut parts = link.split(';');
+            let target = parts.next().unwrap_or("").trim();
+            if !target.starts_with('<') || !target.ends_with('>') {
+                return None;
+            }
+            let is_next = parts.any(|param| {
+                let mut pair = param.splitn(2, '=');
+                pair.next().map(|key| key.trim().eq_ignore_ascii_case("rel")).unwrap_or(false)
+                    && pair.next().map(|rels| rels.trim().trim_matches('"').split_whitespace().any(|rel| rel.eq_ignore_ascii_case("next"))).unwrap_or(false)
+            });
+            if is_next { Some(target[1..target.len() - 1].to_string()) } else { None }
+        })
+        .next()
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Iterator for Pages {
+    type Item = Result<ResponseJSON, GenericError>;
+
+    fn next(&mut self) -> Option<Result<ResponseJSON, GenericError>> {
+        match self.next.take() {
+            Some(Ok(ref url)) if self.fetched.len() >= self.max_pages => {
+                Some(amiwo_error!(format!("::AMIWO::CONTRIB::HYPER::PAGINATE::ERROR More than {} pages, next page {}", self.max_pages, url)))
+            },
+            Some(Ok(url)) => match self.fetch(url.clone()) {
+                Ok((rjson, next)) => {
+                    self.fetched.insert(url);
+                    self.next = match next {
+                        Some(ref next) if self.fetched.contains(next) => None,
+                        next => next.map(Ok),
+                    };
+                    Some(Ok(rjson))
+                },
+                Err(err) => Some(Err(err)),
+            },
+            Some(Err(err)) => Some(Err(err)),
+            None => None,
+        }
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ next_link, paginate, PageStrategy };
+    use contrib::hyper::AmiwoClient;
+    use error::ErrorKind;
+    use contrib::hyper::tests::{ json_response, serve };
+
+    #[test]
+    fn paginate_test_link_header() {
+        assert_eq!(next_link(r#"<https://api.example.com/items?page=1>; rel="prev", <https://api.example.com/items?page=3>; rel="next""#),
+            Some("https://api.example.com/items?page=3".to_string()));
+        assert_eq!(next_link("</items?page=2>; rel=next"), Some("/items?page=2".to_string()));
+        assert_eq!(next_link(r#"</items?page=9>; rel="last""#), None);
+
+        let body = r#"{"success":true,"http_code":200,"data":[1,2]}"#;
+        let (url, handle) = serve(vec![
+            format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nLink: </items?page=2>; rel=\"next\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[3]}"#),
+        ]);
+        let pages : Vec<_> = paginate(&format!("{}/items", url), PageStrategy::LinkHeader).map(|page| page.unwrap().data).collect();
+        assert_eq!(pages, vec![json!([1, 2]), json!([3])]);
+        assert!(handle.join().unwrap()[1].starts_with("GET /items?page=2 HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn paginate_test_next_url() {
+        let (url, handle) = serve(vec![
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1],"next":"/items?cursor=abc"}"#),
+            json_response("503 Service Unavailable", r#"{"success":false,"http_code":503,"message":"Down"}"#),
+        ]);
+        let mut pages = AmiwoClient::new().paginate(&format!("{}/items", url), PageStrategy::NextUrl);
+        assert_eq!(pages.next().unwrap().unwrap().data, json!([1]));
+        assert_eq!(pages.next().unwrap().unwrap_err().kind(), ErrorKind::Upstream);
+        assert!(pages.next().is_none());
+        assert!(handle.join().unwrap()[1].starts_with("GET /items?cursor=abc HTTP/1.1\r\n"));
+
+        let mut pages = paginate("not an url", PageStrategy::NextUrl);
+        assert!(pages.next().unwrap().is_err());
+        assert!(pages.next().is_none());
+    }
+
+    #[test]
+    fn paginate_test_offset_limit() {
+        let (url, handle) = serve(vec![
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1,2]}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[3,4]}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[5]}"#),
+        ]);
+        let pages : Vec<_> = paginate(&format!("{}/items?sort=id&offset=7", url), PageStrategy::OffsetLimit(2)).map(|page| page.unwrap().data).collect();
+        assert_eq!(pages, vec![json!([1, 2]), json!([3, 4]), json!([5])]);
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("GET /items?sort=id&offset=0&limit=2 HTTP/1.1\r\n"));
+        assert!(requests[2].starts_with("GET /items?sort=id&offset=4&limit=2 HTTP/1.1\r\n"));
+
+        let (url, handle) = serve(vec![json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1,2],"total":2}"#)]);
+        assert_eq!(paginate(&url, PageStrategy::OffsetLimit(2)).count(), 1);
+        handle.join().unwrap();
+
+        let mut pages = paginate("http://localhost/items", PageStrategy::OffsetLimit(0));
+        assert!(pages.next().unwrap().is_err());
+        assert!(pages.next().is_none());
+    }
+
+    #[test]
+    fn paginate_test_loop() {
+        let (url, handle) = serve(vec![
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1],"next":"/items"}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1],"next":"/items?page=2"}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[2],"next":"/items?page=1"}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[1],"next":"/items?page=2"}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200,"data":[2],"next":"/items?page=3"}"#),
+        ]);
+        assert_eq!(paginate(&format!("{}/items", url), PageStrategy::NextUrl).count(), 1);
+        assert_eq!(paginate(&format!("{}/items?page=1", url), PageStrategy::NextUrl).count(), 2);
+
+        let mut pages = paginate(&format!("{}/items?page=1", url), PageStrategy::NextUrl).max_pages(2);
+        assert!(pages.next().unwrap().is_ok());
+        assert!(pages.next().unwrap().is_ok());
+        assert!(pages.next().unwrap().is_err());
+        assert!(pages.next().is_none());
+        handle.join().unwrap();
+    }
+}
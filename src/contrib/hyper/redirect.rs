@@ -0,0 +1,234 @@
+//! File holding the RedirectPolicy type, configuring how the redirects answered to the outbound requests are followed
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.3
+//!
+//! ## Release notes
+//! - v1.3 : the cookies of a CookieJar are sent & stored on each hop
+//! - v1.2 : the `Authorization`, `Proxy-Authorization` & `Cookie` headers are not sent to another host
+//! - v1.1 : `send_following()` returns the final response, read by the caller
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use hyper::client::{ Client, Response };
+use hyper::header::{ ContentType, Headers, Location };
+use hyper::method::Method;
+use hyper::Url;
+
+use contrib::hyper::CookieJar;
+use error::GenericError;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Redirect policy of the outbound requests
+///
+/// - `301`, `302` & `303` redirects are followed with a `GET` request without body (a `HEAD` request staying a `HEAD`)
+/// - `307` & `308` redirects are followed with the same method & body
+/// - the `Authorization`, `Proxy-Authorization` & `Cookie` headers are removed when redirected to another host (or port)
+/// - with a CookieJar, each hop is sent with the cookies of its host & the `Set-Cookie` headers of each response are stored
+/// - the `resource` of the ResponseJSON of a redirected request is set to the final URL
+/// - following more than `max_redirects` redirects or, if `same_host_only` is set, a redirect to another host
+///   fails with a `GenericError::Upstream` error recording the location of the redirect
+///
+/// Without a RedirectPolicy the redirects are followed by hyper (without limit & with the same method)
+///
+/// ```rust,ignore
+/// let client = AmiwoClient::with_config(ClientConfig::new().redirect(RedirectPolicy::new().max_redirects(3).same_host_only(true)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RedirectPolicy {
+    max_redirects: u32,
+    same_host_only: bool,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl RedirectPolicy {
+    /// Default policy: up to 10 redirects to any host
+    pub fn new() -> RedirectPolicy {
+        RedirectPolicy {
+            max_redirects: 10,
+            same_host_only: false,
+        }
+    }
+
+    /// Policy not following the redirects, the redirect responses being returned as is
+    pub fn none() -> RedirectPolicy {
+        RedirectPolicy::new().max_redirects(0)
+    }
+
+    /// Set the maximum number of redirects followed
+    pub fn max_redirects(mut self, max_redirects: u32) -> RedirectPolicy {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    /// Only follow the redirects to the same host (& port)
+    pub fn same_host_only(mut self, same_host_only: bool) -> RedirectPolicy {
+        self.same_host_only = same_host_only;
+        self
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Send a request with `client` following the redirects according to `policy`, returns the final response & its URL if the request was redirected
+pub(crate) fn send_following(client: &Client, policy: &RedirectPolicy, mut method: Method, mut url: Url, mut headers: Headers, body: Option<&[u8]>, cookies: Option<&CookieJar>) -> Result<(Response, Option<Url>), GenericError> {
+    let mut body = body;
+    let mut redirects = 0;
+    loop {
+        let mut hop_headers = headers.clone();
+        if let Some(jar) = cookies {
+            jar.add_to(url.as_str(), &mut hop_headers);
+        }
+        let builder = client.request(method.clone(), url.clone()).headers(hop_headers);
+        let builder = match body {
+            Some(body) => builder.body(body),
+            None => builder,
+        };
+        let response = match builder.send() {
+            Ok(response) => response,
+            Err(err) if redirects > 0 => return amiwo_error!(format!("::AMIWO::CONTRIB::HYPER::REDIRECT::ERROR Redirected request to {} failed", url), GenericError::from(err)),
+            Err(err) => return Err(GenericError::from(err)),
+        };
+        if let Some(jar) = cookies {
+            jar.store(&url, &response.headers);
+        }
+
+        let status = response.status.to_u16();
+        let location = match response.headers.get::<Location>() {
+            Some(&Location(ref location)) if is_redirect(status) && policy.max_redirects > 0 => location.clone(),
+            _ => return Ok((response, if redirects > 0 { Some(url) } else { None })),
+        };
+
+        let next = url.join(&location)?;
+        if redirects >= policy.max_redirects {
+            return Err(GenericError::Upstream((status, format!("::AMIWO::CONTRIB::HYPER::REDIRECT::ERROR Too many redirects (more than {}), last location {}", policy.max_redirects, next))));
+        }
+        let other_host = next.host_str() != url.host_str() || next.port_or_known_default() != url.port_or_known_default();
+        if policy.same_host_only && other_host {
+            return Err(GenericError::Upstream((status, format!("::AMIWO::CONTRIB::HYPER::REDIRECT::ERROR Redirect from {} to another host refused, location {}", url, next))));
+        }
+        if other_host {
+            headers.remove_raw("Authorization");
+            headers.remove_raw("Proxy-Authorization");
+            headers.remove_raw("Cookie");
+        }
+
+        if status != 307 && status != 308 && method != Method::Head {
+            method = Method::Get;
+            body = None;
+            headers.remove::<ContentType>();
+        }
+        url = next;
+        redirects += 1;
+    }
+}
+
+// `true` if `status` is a redirect to the `Location` of the response
+fn is_redirect(status: u16) -> bool {
+    match status {
+        301 | 302 | 303 | 307 | 308 => true,
+        _ => false,
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Default for RedirectPolicy {
+    fn default() -> RedirectPolicy {
+        RedirectPolicy::new()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::RedirectPolicy;
+    use contrib::hyper::{ AmiwoClient, ClientConfig, RequestBuilder };
+    use contrib::hyper::tests::{ json_response, serve };
+    use error::GenericError;
+
+    fn redirect_response(status: &str, location: &str) -> String {
+        format!("HTTP/1.1 {}\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status, location)
+    }
+
+    #[test]
+    fn RedirectPolicy_test_follow() {
+        let (url, handle) = serve(vec![
+            redirect_response("303 See Other", "/users/17"),
+            json_response("200 OK", r#"{"success":true,"http_code":200}"#),
+            redirect_response("308 Permanent Redirect", "/v2/users"),
+            json_response("201 Created", r#"{"success":true,"http_code":201}"#),
+            "HTTP/1.1 302 Found\r\nLocation: /elsewhere\r\nContent-Length: 32\r\nConnection: close\r\n\r\n{\"success\":true,\"http_code\":302}".to_string(),
+        ]);
+
+        let client = AmiwoClient::with_config(ClientConfig::new().redirect(RedirectPolicy::new()));
+        let rjson = client.post_json(&format!("{}/users", url), &json!({ "name": "Boris" })).unwrap();
+        assert_eq!(rjson.resource, Some(format!("{}/users/17", url)));
+
+        let rjson = RequestBuilder::new().method("PUT").url(&format!("{}/users", url)).body("name=Boris").redirect(RedirectPolicy::new()).send().unwrap();
+        assert_eq!(rjson.http_code, 201);
+        assert_eq!(rjson.resource, Some(format!("{}/v2/users", url)));
+
+        let rjson = RequestBuilder::new().url(&url).redirect(RedirectPolicy::none()).send().unwrap();
+        assert_eq!(rjson.http_code, 302);
+        assert_eq!(rjson.resource, None);
+
+        let requests = handle.join().unwrap();
+        assert!(requests[0].starts_with("POST /users HTTP/1.1\r\n"));
+        assert!(requests[1].starts_with("GET /users/17 HTTP/1.1\r\n"));
+        assert!(!requests[1].contains("Content-Type"));
+        assert!(requests[2].starts_with("PUT /users HTTP/1.1\r\n"));
+        assert!(requests[3].starts_with("PUT /v2/users HTTP/1.1\r\n"));
+        assert!(requests[3].ends_with("\r\n\r\nname=Boris"));
+    }
+
+    #[test]
+    fn RedirectPolicy_test_errors() {
+        let (url, handle) = serve(vec![
+            redirect_response("302 Found", "/a"),
+            redirect_response("302 Found", "/b"),
+            redirect_response("301 Moved Permanently", "http://example.invalid/"),
+        ]);
+
+        match RequestBuilder::new().url(&url).redirect(RedirectPolicy::new().max_redirects(1)).send() {
+            Err(GenericError::Upstream((302, ref message))) => assert!(message.ends_with(&format!("last location {}/b", url))),
+            other => panic!("unexpected result {:?}", other),
+        }
+        match RequestBuilder::new().url(&url).redirect(RedirectPolicy::new().same_host_only(true)).send() {
+            Err(GenericError::Upstream((301, ref message))) => assert!(message.contains("to another host refused")),
+            other => panic!("unexpected result {:?}", other),
+        }
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn RedirectPolicy_test_other_host() {
+        let (other_url, other_handle) = serve(vec![json_response("200 OK", r#"{"success":true,"http_code":200}"#)]);
+        let (url, handle) = serve(vec![
+            redirect_response("302 Found", "/users"),
+            redirect_response("302 Found", &format!("{}/users", other_url)),
+        ]);
+
+        let rjson = RequestBuilder::new().url(&url).bearer("secret").header("Cookie", "session=abc").redirect(RedirectPolicy::new()).send().unwrap();
+        assert_eq!(rjson.resource, Some(format!("{}/users", other_url)));
+
+        let requests = handle.join().unwrap();
+        assert!(requests[1].contains("Authorization: Bearer secret\r\n"));
+        assert!(requests[1].contains("Cookie: session=abc\r\n"));
+        let requests = other_handle.join().unwrap();
+        assert!(!requests[0].contains("Authorization:"));
+        assert!(!requests[0].contains("Cookie:"));
+    }
+}
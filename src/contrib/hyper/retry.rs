@@ -0,0 +1,243 @@
+//! File holding the RetryPolicy type, retrying the idempotent requests failing with a retryable error
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.3
+//!
+//! ## Release notes
+//! - v1.3 : added `retry_raw()` retrying the requests whose response isn't read
+//! - v1.2 : `retry_with()` also returns the HTTP status of the last attempt
+//! - v1.1 : added `retry_with()` retrying any sending function
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::cmp;
+use std::fmt;
+use std::thread;
+use std::time::{ Duration, SystemTime, UNIX_EPOCH };
+
+use hyper::client::{ Client, Response };
+use hyper::header::Headers;
+use hyper::method::Method;
+use hyper::Url;
+
+use contrib::hyper::{ send_response, RedirectPolicy };
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Retry policy of the idempotent requests (`GET`, `HEAD`, `PUT`, `DELETE`, `OPTIONS` & `TRACE`).
+///
+/// A response with a 5xx status is handled as a `GenericError::Upstream` error: it is retried if `retry_on` accepts it,
+/// and returned as is (`Ok(ResponseJSON)`) if not.
+/// The n-th retry waits `backoff * 2^(n - 1)` minus a random jitter of up to 50%.
+/// Once all the attempts failed, the last error is returned wrapped into a `Compound` error recording the number of attempts
+///
+/// ```rust,ignore
+/// let client = AmiwoClient::new().retry(RetryPolicy::new().max_attempts(5).backoff(Duration::from_millis(200)));
+/// ```
+#[derive(Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Duration,
+    retry_on: fn(&GenericError) -> bool,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl RetryPolicy {
+    /// Default policy: 3 attempts, 100ms backoff, retrying on `GenericError::is_retryable()`
+    pub fn new() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_millis(100),
+            retry_on: GenericError::is_retryable,
+        }
+    }
+
+    /// Set the maximum number of attempts (first one included)
+    pub fn max_attempts(mut self, max_attempts: u32) -> RetryPolicy {
+        self.max_attempts = cmp::max(max_attempts, 1);
+        self
+    }
+
+    /// Set the delay before the first retry, doubled for each following retry
+    pub fn backoff(mut self, backoff: Duration) -> RetryPolicy {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set the function deciding if an error is worth a retry
+    pub fn retry_on(mut self, retry_on: fn(&GenericError) -> bool) -> RetryPolicy {
+        self.retry_on = retry_on;
+        self
+    }
+
+    // Delay before the retry following the `attempt`-th attempt
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff.as_secs() * 1000 + (self.backoff.subsec_nanos() / 1_000_000) as u64;
+        let delay = backoff.saturating_mul(1 << cmp::min(attempt - 1, 16));
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.subsec_nanos() as u64).unwrap_or(0);
+        Duration::from_millis(delay - seed % (delay / 2 + 1))
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Send a request with `client`, retried according to `policy` if any
+pub(crate) fn send_with_retry(client: &Client, policy: Option<&RetryPolicy>, redirect: Option<&RedirectPolicy>, method: Method, url: Url, headers: Headers, body: Option<&[u8]>) -> Result<ResponseJSON, GenericError> {
+    retry_with(policy, &method, &url, || send_response(client, redirect, method.clone(), url.clone(), headers.clone(), body))
+        .map(|(_, rjson)| rjson)
+}
+
+// Call `send` (sending a `method` request to `url`) until it succeeds, retried according to `policy` if any
+// & if the request is idempotent, returns the HTTP status & the ResponseJSON of the last attempt
+pub(crate) fn retry_with<F>(policy: Option<&RetryPolicy>, method: &Method, url: &Url, send: F) -> Result<(u16, ResponseJSON), GenericError>
+    where F: FnMut() -> Result<(u16, ResponseJSON), GenericError>
+{
+    retry_attempts(policy, method, url, send, |rjson| rjson.message.clone())
+}
+
+// Same as `retry_with()` for a request whose response isn't read, returns the response of the last attempt
+// (& its URL if it was redirected by the redirect policy)
+pub(crate) fn retry_raw<F>(policy: Option<&RetryPolicy>, method: &Method, url: &Url, mut send: F) -> Result<(Response, Option<Url>), GenericError>
+    where F: FnMut() -> Result<(Response, Option<Url>), GenericError>
+{
+    retry_attempts(policy, method, url, || send().map(|sent| (sent.0.status.to_u16(), sent)), |_| None)
+        .map(|(_, sent)| sent)
+}
+
+// Call `send` until it succeeds (see `retry_with()`), `message` describing a response with a 5xx status (if possible)
+fn retry_attempts<T, F, M>(policy: Option<&RetryPolicy>, method: &Method, url: &Url, mut send: F, message: M) -> Result<(u16, T), GenericError>
+    where F: FnMut() -> Result<(u16, T), GenericError>, M: Fn(&T) -> Option<String>
+{
+    let policy = match policy {
+        Some(policy) if is_idempotent(method) => policy,
+        _ => return send(),
+    };
+
+    let mut attempt = 1;
+    loop {
+        let (err, response) = match send() {
+            Ok((status, response)) if status >= 500 => {
+                let description = message(&response).unwrap_or(format!("Upstream service answered with status {}", status));
+                (GenericError::Upstream((status, description)), Some((status, response)))
+            },
+            Ok(response) => return Ok(response),
+            Err(err) => (err, None),
+        };
+
+        if !(policy.retry_on)(&err) || policy.max_attempts == 1 {
+            return match response {
+                Some(response) => Ok(response),
+                None => Err(err),
+            };
+        }
+        if attempt >= policy.max_attempts {
+            return amiwo_error!(format!("::AMIWO::CONTRIB::HYPER::RETRY::ERROR {} {} failed after {} attempts", method, url, attempt), err);
+        }
+
+        warn!("::AMIWO::CONTRIB::HYPER::RETRY::WARNING Attempt {} of {} {} failed, retrying: {}", attempt, method, url, err);
+        thread::sleep(policy.delay(attempt));
+        attempt += 1;
+    }
+}
+
+// `true` if sending the request several times has the same effect as sending it once
+fn is_idempotent(method: &Method) -> bool {
+    match *method {
+        Method::Get | Method::Head | Method::Put | Method::Delete | Method::Options | Method::Trace => true,
+        _ => false,
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::new()
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .finish()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::error::Error;
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use super::RetryPolicy;
+    use contrib::hyper::{ AmiwoClient, RequestBuilder };
+    use contrib::hyper::tests::{ json_response, serve };
+    use error::{ ErrorKind, GenericError };
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new().backoff(Duration::from_millis(1))
+    }
+
+    #[test]
+    fn RetryPolicy_test_retry() {
+        let (url, handle) = serve(vec![
+            json_response("503 Service Unavailable", r#"{"success":false,"http_code":503,"message":"down"}"#),
+            json_response("200 OK", r#"{"success":true,"http_code":200}"#),
+            json_response("503 Service Unavailable", r#"{"success":false,"http_code":503,"message":"down"}"#),
+            json_response("502 Bad Gateway", r#"{"success":false,"http_code":502,"message":"down"}"#),
+            json_response("503 Service Unavailable", r#"{"success":false,"http_code":503,"message":"down"}"#),
+        ]);
+
+        let client = AmiwoClient::new().retry(policy());
+        assert!(client.request("GET", &url).unwrap().success);
+        // POST isn't idempotent
+        assert_eq!(client.request("POST", &url).unwrap().http_code, 503);
+
+        let err = RequestBuilder::new().url(&url).retry(policy().max_attempts(2)).send().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Compound);
+        assert!(err.description().contains("failed after 2 attempts"));
+        match err {
+            GenericError::Compound((_, ref cause)) => match **cause {
+                GenericError::Upstream((503, ref message)) => assert_eq!(message, "down"),
+                ref cause => panic!("unexpected cause {:?}", cause),
+            },
+            ref err => panic!("unexpected error {:?}", err),
+        }
+
+        assert_eq!(handle.join().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn RetryPolicy_test_retry_on() {
+        fn never(_: &GenericError) -> bool { false }
+
+        let (url, handle) = serve(vec![json_response("500 Internal Server Error", r#"{"success":false,"http_code":500,"message":"bug"}"#)]);
+        let rjson = RequestBuilder::new().url(&url).retry(policy().retry_on(never)).send().unwrap();
+        assert_eq!(rjson.http_code, 500);
+        handle.join().unwrap();
+
+        // nobody listens on the port anymore => connection refused
+        let url = format!("http://{}", TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap());
+        let err = RequestBuilder::new().url(&url).retry(policy()).send().unwrap_err();
+        assert!(err.description().contains("failed after 3 attempts"));
+        assert!(err.is_transient());
+
+        assert!(policy().delay(3) <= Duration::from_millis(4) && policy().delay(3) >= Duration::from_millis(2));
+    }
+}
@@ -0,0 +1,238 @@
+//! JSON:API output format adapter
+//!
+//! Renders a `ResponseJSON` into a [JSON:API](http://jsonapi.org/format/) compliant document:
+//!
+//! - OK ResponseJSON => `data` holds one resource object (or an array of them if `data` is an array). 
+//! The `id` key of each object becomes the resource id, its `relationships` key (if any) becomes the resource relationships 
+//! and all other keys become the resource attributes
+//! - Error ResponseJSON => `errors` holds one error object built from `http_code`, `error_code` & `message`
+//! - Pagination fields are rendered as top-level `meta` (`page`, `per_page`, `total`, `total_pages`) & `links` (`next`, `prev`)
+//!
+//! `JsonApiDocument` implements Rocket's `Responder` with Content-Type `application/vnd.api+json`
+//!
+//! ```rust,ignore
+//! #[get("/users/<id>")]
+//! fn user(id: u32) -> JsonApiDocument {
+//!     ResponseJSON::ok().data(json!({ "id": id, "name": "Boris" })).into_jsonapi("users")
+//! }
+//! ```
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use rocket::Response;
+use rocket::http::{ ContentType, Status };
+use rocket::response::content;
+use rocket::response::Responder;
+use serde::Serialize;
+use serde_json;
+use serde_json::Value;
+use serde_json::map::Map;
+
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// A JSON:API top-level document
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct JsonApiDocument {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<JsonApiError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Map<String, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Map<String, Value>>,
+    #[serde(skip_serializing)]
+    http_code: u16,
+}
+
+/// A JSON:API error object
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct JsonApiError {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Map<String, Value>>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl<T: Serialize> ResponseJSON<T> {
+    /// Render this ResponseJSON as a JSON:API document, `resource_type` being the `type` of the resource objects in `data`
+    pub fn into_jsonapi(self, resource_type: &str) -> JsonApiDocument {
+        let mut meta = Map::new();
+        if let Some(page) = self.page { meta.insert("page".to_string(), Value::from(page)); }
+        if let Some(per_page) = self.per_page { meta.insert("per_page".to_string(), Value::from(per_page)); }
+        if let Some(total) = self.total { meta.insert("total".to_string(), Value::from(total)); }
+        if let Some(total_pages) = self.total_pages { meta.insert("total_pages".to_string(), Value::from(total_pages)); }
+
+        let mut links = Map::new();
+        if let Some(ref next) = self.next { links.insert("next".to_string(), Value::String(next.clone())); }
+        if let Some(ref prev) = self.prev { links.insert("prev".to_string(), Value::String(prev.clone())); }
+
+        let (data, errors) = if self.success {
+            let data = serde_json::to_value(&self.data).unwrap_or(Value::Null);
+            (Some(to_resource_data(data, resource_type)), None)
+        } else {
+            let mut error_meta = Map::new();
+            if let Some(ref method) = self.method { error_meta.insert("method".to_string(), Value::String(method.clone())); }
+            if let Some(ref resource) = self.resource { error_meta.insert("resource".to_string(), Value::String(resource.clone())); }
+
+            (None, Some(vec![JsonApiError {
+                status: self.http_code.to_string(),
+                code: self.error_code.clone(),
+                title: Status::from_code(self.http_code).map_or("Unknown error", |status| status.reason).to_string(),
+                detail: self.message.clone(),
+                meta: if error_meta.is_empty() { None } else { Some(error_meta) },
+            }]))
+        };
+
+        JsonApiDocument {
+            data: data,
+            errors: errors,
+            meta: if meta.is_empty() { None } else { Some(meta) },
+            links: if links.is_empty() { None } else { Some(links) },
+            http_code: self.http_code,
+        }
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Convert `data` into a resource object (or an array of resource objects)
+fn to_resource_data(data: Value, resource_type: &str) -> Value {
+    match data {
+        Value::Null => Value::Null,
+        Value::Array(vect) => Value::Array(vect.into_iter().map(|item| to_resource_object(item, resource_type)).collect()),
+        item @ _ => to_resource_object(item, resource_type),
+    }
+}
+
+// Convert `item` into a resource object: `id` & `relationships` keys are moved out of the attributes
+fn to_resource_object(item: Value, resource_type: &str) -> Value {
+    let mut resource = Map::new();
+    resource.insert("type".to_string(), Value::String(resource_type.to_string()));
+
+    match item {
+        Value::Object(mut attributes) => {
+            match attributes.remove("id") {
+                Some(Value::String(id)) => { resource.insert("id".to_string(), Value::String(id)); },
+                Some(Value::Null) | None => (),
+                Some(id) => { resource.insert("id".to_string(), Value::String(id.to_string())); },
+            }
+            if let Some(relationships) = attributes.remove("relationships") {
+                resource.insert("relationships".to_string(), relationships);
+            }
+            resource.insert("attributes".to_string(), Value::Object(attributes));
+        },
+        value @ _ => {
+            let mut attributes = Map::new();
+            attributes.insert("value".to_string(), value);
+            resource.insert("attributes".to_string(), Value::Object(attributes));
+        },
+    }
+    Value::Object(resource)
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+/// Serializes the document with Content-Type `application/vnd.api+json` and the `http_code` of the original ResponseJSON as HTTP status
+impl<'r> Responder<'r> for JsonApiDocument {
+    fn respond(self) -> Result<Response<'r>, Status> {
+        let status = Status::from_code(self.http_code).unwrap_or(Status::new(self.http_code, "<unknown code>"));
+        serde_json::to_string(&self)
+            .map_err(|err| {
+                error!("::AMIWO::CONTRIB::JSONAPI::RESPOND::ERROR Unable to serialize JSON:API document => {:?}", err);
+                Status::InternalServerError
+            })
+            .and_then(|body| content::Content(ContentType::new("application", "vnd.api+json"), body).respond())
+            .map(|mut response| {
+                response.set_status(status);
+                response
+            })
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use serde_json;
+
+    use rocket::http::Status;
+    use rocket::response::Responder;
+
+    use types::ResponseJSON;
+
+    #[test]
+    fn JsonApiDocument_test_single_resource() {
+        let doc = ResponseJSON::ok()
+            .data(json!({
+                "id": 17,
+                "name": "Boris",
+                "relationships": { "company": { "data": { "type": "companies", "id": "1" } } }
+            }))
+            .into_jsonapi("users");
+
+        assert_eq!(serde_json::to_value(&doc).unwrap(), json!({
+            "data": {
+                "type": "users",
+                "id": "17",
+                "attributes": { "name": "Boris" },
+                "relationships": { "company": { "data": { "type": "companies", "id": "1" } } }
+            }
+        }));
+    }
+
+    #[test]
+    fn JsonApiDocument_test_collection() {
+        let doc = ResponseJSON::ok()
+            .paginated(json!([ { "id": "a", "v": 1 }, { "id": "b", "v": 2 } ]), 1, 2, 3)
+            .next("/items?page=2".to_string())
+            .into_jsonapi("items");
+
+        assert_eq!(serde_json::to_value(&doc).unwrap(), json!({
+            "data": [
+                { "type": "items", "id": "a", "attributes": { "v": 1 } },
+                { "type": "items", "id": "b", "attributes": { "v": 2 } }
+            ],
+            "meta": { "page": 1, "per_page": 2, "total": 3, "total_pages": 2 },
+            "links": { "next": "/items?page=2" }
+        }));
+    }
+
+    #[test]
+    fn JsonApiDocument_test_error() {
+        let doc = ResponseJSON::error()
+            .http_code(404)
+            .message("No user with id 17".to_string())
+            .resource("/users/17".to_string())
+            .into_jsonapi("users");
+
+        assert_eq!(serde_json::to_value(&doc).unwrap(), json!({
+            "errors": [ {
+                "status": "404",
+                "title": "Not Found",
+                "detail": "No user with id 17",
+                "meta": { "resource": "/users/17" }
+            } ]
+        }));
+
+        let response = doc.respond().unwrap();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.header_values("Content-Type").next(), Some("application/vnd.api+json"));
+    }
+}
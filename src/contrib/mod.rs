@@ -2,4 +2,13 @@
 pub mod rocket;
 
 #[cfg(feature = "amiwo_hyper")]
-pub mod hyper;
\ No newline at end of file
+pub mod hyper;
+
+#[cfg(feature = "rest-reqwest")]
+pub mod reqwest;
+
+#[cfg(feature = "amiwo_jsonapi")]
+pub mod jsonapi;
+
+#[cfg(feature = "amiwo_schema")]
+pub mod schema;
\ No newline at end of file
@@ -0,0 +1,139 @@
+//! Utility functions for `reqwest` crate, mirroring `contrib::hyper` to ease the migration off hyper 0.x
+//!
+//! Enabled by the `rest-reqwest` feature. The functions & the `RequestBuilder` have the same names & signatures as their
+//! `contrib::hyper` counterparts, the headers being a `reqwest::header::HeaderMap`.
+//! The responses compressed with gzip are decoded by `reqwest`
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.2
+//!
+//! ## Release notes
+//! - v1.2 : upgraded to reqwest 0.9, the errors being converted into `GenericError::Timeout` or `GenericError::Io`
+//! - v1.1 : the timeouts & I/O errors are classified like in `contrib::hyper` (`GenericError::Timeout`, `GenericError::Hyper`)
+//! - v1.0 : creation
+
+mod request_builder;
+
+pub use self::request_builder::RequestBuilder;
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use reqwest;
+use reqwest::header::{ HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE };
+use reqwest::{ Method, Url };
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Send a simple `method` request to `url` and pre-process the response to try to build a `ResponseJSON` from it
+///
+/// A new `reqwest::Client` is created for each call
+pub fn request(method: &str, url: &str) -> Result<ResponseJSON, GenericError> {
+    request_with_headers(method, url, HeaderMap::new(), None)
+}
+
+/// Send a simple `method` request to `url` and deserialize the response into `T` (see `contrib::hyper::request_as()`)
+pub fn request_as<T: DeserializeOwned>(method: &str, url: &str) -> Result<T, GenericError> {
+    request(method, url).and_then(ResponseJSON::into_typed)
+}
+
+/// Send a `method` request with `body` to `url` and pre-process the response to try to build a `ResponseJSON` from it
+pub fn request_with_body(method: &str, url: &str, body: &str) -> Result<ResponseJSON, GenericError> {
+    request_with_headers(method, url, HeaderMap::new(), Some(body))
+}
+
+/// Send a `method` request with `headers` (and `body` if any) to `url` and pre-process the response to try to build a `ResponseJSON` from it
+pub fn request_with_headers(method: &str, url: &str, headers: HeaderMap, body: Option<&str>) -> Result<ResponseJSON, GenericError> {
+    let (method, url) = parse_target(method, url)?;
+    send_request(&reqwest::Client::new(), method, url, headers, body.map(|body| body.as_bytes().to_vec()))
+}
+
+/// Send a `POST` request to `url` with `body` serialized as JSON (`Content-Type: application/json`)
+/// and pre-process the response to try to build a `ResponseJSON` from it
+pub fn post_json<T: Serialize>(url: &str, body: &T) -> Result<ResponseJSON, GenericError> {
+    post_json_with_headers(url, body, HeaderMap::new())
+}
+
+/// Same as `post_json()` with additional `headers` (`Content-Type` being always set to `application/json`)
+pub fn post_json_with_headers<T: Serialize>(url: &str, body: &T, mut headers: HeaderMap) -> Result<ResponseJSON, GenericError> {
+    let url = Url::parse(url)?;
+    let body = serde_json::to_vec(body)?;
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    send_request(&reqwest::Client::new(), Method::POST, url, headers, Some(body))
+}
+
+/// Build a `HeaderMap` from `(name, value)` pairs, a name repeated adding a value to the header.
+/// The pairs whose name or value isn't valid in a HTTP header are skipped (& logged)
+pub fn build_headers(pairs: &[(&str, &str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for &(name, value) in pairs {
+        match header(name, value) {
+            Ok((name, value)) => { headers.append(name, value); },
+            Err(err) => warn!("{}", err),
+        }
+    }
+    headers
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Parse the method & url of a request
+pub(crate) fn parse_target(method: &str, url: &str) -> Result<(Method, Url), GenericError> {
+    let method = Method::from_bytes(method.to_uppercase().as_bytes())
+        .or_else(|_| amiwo_error!(format!("::AMIWO::CONTRIB::REQWEST::ERROR Invalid method `{}`", method)))?;
+    let url = Url::parse(url)?;
+    Ok((method, url))
+}
+
+// Parse the name & value of a header
+pub(crate) fn header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue), GenericError> {
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .or_else(|_| amiwo_error!(format!("::AMIWO::CONTRIB::REQWEST::ERROR Invalid header name `{}`", name)))?;
+    let value = HeaderValue::from_str(value)
+        .or_else(|_| amiwo_error!(format!("::AMIWO::CONTRIB::REQWEST::ERROR Invalid value of the header `{}`", name)))?;
+    Ok((name, value))
+}
+
+// Send a request with `client` and pre-process the response to try to build a `ResponseJSON` from it
+pub(crate) fn send_request(client: &reqwest::Client, method: Method, url: Url, headers: HeaderMap, body: Option<Vec<u8>>) -> Result<ResponseJSON, GenericError> {
+    let mut builder = client.request(method, url).headers(headers);
+    if let Some(body) = body {
+        builder = builder.body(body);
+    }
+    let response = builder.send()?;
+    ResponseJSON::from_reader(response)
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ build_headers, parse_target, request };
+
+    #[test]
+    fn reqwest_test_build_headers() {
+        let headers = build_headers(&[("Accept", "application/json"), ("X-Tag", "a"), ("X-Tag", "b")]);
+        assert_eq!(headers.keys_len(), 2);
+        assert_eq!(headers.get_all("X-Tag").iter().count(), 2);
+
+        assert_eq!(build_headers(&[("X Tag", "a"), ("X-Tag", "b\n"), ("X-Tag", "c")]).len(), 1);
+    }
+
+    #[test]
+    fn reqwest_test_errors() {
+        assert!(parse_target("get", "http://localhost/users").is_ok());
+        assert!(parse_target("GE,T", "http://localhost").is_err());
+        assert!(request("GET", "not an url").is_err());
+    }
+}
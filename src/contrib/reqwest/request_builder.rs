@@ -0,0 +1,238 @@
+//! File holding the RequestBuilder type, a fluent builder of outbound requests sent with `reqwest` & answered with a ResponseJSON
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.1
+//!
+//! ## Release notes
+//! - v1.1 : upgraded to reqwest 0.9, an invalid header being reported by `send()`
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::time::Duration;
+
+use hyper::header::{ Authorization, Basic, HeaderFormatter };
+use reqwest;
+use reqwest::header::{ HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE };
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use contrib::reqwest::{ header, parse_target, send_request };
+use error::GenericError;
+use types::ResponseJSON;
+use util::UrlBuilder;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Fluent builder of an outbound request whose response is pre-processed into a `ResponseJSON`,
+/// same as `contrib::hyper::RequestBuilder`
+///
+/// The errors of the builder methods (e.g. `body_json()` failing to serialize its input, or an invalid header) are reported by `send()`
+///
+/// ```rust,ignore
+/// let rjson = amiwo::contrib::reqwest::RequestBuilder::new()
+///     .method("POST")
+///     .url("http://localhost:8000/users")
+///     .bearer("abc")
+///     .body_json(&json!({ "name": "Boris" }))
+///     .timeout(Duration::from_secs(5))
+///     .send()?;
+/// ```
+#[derive(Debug)]
+pub struct RequestBuilder {
+    method: String,
+    url: String,
+    query: Vec<(String, String)>,
+    headers: HeaderMap,
+    body: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+    error: Option<GenericError>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl RequestBuilder {
+    /// Create a builder of a `GET` request (the url must be set with `url()`)
+    pub fn new() -> RequestBuilder {
+        RequestBuilder {
+            method: "GET".to_string(),
+            url: String::new(),
+            query: Vec::new(),
+            headers: HeaderMap::new(),
+            body: None,
+            timeout: None,
+            error: None,
+        }
+    }
+
+    /// Set the HTTP method of the request (case insensitive)
+    pub fn method(mut self, method: &str) -> RequestBuilder {
+        self.method = method.to_string();
+        self
+    }
+
+    /// Set the url of the request
+    pub fn url(mut self, url: &str) -> RequestBuilder {
+        self.url = url.to_string();
+        self
+    }
+
+    /// Set the url of the request to the url built by `builder` (see `util::UrlBuilder`)
+    pub fn url_builder(mut self, builder: UrlBuilder) -> RequestBuilder {
+        match builder.build() {
+            Ok(url) => self.url = url,
+            Err(err) => if self.error.is_none() {
+                self.error = Some(err);
+            },
+        }
+        self
+    }
+
+    /// Append the `key=value` pair (URL encoded) to the query string of the url
+    pub fn query<K: ToString, V: ToString>(mut self, key: K, value: V) -> RequestBuilder {
+        self.query.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add a header to the request, a name repeated adding a value to the header
+    pub fn header(mut self, name: &str, value: &str) -> RequestBuilder {
+        match header(name, value) {
+            Ok((name, value)) => { self.headers.append(name, value); },
+            Err(err) => if self.error.is_none() {
+                self.error = Some(err);
+            },
+        }
+        self
+    }
+
+    /// Set the `Authorization: Bearer <token>` header
+    pub fn bearer(self, token: &str) -> RequestBuilder {
+        self.authorization(format!("Bearer {}", token))
+    }
+
+    /// Set the `Authorization: Basic <base64 of user:password>` header
+    pub fn basic(self, user: &str, password: &str) -> RequestBuilder {
+        let credentials = Authorization(Basic { username: user.to_string(), password: Some(password.to_string()) });
+        self.authorization(HeaderFormatter(&credentials).to_string())
+    }
+
+    /// Set the body of the request
+    pub fn body(mut self, body: &str) -> RequestBuilder {
+        self.body = Some(body.as_bytes().to_vec());
+        self
+    }
+
+    /// Set the body of the request to `body` serialized as JSON, with `Content-Type: application/json`
+    pub fn body_json<T: Serialize>(mut self, body: &T) -> RequestBuilder {
+        match serde_json::to_vec(body) {
+            Ok(body) => {
+                self.headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                self.body = Some(body);
+            },
+            Err(err) => if self.error.is_none() {
+                self.error = Some(GenericError::new_compound("::AMIWO::CONTRIB::REQWEST::REQUEST_BUILDER::BODY_JSON::ERROR Unable to serialize the body", GenericError::from(err)));
+            },
+        }
+        self
+    }
+
+    /// Set the timeout of the request
+    pub fn timeout(mut self, timeout: Duration) -> RequestBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send the request and pre-process the response to try to build a `ResponseJSON` from it
+    pub fn send(self) -> Result<ResponseJSON, GenericError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+
+        let (method, mut url) = parse_target(&self.method, &self.url)?;
+        if !self.query.is_empty() {
+            let mut query = url.query_pairs_mut();
+            for &(ref key, ref value) in &self.query {
+                query.append_pair(key, value);
+            }
+        }
+
+        let client = match self.timeout {
+            Some(timeout) => reqwest::Client::builder().timeout(timeout).build()?,
+            None => reqwest::Client::new(),
+        };
+        send_request(&client, method, url, self.headers, self.body)
+    }
+
+    /// Send the request and deserialize the response into `T` (see `contrib::hyper::request_as()`)
+    pub fn send_as<T: DeserializeOwned>(self) -> Result<T, GenericError> {
+        self.send().and_then(ResponseJSON::into_typed)
+    }
+
+    // Set the `Authorization` header to `value`
+    fn authorization(mut self, value: String) -> RequestBuilder {
+        match HeaderValue::from_str(&value) {
+            Ok(value) => { self.headers.insert(AUTHORIZATION, value); },
+            Err(_) => if self.error.is_none() {
+                self.error = Some(GenericError::from("::AMIWO::CONTRIB::REQWEST::REQUEST_BUILDER::AUTHORIZATION::ERROR Invalid credentials".to_string()));
+            },
+        }
+        self
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Default for RequestBuilder {
+    fn default() -> RequestBuilder {
+        RequestBuilder::new()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::collections::BTreeMap;
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use super::RequestBuilder;
+    use error::ErrorKind;
+
+    #[test]
+    fn RequestBuilder_test_errors() {
+        assert!(RequestBuilder::new().send().is_err());
+        assert!(RequestBuilder::new().method("GE,T").url("http://localhost").send().is_err());
+
+        let mut map = BTreeMap::new();
+        map.insert(vec![1], 1);
+        let err = RequestBuilder::new().url("http://localhost").body_json(&map).send().unwrap_err();
+        assert_eq!(err.http_code(), 500);
+        assert!(RequestBuilder::new().url("http://localhost").header("X-Tag", "a\nb").send().is_err());
+        assert!(RequestBuilder::new().url("http://localhost").bearer("a\nb").send().is_err());
+    }
+
+    #[test]
+    fn RequestBuilder_test_timeout() {
+        // the listener accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let err = RequestBuilder::new().url(&format!("http://{}", listener.local_addr().unwrap())).timeout(Duration::from_millis(50)).send().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Timeout);
+        assert!(err.is_transient());
+        assert_eq!(err.http_code(), 504);
+
+        // nothing listens on the port anymore
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+        let err = RequestBuilder::new().url(&format!("http://{}", address)).send().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+    }
+}
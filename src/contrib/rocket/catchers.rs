@@ -0,0 +1,140 @@
+//! File holding Rocket error catchers responding with an error ResponseJSON instead of Rocket's HTML error pages
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.1
+//!
+//! ## Release notes
+//! - v1.1 : added a catcher for 413
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use rocket::{ Catcher, Error, Request };
+use rocket::http::Status;
+use rocket::response;
+use rocket::response::Responder;
+
+use contrib::rocket::RequestId;
+use types::ResponseJSON;
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Returns catchers for 400, 401, 403, 404, 413, 422 & 500 responding with an error ResponseJSON
+/// whose `method` & `resource` are those of the failed request and `message` is the reason of the status.
+/// The `X-Request-Id` header of the request (if any) is copied into `request_id`
+///
+/// ```rust,ignore
+/// rocket::ignite()
+///     .mount("/", routes![...])
+///     .catch(amiwo::contrib::rocket::catchers())
+///     .launch();
+/// ```
+pub fn catchers() -> Vec<Catcher> {
+    vec![
+        Catcher::new(400, handle_400),
+        Catcher::new(401, handle_401),
+        Catcher::new(403, handle_403),
+        Catcher::new(404, handle_404),
+        Catcher::new(413, handle_413),
+        Catcher::new(422, handle_422),
+        Catcher::new(500, handle_500),
+    ]
+}
+
+/// Build the error ResponseJSON describing the failure of `request` with `code`
+pub fn error_response(code: u16, request: &Request) -> ResponseJSON {
+    let reason = Status::from_code(code).map_or("Unexpected error", |status| status.reason);
+    let rjson = ResponseJSON::error()
+        .http_code(code)
+        .message(reason.to_string())
+        .method(request.method().to_string())
+        .resource(request.uri().as_str().to_string());
+
+    match request.headers().get_one(RequestId::HEADER) {
+        Some(id) => rjson.request_id(id.to_string()),
+        None => rjson,
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+fn handle_400<'r>(_: Error, request: &'r Request) -> response::Result<'r> {
+    error_response(400, request).respond()
+}
+
+fn handle_401<'r>(_: Error, request: &'r Request) -> response::Result<'r> {
+    error_response(401, request).respond()
+}
+
+fn handle_403<'r>(_: Error, request: &'r Request) -> response::Result<'r> {
+    error_response(403, request).respond()
+}
+
+fn handle_404<'r>(_: Error, request: &'r Request) -> response::Result<'r> {
+    error_response(404, request).respond()
+}
+
+fn handle_413<'r>(_: Error, request: &'r Request) -> response::Result<'r> {
+    error_response(413, request).respond()
+}
+
+fn handle_422<'r>(_: Error, request: &'r Request) -> response::Result<'r> {
+    error_response(422, request).respond()
+}
+
+fn handle_500<'r>(_: Error, request: &'r Request) -> response::Result<'r> {
+    error_response(500, request).respond()
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use rocket::Request;
+    use rocket::http::{ Header, Method, Status };
+
+    use types::ResponseJSON;
+
+    use super::{ catchers, error_response };
+
+    #[test]
+    fn catchers_test_codes() {
+        let codes : Vec<u16> = catchers().iter().map(|catcher| catcher.code).collect();
+        assert_eq!(codes, vec![400, 401, 403, 404, 413, 422, 500]);
+    }
+
+    #[test]
+    fn catchers_test_error_response() {
+        let request = Request::new(Method::Post, "/users/17?full=true");
+        let json = error_response(404, &request);
+        assert_eq!(json.success, false);
+        assert_eq!(json.http_code, 404);
+        assert_eq!(json.message, Some("Not Found".to_string()));
+        assert_eq!(json.method, Some("POST".to_string()));
+        assert_eq!(json.resource, Some("/users/17?full=true".to_string()));
+        assert_eq!(json.request_id, None);
+
+        let mut request = Request::new(Method::Get, "/");
+        request.add_header(Header::new("X-Request-Id", "abc-123"));
+        let json = error_response(422, &request);
+        assert_eq!(json.message, Some("Unprocessable Entity".to_string()));
+        assert_eq!(json.request_id, Some("abc-123".to_string()));
+    }
+
+    #[test]
+    fn catchers_test_respond() {
+        use rocket::response::Responder;
+
+        let request = Request::new(Method::Get, "/missing");
+        let mut response = error_response(404, &request).respond().unwrap();
+        assert_eq!(response.status(), Status::NotFound);
+        let body_str = response.body().and_then(|b| b.into_string()).unwrap();
+        assert_eq!(ResponseJSON::from_str(&body_str).unwrap().resource, Some("/missing".to_string()));
+    }
+}
@@ -0,0 +1,273 @@
+//! File holding the types used to compress (gzip / deflate) responses
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.1
+//!
+//! ## Release notes
+//! - v1.1 : `AcceptEncoding::parse()` picks the encoding with the highest `q` weight
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::io::{ Cursor, Write };
+
+use flate2::Compression;
+use flate2::write::{ GzEncoder, ZlibEncoder };
+
+use rocket;
+use rocket::{ Request, Response };
+use rocket::http::Status;
+use rocket::request::FromRequest;
+use rocket::response::Responder;
+
+// =======================================================================
+// CONSTANTS
+// =======================================================================
+/// Default minimum size (in bytes) of a body to be compressed
+pub const DEFAULT_MIN_COMPRESSION_SIZE: usize = 1024;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Encodings supported by the `Compressed` responder
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+    Identity,
+}
+
+/// A request guard holding the preferred encoding accepted by the client (read from the `Accept-Encoding` header):
+/// the encoding with the highest `q` weight, `gzip` being preferred over `deflate` for the same weight
+///
+/// ```rust,ignore
+/// #[get("/items")]
+/// fn items(accept_encoding: AcceptEncoding) -> Compressed<ResponseJSON> { 
+///     accept_encoding.compress(ResponseJSON::ok().data(...))
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AcceptEncoding(Encoding);
+
+/// A responder wrapper compressing the body of the wrapped responder with the given encoding,
+/// if the body is at least `min_size` bytes long
+#[derive(Debug)]
+pub struct Compressed<R> {
+    responder: R,
+    encoding: Encoding,
+    min_size: usize,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl Encoding {
+    /// Returns the value of the `Content-Encoding` header for this encoding
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Identity => "identity",
+        }
+    }
+}
+
+impl AcceptEncoding {
+    /// Parse the value of an `Accept-Encoding` header, picking the supported encoding with the highest weight
+    /// (`q=1` if not set, `*` giving its weight to the encodings not listed). Encodings with a `q=0` weight are refused,
+    /// `identity` is picked only if its weight is higher than the one of `gzip` & `deflate`
+    pub fn parse(header: &str) -> AcceptEncoding {
+        let weights : Vec<(String, f32)> = header.split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';').map(|part| part.trim());
+                let name = parts.next().unwrap_or("").to_lowercase();
+                let weight = parts
+                    .filter(|param| param.starts_with("q="))
+                    .filter_map(|param| param[2..].parse::<f32>().ok())
+                    .next()
+                    .unwrap_or(1.0);
+                if name.is_empty() { None } else { Some((name, weight)) }
+            })
+            .collect();
+        let weight_of = |name: &str| weights.iter()
+            .find(|&&(ref accepted, _)| accepted == name)
+            .or_else(|| weights.iter().find(|&&(ref accepted, _)| accepted == "*"))
+            .map_or(0.0, |&(_, weight)| weight);
+
+        let mut preferred = (Encoding::Identity, 0.0);
+        for &encoding in &[Encoding::Gzip, Encoding::Deflate] {
+            let weight = weight_of(encoding.as_str());
+            if weight > preferred.1 {
+                preferred = (encoding, weight);
+            }
+        }
+        let identity = weights.iter().find(|&&(ref accepted, _)| accepted == "identity").map_or(0.0, |&(_, weight)| weight);
+        if identity > preferred.1 {
+            preferred = (Encoding::Identity, identity);
+        }
+        AcceptEncoding(preferred.0)
+    }
+
+    /// Returns the preferred encoding
+    pub fn encoding(&self) -> Encoding {
+        self.0
+    }
+
+    /// Wrap `responder` so its body is compressed with the preferred encoding
+    pub fn compress<R>(&self, responder: R) -> Compressed<R> {
+        Compressed::new(responder, self.0)
+    }
+}
+
+impl<R> Compressed<R> {
+    /// Wrap `responder` so its body is compressed with `encoding`
+    pub fn new(responder: R, encoding: Encoding) -> Compressed<R> {
+        Compressed {
+            responder: responder,
+            encoding: encoding,
+            min_size: DEFAULT_MIN_COMPRESSION_SIZE,
+        }
+    }
+
+    /// Set the minimum size (in bytes) of the body to be compressed. Smaller bodies are sent as is
+    pub fn min_size(mut self, bytes: usize) -> Compressed<R> {
+        self.min_size = bytes;
+        self
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+fn encode(bytes: &[u8], encoding: Encoding) -> ::std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::Default);
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        },
+        Encoding::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::Default);
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        },
+        Encoding::Identity => Ok(bytes.to_vec()),
+    }
+}
+
+// =======================================================================
+// EXTERNAL TRAITS IMPLEMENTATION
+// =======================================================================
+/// Reads the `Accept-Encoding` header. Never fails nor forwards
+impl<'a, 'r> FromRequest<'a, 'r> for AcceptEncoding {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> rocket::request::Outcome<Self, Self::Error> {
+        let header = request.headers().get("Accept-Encoding").collect::<Vec<_>>().join(",");
+        rocket::Outcome::Success(AcceptEncoding::parse(&header))
+    }
+}
+
+/// Responds with the wrapped responder, compressing its body if it is large enough and the encoding isn't `Identity`.
+/// Sets `Content-Encoding` & `Vary: Accept-Encoding` when the body is compressed
+impl<'r, R: Responder<'r>> Responder<'r> for Compressed<R> {
+    fn respond(self) -> Result<Response<'r>, Status> {
+        let mut response = self.responder.respond()?;
+        if self.encoding == Encoding::Identity {
+            return Ok(response);
+        }
+
+        let bytes = match response.take_body().and_then(|body| body.into_bytes()) {
+            Some(bytes) => bytes,
+            None => return Ok(response),
+        };
+
+        if bytes.len() < self.min_size {
+            response.set_sized_body(Cursor::new(bytes));
+            return Ok(response);
+        }
+
+        match encode(&bytes, self.encoding) {
+            Ok(compressed) => {
+                response.set_raw_header("Content-Encoding", self.encoding.as_str());
+                response.set_raw_header("Vary", "Accept-Encoding");
+                response.set_sized_body(Cursor::new(compressed));
+            },
+            Err(err) => {
+                warn!("::AMIWO::CONTRIB::ROCKET::COMPRESSED::RESPOND::WARNING Unable to compress body => sent uncompressed [error = {:?}]", err);
+                response.set_sized_body(Cursor::new(bytes));
+            }
+        }
+        Ok(response)
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::{ AcceptEncoding, Encoding };
+
+    use rocket::http::Status;
+    use rocket::response::Responder;
+
+    use types::ResponseJSON;
+
+    #[test]
+    fn Compressed_test_accept_encoding() {
+        assert_eq!(AcceptEncoding::parse("gzip, deflate, br").encoding(), Encoding::Gzip);
+        assert_eq!(AcceptEncoding::parse("deflate").encoding(), Encoding::Deflate);
+        assert_eq!(AcceptEncoding::parse("gzip;q=0, deflate;q=0.5").encoding(), Encoding::Deflate);
+        assert_eq!(AcceptEncoding::parse("gzip;q=0.8").encoding(), Encoding::Gzip);
+        assert_eq!(AcceptEncoding::parse("gzip;q=0.5, deflate").encoding(), Encoding::Deflate);
+        assert_eq!(AcceptEncoding::parse("deflate;q=0.8, gzip;q=0.9").encoding(), Encoding::Gzip);
+        assert_eq!(AcceptEncoding::parse("deflate, gzip").encoding(), Encoding::Gzip);
+        assert_eq!(AcceptEncoding::parse("*;q=0.5, deflate").encoding(), Encoding::Deflate);
+        assert_eq!(AcceptEncoding::parse("*;q=0.5").encoding(), Encoding::Gzip);
+        assert_eq!(AcceptEncoding::parse("identity, gzip;q=0.5").encoding(), Encoding::Identity);
+        assert_eq!(AcceptEncoding::parse("br").encoding(), Encoding::Identity);
+        assert_eq!(AcceptEncoding::parse("").encoding(), Encoding::Identity);
+    }
+
+    #[test]
+    fn Compressed_test_gzip() {
+        let json = ResponseJSON::ok().data(json!((0..500).collect::<Vec<u32>>()));
+        let expected = json.to_string();
+
+        let mut response = AcceptEncoding::parse("gzip").compress(json).respond().unwrap();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.header_values("Content-Encoding").next(), Some("gzip"));
+        assert_eq!(response.header_values("Content-Type").next(), Some("application/json"));
+
+        let bytes = response.body().and_then(|b| b.into_bytes()).unwrap();
+        let mut body = String::new();
+        GzDecoder::new(&bytes[..]).unwrap().read_to_string(&mut body).unwrap();
+        assert_eq!(body, expected);
+    }
+
+    #[test]
+    fn Compressed_test_below_threshold() {
+        let json = ResponseJSON::ok().data(json!([1, 2, 3]));
+        let expected = json.to_string();
+
+        let mut response = AcceptEncoding::parse("gzip").compress(json).respond().unwrap();
+        assert!(response.header_values("Content-Encoding").next().is_none());
+        assert_eq!(response.body().and_then(|b| b.into_string()), Some(expected));
+
+        let json = ResponseJSON::ok().data(json!([1, 2, 3]));
+        let response = AcceptEncoding::parse("gzip").compress(json).min_size(0).respond().unwrap();
+        assert_eq!(response.header_values("Content-Encoding").next(), Some("gzip"));
+
+        let json = ResponseJSON::ok().data(json!([1, 2, 3]));
+        let response = AcceptEncoding::parse("br").compress(json).min_size(0).respond().unwrap();
+        assert!(response.header_values("Content-Encoding").next().is_none());
+    }
+}
@@ -0,0 +1,235 @@
+//! File holding the CsvResponse type streaming the array data of a ResponseJSON as CSV
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::cmp;
+use std::io;
+use std::io::Read;
+use std::vec;
+
+use rocket::Response;
+use rocket::http::{ ContentType, Status };
+use rocket::response::{ Responder, Stream };
+use serde::Serialize;
+use serde_json;
+use serde_json::Value;
+
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// A Rocket responder streaming the `data` of a ResponseJSON as CSV, with Content-Type `text/csv`.
+///
+/// `data` must be an array of flat objects: the header row holds the keys of all the objects (in order of appearance),
+/// each following row the values of one object. Missing & `null` values are left empty, nested arrays / objects are written as JSON text
+///
+/// ```rust,ignore
+/// #[get("/users.csv")]
+/// fn export() -> Result<CsvResponse, GenericError> {
+///     CsvResponse::new(ResponseJSON::ok().data(users)).map(|csv| csv.filename("users.csv"))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct CsvResponse {
+    columns: Vec<String>,
+    rows: vec::IntoIter<Value>,
+    status: Status,
+    filename: Option<String>,
+    buffer: Vec<u8>,
+    position: usize,
+    header_written: bool,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl CsvResponse {
+    /// Create a CsvResponse from `rjson`. Fails if its `data` isn't an array of objects
+    pub fn new<T: Serialize>(rjson: ResponseJSON<T>) -> Result<CsvResponse, GenericError> {
+        let status = Status::from_code(rjson.http_code).unwrap_or(Status::new(rjson.http_code, "<unknown code>"));
+        let rows = match serde_json::to_value(rjson.data) {
+            Ok(Value::Array(rows)) => rows,
+            Ok(_) => return amiwo_error!("::AMIWO::CONTRIB::ROCKET::CSV_RESPONSE::NEW::ERROR data must be an array".to_string()),
+            Err(serde_err) => return Err(GenericError::Serde(serde_err)),
+        };
+
+        let mut columns : Vec<String> = Vec::new();
+        for row in rows.iter() {
+            match row.as_object() {
+                Some(map) => for key in map.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                },
+                None => return amiwo_error!("::AMIWO::CONTRIB::ROCKET::CSV_RESPONSE::NEW::ERROR data must be an array of objects".to_string()),
+            }
+        }
+
+        Ok(CsvResponse {
+            columns: columns,
+            rows: rows.into_iter(),
+            status: status,
+            filename: None,
+            buffer: Vec::new(),
+            position: 0,
+            header_written: false,
+        })
+    }
+
+    /// Returns the columns of the CSV (i.e. the header row)
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Send the CSV as an attachment named `filename` (`Content-Disposition` header)
+    pub fn filename<S: ToString>(mut self, filename: S) -> CsvResponse {
+        self.filename = Some(filename.to_string());
+        self
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Write one CSV record holding `fields` into `buffer`
+fn write_record<I: Iterator<Item = String>>(buffer: &mut Vec<u8>, fields: I) {
+    for (idx, field) in fields.enumerate() {
+        if idx > 0 {
+            buffer.push(b',');
+        }
+        buffer.extend_from_slice(escape_field(&field).as_bytes());
+    }
+    buffer.extend_from_slice(b"\r\n");
+}
+
+// Quote `field` if it holds a separator, a quote or a line break (RFC 4180)
+fn escape_field(field: &str) -> String {
+    if field.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace("\"", "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Text of a CSV field holding `value`
+fn field_of(value: Option<&Value>) -> String {
+    match value {
+        None | Some(&Value::Null) => String::new(),
+        Some(&Value::String(ref string)) => string.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Read for CsvResponse {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position >= self.buffer.len() {
+            self.buffer.clear();
+            self.position = 0;
+
+            if !self.header_written {
+                self.header_written = true;
+                if !self.columns.is_empty() {
+                    write_record(&mut self.buffer, self.columns.iter().cloned());
+                }
+                continue;
+            }
+
+            match self.rows.next() {
+                Some(row) => {
+                    let columns = &self.columns;
+                    write_record(&mut self.buffer, columns.iter().map(|column| field_of(row.get(column))));
+                },
+                None => return Ok(0),
+            }
+        }
+
+        let size = cmp::min(buf.len(), self.buffer.len() - self.position);
+        buf[..size].copy_from_slice(&self.buffer[self.position..self.position + size]);
+        self.position += size;
+        Ok(size)
+    }
+}
+
+/// Responds with a chunked body, Content-Type `text/csv` and the `http_code` of the ResponseJSON as HTTP status
+impl<'r> Responder<'r> for CsvResponse {
+    fn respond(self) -> Result<Response<'r>, Status> {
+        let status = self.status;
+        let disposition = self.filename.as_ref().map(|filename| format!("attachment; filename=\"{}\"", filename.replace("\"", "")));
+        Stream::from(self).respond()
+            .map(|mut response| {
+                response.set_header(ContentType::CSV);
+                response.set_status(status);
+                if let Some(disposition) = disposition {
+                    response.set_raw_header("Content-Disposition", disposition);
+                }
+                response
+            })
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::io::Read;
+
+    use rocket::http::Status;
+    use rocket::response::Responder;
+
+    use types::ResponseJSON;
+
+    use super::CsvResponse;
+
+    #[test]
+    fn CsvResponse_test_read() {
+        let json = ResponseJSON::ok().data(json!([
+            { "id": 1, "name": "Boris", "active": true },
+            { "id": 2, "name": "Doe, \"John\"", "tags": ["a", "b"] },
+            { "id": 3, "name": null }
+        ]));
+
+        let mut csv = CsvResponse::new(json).unwrap();
+        assert_eq!(csv.columns(), &["active", "id", "name", "tags"]);
+
+        let mut body = String::new();
+        csv.read_to_string(&mut body).unwrap();
+        assert_eq!(body, "active,id,name,tags\r\ntrue,1,Boris,\r\n,2,\"Doe, \"\"John\"\"\",\"[\"\"a\"\",\"\"b\"\"]\"\r\n,3,,\r\n");
+
+        let mut body = String::new();
+        CsvResponse::new(ResponseJSON::ok().data(json!([]))).unwrap().read_to_string(&mut body).unwrap();
+        assert_eq!(body, "");
+    }
+
+    #[test]
+    fn CsvResponse_test_invalid_data() {
+        assert!(CsvResponse::new(ResponseJSON::ok().data(json!({ "id": 1 }))).is_err());
+        assert!(CsvResponse::new(ResponseJSON::ok().data(json!([{ "id": 1 }, 2]))).is_err());
+    }
+
+    #[test]
+    fn CsvResponse_test_responder() {
+        let csv = CsvResponse::new(ResponseJSON::ok().http_code(201).data(json!([{ "id": 1 }]))).unwrap();
+        let mut response = csv.filename("users.csv").respond().unwrap();
+        assert_eq!(response.status(), Status::Created);
+        assert_eq!(response.header_values("Content-Type").next(), Some("text/csv; charset=utf-8"));
+        assert_eq!(response.header_values("Content-Disposition").next(), Some("attachment; filename=\"users.csv\""));
+
+        let body_str = response.body().and_then(|b| b.into_string()).unwrap();
+        assert_eq!(body_str, "id\r\n1\r\n");
+    }
+}
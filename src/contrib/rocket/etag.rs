@@ -0,0 +1,234 @@
+//! File holding the types used to support conditional GET (`ETag` / `If-None-Match`) on ResponseJSON
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.2
+//!
+//! ## Release notes
+//! - v1.2 : the ETag is a FNV-1a hash, stable across builds, & only 2xx responses can be `304 Not Modified`
+//! - v1.1 : custom headers of the ResponseJSON are sent with the response
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use rocket;
+use rocket::{ Request, Response };
+use rocket::http::Status;
+use rocket::request::FromRequest;
+use rocket::response::content;
+use rocket::response::Responder;
+use serde::Serialize;
+
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// A request guard holding the value of the `If-None-Match` header (if any)
+///
+/// ```rust,ignore
+/// #[get("/users")]
+/// fn users(if_none_match: IfNoneMatch) -> ETagged { 
+///     if_none_match.respond_with(ResponseJSON::ok().data(...))
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct IfNoneMatch(Option<String>);
+
+/// A ResponseJSON responder with an `ETag` header computed from the serialized body.
+/// Responds with `304 Not Modified` and an empty body when the `If-None-Match` header of the request matches the ETag
+/// of a successful (2xx) response
+#[derive(Clone, Debug)]
+pub struct ETagged {
+    etag: String,
+    not_modified: bool,
+    status: Status,
+    body: String,
+    headers: Vec<(String, String)>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl IfNoneMatch {
+    /// Returns `true` if `etag` matches any of the ETags listed in the header (or if the header is `*`).
+    /// Weak comparison is used as per RFC 7232
+    pub fn matches(&self, etag: &str) -> bool {
+        self.0.as_ref().map_or(false, |header| {
+            header.split(',')
+                .map(|tag| tag.trim())
+                .any(|tag| tag == "*" || strip_weak(tag) == strip_weak(etag))
+        })
+    }
+
+    /// Build the ETagged responder for `rjson`
+    pub fn respond_with<T: Serialize>(&self, rjson: ResponseJSON<T>) -> ETagged {
+        let status = Status::from_code(rjson.http_code).unwrap_or(Status::new(rjson.http_code, "<unknown code>"));
+        let headers = rjson.headers.clone();
+        let body = rjson.into_string();
+        let etag = etag_of(&body);
+
+        ETagged {
+            not_modified: status.code >= 200 && status.code < 300 && self.matches(&etag),
+            etag: etag,
+            status: status,
+            body: body,
+            headers: headers,
+        }
+    }
+}
+
+impl ETagged {
+    /// Returns the ETag of the response
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    /// Returns `true` if the response will be a `304 Not Modified`
+    pub fn is_not_modified(&self) -> bool {
+        self.not_modified
+    }
+}
+
+impl<T: Serialize> ResponseJSON<T> {
+    /// Compute the (strong) ETag of this ResponseJSON from a hash of its serialized form, stable across builds & releases
+    pub fn etag(&self) -> String {
+        etag_of(&self.to_string())
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// 64-bit FNV-1a hash of `body`, quoted
+fn etag_of(body: &str) -> String {
+    let hash = body.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("\"{:016x}\"", hash)
+}
+
+fn strip_weak(tag: &str) -> &str {
+    if tag.starts_with("W/") { &tag[2..] } else { tag }
+}
+
+// =======================================================================
+// EXTERNAL TRAITS IMPLEMENTATION
+// =======================================================================
+/// Reads the `If-None-Match` header. Never fails nor forwards
+impl<'a, 'r> FromRequest<'a, 'r> for IfNoneMatch {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> rocket::request::Outcome<Self, Self::Error> {
+        rocket::Outcome::Success(IfNoneMatch(request.headers().get_one("If-None-Match").map(|header| header.to_string())))
+    }
+}
+
+/// Responds with `304 Not Modified` & an empty body if the ETag matched, with the JSON body otherwise.
+/// The `ETag` header and the custom headers of the ResponseJSON are set in both cases
+impl<'r> Responder<'r> for ETagged {
+    fn respond(self) -> Result<Response<'r>, Status> {
+        let headers = self.headers;
+        let response = if self.not_modified {
+            Response::build()
+                .status(Status::NotModified)
+                .raw_header("ETag", self.etag)
+                .ok()
+        } else {
+            let etag = self.etag;
+            let status = self.status;
+            content::JSON(self.body).respond()
+                .map(|mut response| {
+                    response.set_status(status);
+                    response.set_raw_header("ETag", etag);
+                    response
+                })
+        };
+
+        response.map(|mut response| {
+            for (name, value) in headers {
+                response.adjoin_raw_header(name, value);
+            }
+            response
+        })
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ etag_of, IfNoneMatch };
+
+    use rocket::Request;
+    use rocket::http::{ Header, Method, Status };
+    use rocket::request::FromRequest;
+    use rocket::response::Responder;
+
+    use types::ResponseJSON;
+
+    #[test]
+    fn ETagged_test_etag() {
+        let json = ResponseJSON::ok().data(json!([1, 2, 3]));
+        assert_eq!(json.etag(), json.clone().etag());
+        assert!(json.etag() != ResponseJSON::ok().data(json!([1, 2])).etag());
+        assert!(json.etag().starts_with('"') && json.etag().ends_with('"'));
+
+        // FNV-1a reference values
+        assert_eq!(etag_of(""), "\"cbf29ce484222325\"");
+        assert_eq!(etag_of("a"), "\"af63dc4c8601ec8c\"");
+    }
+
+    #[test]
+    fn ETagged_test_not_modified() {
+        let json = ResponseJSON::ok().data(json!([1, 2, 3]));
+        let etag = json.etag();
+
+        let mut request = Request::new(Method::Get, "/");
+        request.add_header(Header::new("If-None-Match", format!("\"other\", W/{}", etag)));
+        let if_none_match = IfNoneMatch::from_request(&request).unwrap();
+
+        let mut response = if_none_match.respond_with(json.header("Cache-Control", "max-age=60")).respond().unwrap();
+        assert_eq!(response.status(), Status::NotModified);
+        assert_eq!(response.header_values("ETag").next(), Some(etag.as_str()));
+        assert_eq!(response.header_values("Cache-Control").next(), Some("max-age=60"));
+        assert!(response.body().is_none());
+    }
+
+    #[test]
+    fn ETagged_test_modified() {
+        let json = ResponseJSON::ok().http_code(201).data(json!([1, 2, 3]));
+        let etag = json.etag();
+
+        let mut request = Request::new(Method::Get, "/");
+        request.add_header(Header::new("If-None-Match", "\"other\""));
+        let if_none_match = IfNoneMatch::from_request(&request).unwrap();
+
+        let tagged = if_none_match.respond_with(json);
+        assert_eq!(tagged.is_not_modified(), false);
+        assert_eq!(tagged.etag(), etag);
+
+        let mut response = tagged.respond().unwrap();
+        assert_eq!(response.status(), Status::Created);
+        assert_eq!(response.header_values("ETag").next(), Some(etag.as_str()));
+        assert_eq!(response.body().and_then(|b| b.into_string()), Some(r#"{"data":[1,2,3],"http_code":201,"success":true}"#.to_string()));
+
+        // no header
+        let request = Request::new(Method::Get, "/");
+        let if_none_match = IfNoneMatch::from_request(&request).unwrap();
+        assert_eq!(if_none_match.matches(&etag), false);
+    }
+
+    #[test]
+    fn ETagged_test_error_modified() {
+        let json = ResponseJSON::error().http_code(404);
+        let mut request = Request::new(Method::Get, "/");
+        request.add_header(Header::new("If-None-Match", json.etag()));
+        let if_none_match = IfNoneMatch::from_request(&request).unwrap();
+
+        let tagged = if_none_match.respond_with(json);
+        assert_eq!(tagged.is_not_modified(), false);
+        assert_eq!(tagged.respond().unwrap().status(), Status::NotFound);
+    }
+}
@@ -2,9 +2,30 @@
 //! exposing a simplified Map type interface to access them
 //!
 //! Author: [Boris](mailto:boris@humanenginuity.com)
-//! Version: 2.0
+//! Version: 2.21
 //!
 //! ## Release notes
+//! - v2.21 : `coerced()` only converts the strings which round-trip (`"1.50"`, `"1e3"` & the numbers losing precision are kept as strings)
+//! - v2.20 : moved the query string encoding to `util::url_encode()` (shared with `util::UrlBuilder`)
+//! - v2.19 : added `take()` to remove a parameter (e.g. `api_key` before forwarding the others)
+//! - v2.18 : added `push()` to add parameters with the parsing rules
+//! - v2.17 : added `Sanitizer` to trim / collapse whitespace / strip control characters & HTML tags of the values (`sanitized()`), with per field overrides in the managed `FormConfig`
+//! - v2.16 : added `get_one_or_many()` returning the values as a `OneOrMany<String>`
+//! - v2.15 : moved the `x-www-form-urlencoded` parsing to `util::parse_query_string()`
+//! - v2.14 : added `application/xml` & `text/xml` support (element => key, text => value, repeated elements => array)
+//! - v2.13 : added default values (`with_defaults()`) & parameter aliases (`alias()`), applied by the guard from the managed `FormConfig`
+//! - v2.12 : added `to_json_value()`, `to_query_string()` & `Serialize` to forward the parameters
+//! - v2.11 : added `coerced()` converting boolean & number strings into native JSON types
+//! - v2.10 : removed the unsafe `transmute` of the form string, the map owning its keys & values
+//! - v2.9 : added iteration & inspection methods (`keys()`, `values()`, `iter()`, `len()`, `is_empty()`, `contains_key()`) and `IntoIterator`
+//! - v2.8 : added size limits per content type (managed `FormLimits`) & per route (`LimitedFormHashMap`), bodies over the limit fail with `413 Payload Too Large` instead of being truncated
+//! - v2.7 : keys with a `[]` suffix are always arrays (in multipart forms too), added `get_all()`
+//! - v2.6 : keys using the bracket notation (`user[name]=bob&user[tags][]=x`) are parsed into nested objects & arrays, keys are now URL decoded
+//! - v2.5 : added typed getters `get_as()` & `get_opt_as()`
+//! - v2.4 : `multipart/form-data` bodies are streamed, big files being spooled to disk as configured by the managed `MultipartConfig`
+//! - v2.3 : added `multipart/form-data` support, files being exposed as `FormFile` through `files()` & `file()`
+//! - v2.2 : added `validate()` to check the parameters against a JSON Schema (feature `amiwo_schema`)
+//! - v2.1 : added `fields()` & `ResponseJSON::filter_fields_from()` to support `?fields=` filtering
 //! - v2.0 : refactored using serde_json Map & Value
 //! - v1.1 : implemented Index trait, renamed old `new()` method into `from_application_data`, added method `from_json_data`
 //! - v1.0 : creation
@@ -17,38 +38,139 @@
 // =======================================================================
 // LIBRARY IMPORTS
 // =======================================================================
+use std::cmp;
 use std::convert::AsRef;
 use std::error::Error;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::io;
 use std::io::Read;
-use std::ops::Index;
+use std::ops::{ Deref, Index };
 
 use rocket;
 use rocket::{ Request, Data };
 use rocket::data::FromData;
-use rocket::http::Status;
+use rocket::http::{ ContentType, Status };
 use rocket::outcome::IntoOutcome;
-use rocket::request::{ FromForm, FromFormValue, FormItems };
+use rocket::request::{ FromForm, FromRequest, FormItems, State };
 
+use serde::{ Serialize, Serializer };
+use serde::de;
+use serde::de::DeserializeOwned;
 use serde_json;
 use serde_json::Value;
+use serde_json::map;
 use serde_json::map::Map;
 
+use contrib::rocket::multipart;
+use contrib::rocket::multipart::{ FormFile, MultipartConfig };
+use contrib::rocket::xml;
+#[cfg(feature = "amiwo_schema")]
+use contrib::schema::{ SchemaViolation, Validator };
 use error::GenericError;
-use traits::Pushable;
+use types::{ OneOrMany, ResponseJSON };
+use util;
 
 // =======================================================================
 // STRUCT & TRAIT DEFINITION
 // =======================================================================
 /// A `FromData` type that creates a map of the key/value pairs from a
-/// `x-www-form-urlencoded` or `json` form string 
+/// `x-www-form-urlencoded`, `json`, `xml` or `multipart/form-data` form string.
+/// The files of a `multipart/form-data` form are available through `files()` (big files can be spooled to disk, see `MultipartConfig`)
 pub struct FormHashMap<'s> {
     form_string: String,
     map: Map<String, Value>,
-    _phantom: PhantomData<&'s str>,
+    files: Vec<FormFile>,
+    _phantom: PhantomData<&'s str>, // nothing borrows the form string, the lifetime is kept for API compatibility
 }
 
+/// Size limits (in bytes) of the FormHashMap bodies per content type, to be managed by Rocket.
+/// A missing limit falls back to the `limits.application`, `limits.json`, `limits.xml` & `limits.multipart` config extras,
+/// then to the defaults (32 KiB, 1 MiB, 1 MiB & 8 MiB)
+///
+/// ```rust,ignore
+/// rocket::ignite()
+///     .manage(FormLimits::new().json(64 << 10).multipart(50 << 20))
+///     .mount("/", routes![...])
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormLimits {
+    application: Option<u64>,
+    json: Option<u64>,
+    xml: Option<u64>,
+    multipart: Option<u64>,
+}
+
+/// Size limit of a `LimitedFormHashMap`
+pub trait FormLimit {
+    /// Maximum size of the body in bytes
+    fn limit() -> u64;
+}
+
+/// A FormHashMap whose body is limited to `L::limit()` bytes whatever its content type, to set the size limit of a single route
+///
+/// ```rust,ignore
+/// struct UploadLimit;
+///
+/// impl FormLimit for UploadLimit {
+///     fn limit() -> u64 { 50 << 20 }
+/// }
+///
+/// #[post("/upload", data = "<params>")]
+/// fn upload(params: LimitedFormHashMap<UploadLimit>) -> ResponseJSON { ... }
+/// ```
+pub struct LimitedFormHashMap<'s, L: FormLimit> {
+    form: FormHashMap<'s>,
+    _limit: PhantomData<L>,
+}
+
+/// Default values, parameter aliases & sanitization applied to every FormHashMap parsed from the request data, to be managed by Rocket.
+/// Query strings (`FromForm`) can't access the managed state, use `apply()` explicitly in that case
+///
+/// ```rust,ignore
+/// rocket::ignite()
+///     .manage(FormConfig::new()
+///         .defaults(json!({ "page": 1, "per_page": 20 }))
+///         .alias("per_page", "limit")
+///         .sanitize(Sanitizer::new().trim(true).collapse_whitespace(true))
+///         .sanitize_field("password", Sanitizer::new()))
+///     .mount("/", routes![...])
+///
+/// #[get("/users?<params>")]
+/// fn users(params: FormHashMap, config: State<FormConfig>) -> ResponseJSON {
+///     let params = config.apply(params);
+///     ...
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormConfig {
+    defaults: Map<String, Value>,
+    aliases: Vec<(String, String)>,
+    sanitizer: Sanitizer,
+    field_sanitizers: Vec<(String, Sanitizer)>,
+}
+
+/// Clean up of the string values of a FormHashMap (see `FormHashMap::sanitized()` & `FormConfig::sanitize()`),
+/// every step being disabled by default. The steps run in this order: strip HTML tags, strip control characters,
+/// collapse whitespace, trim
+///
+/// ```rust,ignore
+/// let params = params.sanitized(&Sanitizer::new().trim(true).strip_html(true));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sanitizer {
+    trim: bool,
+    collapse_whitespace: bool,
+    strip_control_chars: bool,
+    strip_html: bool,
+}
+
+// Reader failing (instead of stopping silently like `Read::take()`) when the body is bigger than the limit
+struct LimitedReader<R> {
+    inner: R,
+    remaining: u64,
+    exceeded: bool,
+}
 
 // =======================================================================
 // IMPLEMENTATION
@@ -60,45 +182,251 @@ impl<'s> FormHashMap<'s> {
         self.map.get(key.as_ref())
     }
 
-    /// Returns the raw form string that was used to parse the encapsulated
-    /// object.
-    pub fn raw_form_string(&self) -> &str {
-        &self.form_string
+    /// Get all the values associated with `key`: the items if the value is an array, the value itself otherwise
+    /// (empty if the key is missing). A `[]` suffix is ignored, so `get_all("tags[]")` is `get_all("tags")`
+    pub fn get_all<T: AsRef<str>>(&self, key: T) -> Vec<&Value> {
+        let key = key.as_ref();
+        let key = if key.ends_with("[]") { &key[..key.len() - 2] } else { key };
+        match self.get(key) {
+            Some(&Value::Array(ref vect)) => vect.iter().collect(),
+            Some(value) => vec![value],
+            None => vec![],
+        }
     }
 
-    /// Build a FormHashMap from application data (i.e. content type application/x-www-form-urlencoded)
-    /// Uses Rocket's `FormItems::from<'f>(&'f str)` to parse the form's String
-    fn from_application_data(form_string: String) -> Result<Self, GenericError> {
-        let long_lived_string: &'s str = unsafe {
-            ::std::mem::transmute(form_string.as_str())
+    /// Get the value (or values) associated with `key` as a `OneOrMany`: `Many` if the value is an array, `One` otherwise.
+    /// Booleans & numbers (e.g. from JSON bodies) are converted into strings, a `[]` suffix is ignored like in `get_all()`.
+    /// Returns `None` if the key is missing, `null` or holds an object (or an array of non scalar values)
+    pub fn get_one_or_many<T: AsRef<str>>(&self, key: T) -> Option<OneOrMany<String>> {
+        let key = key.as_ref();
+        let key = if key.ends_with("[]") { &key[..key.len() - 2] } else { key };
+        match self.get(key) {
+            Some(&Value::Array(ref vect)) => vect.iter().map(scalar_to_string).collect::<Option<Vec<_>>>().map(OneOrMany::Many),
+            Some(value) => scalar_to_string(value).map(OneOrMany::One),
+            None => None,
+        }
+    }
+
+    /// Returns `true` if a value is associated with `key`
+    pub fn contains_key<T: AsRef<str>>(&self, key: T) -> bool {
+        self.map.contains_key(key.as_ref())
+    }
+
+    /// Returns the number of parameters (repeated keys counting once)
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if there is no parameter
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the parameter names
+    pub fn keys<'a>(&'a self) -> map::Keys<'a> {
+        self.map.keys()
+    }
+
+    /// Returns an iterator over the parameter values
+    pub fn values<'a>(&'a self) -> map::Values<'a> {
+        self.map.values()
+    }
+
+    /// Returns an iterator over the (name, value) pairs of the parameters
+    ///
+    /// ```rust,ignore
+    /// for (key, value) in params.iter() {
+    ///     info!("received {} = {}", key, value);
+    /// }
+    /// ```
+    pub fn iter<'a>(&'a self) -> map::Iter<'a> {
+        self.map.iter()
+    }
+
+    /// Get the value associated with `key` converted to `T`.
+    /// String values (or arrays of strings) that aren't valid as such are parsed as JSON, so that `?page=2` can be read as a number,
+    /// and booleans & numbers can be read as strings (e.g. after `coerced()`)
+    ///
+    /// ```rust,ignore
+    /// let page = params.get_as::<u32>("page")?;
+    /// ```
+    pub fn get_as<T: DeserializeOwned>(&self, key: &str) -> Result<T, GenericError> {
+        match self.get_opt_as(key) {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => amiwo_error!(
+                format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::GET_AS::ERROR Missing parameter `{}`", key),
+                GenericError::Serde(de::Error::custom(format!("missing parameter `{}`", key)))
+            ),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get the value associated with `key` converted to `T`, `None` if the key is missing (or `null`).
+    /// Fails if the value can't be converted (see `get_as()`)
+    pub fn get_opt_as<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, GenericError> {
+        let value = match self.get(key) {
+            None | Some(&Value::Null) => return Ok(None),
+            Some(value) => value,
         };
-        let mut items = FormItems::from(long_lived_string);
 
-        // Handle parsing or decode errors
-        let parsing_errors: Vec<_> = items.by_ref()
-            .map(|(key, value)| (key, String::from_form_value(value)))
-            .filter(|&(_, ref decoded_value)| decoded_value.is_err())
+        serde_json::from_value(value.clone())
+            .or_else(|err| serde_json::from_value(parse_strings(value)).map_err(|_| err))
+            .or_else(|err| serde_json::from_value(stringify_scalars(value)).map_err(|_| err))
+            .map(Some)
+            .or_else(|err| amiwo_error!(
+                format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::GET_OPT_AS::ERROR Invalid parameter `{}` = {}", key, value),
+                GenericError::Serde(err)
+            ))
+    }
+
+    /// Convert the string values that are booleans (`"true"` / `"false"`) or numbers (`"42"`, `"-1.5"`) into native JSON types,
+    /// recursively in arrays & objects. Strings that wouldn't round-trip (`"007"`, `"+1"`, `" 1"`, `"1."`, `"1.50"`, `"1e3"`,
+    /// integers too big to be stored exactly) are kept as is
+    ///
+    /// ```rust,ignore
+    /// let params = params.coerced(); // ?page=2&active=true => { "page": 2, "active": true }
+    /// ```
+    pub fn coerced(mut self) -> FormHashMap<'s> {
+        self.map = self.map.into_iter()
+            .map(|(key, value)| (key, coerce(value)))
+            .collect();
+        self
+    }
+
+    /// Fill the parameters missing (or `null`) with the values of the `defaults` object, recursively in nested objects.
+    /// Non object defaults are ignored
+    ///
+    /// ```rust,ignore
+    /// let params = params.with_defaults(json!({ "page": 1, "per_page": 20 }));
+    /// ```
+    pub fn with_defaults(mut self, defaults: Value) -> FormHashMap<'s> {
+        match defaults {
+            Value::Object(ref defaults) => fill_defaults(&mut self.map, defaults),
+            _ => warn!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::WITH_DEFAULTS::WARNING Ignoring non object defaults {}", defaults),
+        }
+        self
+    }
+
+    /// Rename the parameter `alias` into `name`, e.g. `alias("per_page", "limit")` to accept `?limit=` as `?per_page=`.
+    /// If both are present, `name` wins and the value of `alias` is dropped
+    pub fn alias<N: Into<String>, A: AsRef<str>>(mut self, name: N, alias: A) -> FormHashMap<'s> {
+        let name = name.into();
+        if let Some(value) = self.map.remove(alias.as_ref()) {
+            if self.map.contains_key(&name) {
+                warn!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::ALIAS::WARNING Both `{}` and its alias `{}` are present, ignoring `{}`", name, alias.as_ref(), alias.as_ref());
+            } else {
+                self.map.insert(name, value);
+            }
+        }
+        self
+    }
+
+    /// Add the parameter `key` with the same rules as when parsing a form string: a repeated key becomes an array,
+    /// the bracket notation (`user[name]`, `tags[]`) builds nested values.
+    /// Returns an error if the key conflicts with a previous parameter (e.g. `a` then `a[b]`)
+    ///
+    /// ```rust,ignore
+    /// params.push("tags[]", "a")?.push("user[name]", "bob")?;
+    /// ```
+    pub fn push<K: AsRef<str>, V: Into<Value>>(&mut self, key: K, value: V) -> Result<&mut Self, GenericError> {
+        util::insert_param(&mut self.map, key.as_ref(), value.into())?;
+        Ok(self)
+    }
+
+    /// Remove the parameter `key` and return its value, e.g. to strip `api_key` before forwarding the parameters upstream
+    pub fn take<T: AsRef<str>>(&mut self, key: T) -> Option<Value> {
+        self.map.remove(key.as_ref())
+    }
+
+    /// Sanitize the string values (recursively in arrays & objects) with `sanitizer`
+    pub fn sanitized(mut self, sanitizer: &Sanitizer) -> FormHashMap<'s> {
+        self.map = self.map.into_iter()
+            .map(|(key, value)| (key, sanitizer.sanitize_value(value)))
             .collect();
+        self
+    }
 
-        if !parsing_errors.is_empty() {
-            return amiwo_error!( format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_APPLICATION_DATA::WARNING Unable to parse form string {} [parsing errors = {:?}]", form_string, parsing_errors) );
+    /// Sanitize the string values of the parameter `key` only with `sanitizer`
+    pub fn sanitized_field<T: AsRef<str>>(mut self, key: T, sanitizer: &Sanitizer) -> FormHashMap<'s> {
+        if let Some(value) = self.map.get_mut(key.as_ref()) {
+            let sanitized = sanitizer.sanitize_value(::std::mem::replace(value, Value::Null));
+            *value = sanitized;
         }
-        if !items.completed() {
-            warn!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_APPLICATION_DATA::WARNING Form string {} couldn't be completely parsed", form_string);
+        self
+    }
+
+    /// Returns the parameters as a JSON object
+    pub fn to_json_value(&self) -> Value {
+        Value::Object(self.map.clone())
+    }
+
+    /// Returns the parameters as an `application/x-www-form-urlencoded` string, e.g. to forward them to an upstream service.
+    /// Nested values use the bracket notation and arrays the `[]` suffix, so that the string is parsed back into the same parameters
+    /// (booleans & numbers become strings, `null` an empty value)
+    ///
+    /// ```rust,ignore
+    /// let url = format!("{}/search?{}", UPSTREAM_URL, params.to_query_string());
+    /// ```
+    pub fn to_query_string(&self) -> String {
+        let mut pairs = Vec::new();
+        for (key, value) in self.map.iter() {
+            util::push_query_pairs(&mut pairs, util::url_encode(key), value);
         }
+        pairs.join("&")
+    }
+
+    /// Returns the files uploaded through a `multipart/form-data` form
+    pub fn files(&self) -> &[FormFile] {
+        &self.files
+    }
+
+    /// Returns the first file uploaded through the form field `name`
+    pub fn file<T: AsRef<str>>(&self, name: T) -> Option<&FormFile> {
+        self.files.iter().find(|file| file.name() == name.as_ref())
+    }
+
+    /// Returns the raw form string that was used to parse the encapsulated
+    /// object.
+    pub fn raw_form_string(&self) -> &str {
+        &self.form_string
+    }
+
+    /// Validate the parameters (as a JSON object) against the JSON Schema of `validator`
+    #[cfg(feature = "amiwo_schema")]
+    pub fn validate(&self, validator: &Validator) -> Result<(), Vec<SchemaViolation>> {
+        validator.validate(&Value::Object(self.map.clone()))
+    }
+
+    /// Returns the list of fields requested through the `fields` parameter.
+    /// Accepts both comma separated values (`?fields=id,name`) and repeated keys (`?fields=id&fields=name`)
+    pub fn fields(&self) -> Vec<String> {
+        self.get_all("fields").into_iter()
+            .filter_map(|value| value.as_str())
+            .flat_map(|value| value.split(','))
+            .map(|field| field.trim())
+            .filter(|field| !field.is_empty())
+            .map(|field| field.to_string())
+            .collect()
+    }
+
+    /// Build a FormHashMap from application data (i.e. content type application/x-www-form-urlencoded)
+    /// Uses `util::parse_query_string()` to parse the form's String.
+    ///
+    /// Keys using the PHP/Rails bracket notation are parsed into nested values:
+    /// `user[name]=bob&user[tags][]=x&user[tags][]=y` => `{ "user": { "name": "bob", "tags": ["x", "y"] } }`.
+    /// A key with a `[]` suffix is always an array (`ids[]=1` => `{ "ids": ["1"] }`), while repeated plain keys
+    /// become an array only if there is more than one value (`id=1` => `{ "id": "1" }`)
+    fn from_application_data(form_string: String) -> Result<Self, GenericError> {
+        let map = util::parse_query_string(&form_string)
+            .or_else(|err| amiwo_error!(
+                format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_APPLICATION_DATA::WARNING Unable to parse form string {}", form_string),
+                err
+            ))?;
 
         Ok(FormHashMap {
             form_string: form_string,
-            map: FormItems::from(long_lived_string)
-                .map(|(key, value)| (key, String::from_form_value(value)))
-                .filter(|&(_, ref decoded_value)| decoded_value.is_ok())
-                .fold(
-                    Map::new(),
-                    |mut map, (key, decoded_value)| {
-                        map.entry(key).or_insert(Value::Null).push(Value::String(decoded_value.unwrap()));
-                        map
-                    }
-                ),
+            map: map,
+            files: Vec::new(),
             _phantom: PhantomData,
         })
     }
@@ -106,18 +434,16 @@ impl<'s> FormHashMap<'s> {
     /// Build a FormHashMap from JSON data (i.e. content type application/json)
     /// Uses serde_json's `serde_json::from_str<'a, T>(&'a str)` to parse the form's String
     fn from_json_data(form_string: String) -> Result<Self, GenericError> {
-        let long_lived_string: &'s str = unsafe {
-            ::std::mem::transmute(form_string.as_str())
-        };
-        serde_json::from_str(long_lived_string)
+        serde_json::from_str(&form_string)
             .or_else(|err| amiwo_error!(
                 format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_JSON_DATA::ERROR Error parsing string {} > {}", form_string, &err.description()),
                 GenericError::Serde(err)
             )).and_then(|value : Value| {
-                if value.is_object() {
+                if let Value::Object(map) = value {
                     Ok(FormHashMap {
                         form_string: form_string,
-                        map: value.as_object().unwrap().clone(),
+                        map: map,
+                        files: Vec::new(),
                         _phantom: PhantomData,
                     })
                 } else {
@@ -126,31 +452,400 @@ impl<'s> FormHashMap<'s> {
             })
     }
 
-    // We'd like to have form objects have pointers directly to the form string. 
-    // This means that the form string has to live at least as long as the form object. So,
-    // to enforce this, we store the form_string along with the form object.
-    //
-    // So far so good. Now, this means that the form_string can never be
-    // deallocated while the object is alive. That implies that the
-    // `form_string` value should never be moved away. We can enforce that
-    // easily by 1) not making `form_string` public, and 2) not exposing any
-    // `&mut self` methods that could modify `form_string`.
+    /// Build a FormHashMap from XML data (i.e. content type application/xml or text/xml)
+    /// The children of the root element are the keys, their text the values (see `contrib::rocket::xml`)
+    fn from_xml_data(form_string: String) -> Result<Self, GenericError> {
+        let map = xml::parse(&form_string)
+            .or_else(|err| amiwo_error!(
+                format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_XML_DATA::ERROR Error parsing string {}", form_string),
+                err
+            ))?;
+
+        Ok(FormHashMap {
+            form_string: form_string,
+            map: map,
+            files: Vec::new(),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Build a FormHashMap from multipart data (i.e. content type multipart/form-data) delimited by `boundary`.
+    /// Text fields are inserted like application data (see `from_application_data()`).
+    /// The body is streamed (files being spooled to disk according to `config`), so the raw form string is empty
+    fn from_multipart_data<R: Read>(boundary: &str, body: R, config: &MultipartConfig) -> Result<Self, GenericError> {
+        let (fields, files) = multipart::parse(boundary, body, config)?;
+
+        let mut map = Map::new();
+        for (key, value) in fields {
+            util::insert_param(&mut map, &key, Value::String(value))?;
+        }
+
+        Ok(FormHashMap {
+            form_string: String::new(),
+            map: map,
+            files: files,
+            _phantom: PhantomData,
+        })
+    }
+
+    // The map owns its keys & values (copied out of the form string), so the form string is only kept
+    // for `raw_form_string()` and the FormHashMap can be freely moved
     fn new(content_type: &str, form_string: String) -> Result<Self, GenericError> {
         match content_type {
             "application" => FormHashMap::from_application_data(form_string),
             "json" => FormHashMap::from_json_data(form_string),
+            "xml" => FormHashMap::from_xml_data(form_string),
             _ => amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::NEW::ERROR Unsupported content type {}", content_type)),
         }
     }
 }
 
+impl<'f> FormHashMap<'f> {
+    // Returns the size limit of a body of `content_type` ("application", "json", "xml" or "multipart")
+    fn size_limit(request: &Request, content_type: &str) -> u64 {
+        State::<FormLimits>::from_request(request).succeeded()
+            .and_then(|limits| limits.inner().get(content_type))
+            .or_else(|| rocket::config::active()
+                .and_then(|c| c.extras.get(&("limits.".to_string() + content_type))) // TODO: remove placeholder when upgrading to rocket version > 0.2.6
+                // .and_then(|c| c.limits.get("application") // In next version
+                .and_then(|limit| limit.as_integer())
+                .map(|limit| limit as u64))
+            .unwrap_or_else(|| match content_type { "json" | "xml" => 1<<20, "multipart" => 1<<23, _ => 32768 })
+    }
+
+    // Parse the data of `request`, `limit` overriding the size limit of its content type
+    fn from_request_data(request: &Request, data: Data, limit: Option<u64>) -> rocket::data::Outcome<Self, GenericError> {
+        if !request.content_type().map_or(false, |ct| ct.is_form() || ct.is_json() || is_xml(&ct) || ct.is_data_form()) {
+            error!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_DATA::WARNING Form data does not have application/x-www-form-urlencoded, application/json, application/xml or multipart/form-data content type.");
+            return rocket::Outcome::Forward(data);
+        }
+
+        let content_type = request.content_type().map_or("unsupported content type", |ct| {
+            if ct.is_form() { "application" } else if ct.is_json() { "json" } else if is_xml(&ct) { "xml" } else { "multipart" }
+        });
+
+        let size_limit = limit.unwrap_or_else(|| FormHashMap::size_limit(request, content_type));
+        let mut reader = LimitedReader { inner: data.open(), remaining: size_limit, exceeded: false };
+
+        let result = if content_type == "multipart" {
+            let boundary = match request.content_type().and_then(|ct| multipart::boundary(&ct)) {
+                Some(boundary) => boundary,
+                None => {
+                    let message = "::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_DATA::ERROR Missing boundary in multipart/form-data content type".to_string();
+                    error!("{}", message);
+                    return rocket::Outcome::Failure((Status::BadRequest, GenericError::Basic(message)));
+                },
+            };
+
+            let default_config = MultipartConfig::new();
+            let managed_config = State::<MultipartConfig>::from_request(request).succeeded();
+            let config = managed_config.as_ref().map_or(&default_config, |config| config.inner());
+
+            FormHashMap::from_multipart_data(&boundary, &mut reader, config)
+        } else {
+            let mut buffer = String::new();
+            reader.read_to_string(&mut buffer)
+                .or_else(|err| amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_DATA::ERROR IO Error: {}", err.description())) )
+                .and_then(|_| FormHashMap::new(content_type, buffer))
+        };
+
+        if reader.exceeded {
+            let message = format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_DATA::ERROR Form data is bigger than the size limit of {} bytes", size_limit);
+            error!("{}", message);
+            return rocket::Outcome::Failure((Status::PayloadTooLarge, GenericError::Basic(message)));
+        }
+
+        result
+            .map(|form| match State::<FormConfig>::from_request(request).succeeded() {
+                Some(config) => config.inner().apply(form),
+                None => form,
+            }).or_else(|error_message| {
+                error!("{}", error_message);
+                Err(error_message)
+            }).into_outcome() // Note: trait implemented by Rocket FromData for Result<S,E> `fn into_outcome(self, status: Status) -> Outcome<S, E>`
+    }
+}
+
+impl FormLimits {
+    /// Create limits falling back to the config extras / defaults for every content type
+    pub fn new() -> FormLimits {
+        FormLimits::default()
+    }
+
+    /// Size limit of `application/x-www-form-urlencoded` bodies
+    pub fn application(mut self, bytes: u64) -> FormLimits {
+        self.application = Some(bytes);
+        self
+    }
+
+    /// Size limit of `application/json` bodies
+    pub fn json(mut self, bytes: u64) -> FormLimits {
+        self.json = Some(bytes);
+        self
+    }
+
+    /// Size limit of `application/xml` & `text/xml` bodies
+    pub fn xml(mut self, bytes: u64) -> FormLimits {
+        self.xml = Some(bytes);
+        self
+    }
+
+    /// Size limit of `multipart/form-data` bodies
+    pub fn multipart(mut self, bytes: u64) -> FormLimits {
+        self.multipart = Some(bytes);
+        self
+    }
+
+    // Returns the limit of `content_type` ("application", "json", "xml" or "multipart"), if set
+    fn get(&self, content_type: &str) -> Option<u64> {
+        match content_type {
+            "application" => self.application,
+            "json" => self.json,
+            "xml" => self.xml,
+            "multipart" => self.multipart,
+            _ => None,
+        }
+    }
+}
+
+impl FormConfig {
+    /// Create a config without defaults, aliases nor sanitization
+    pub fn new() -> FormConfig {
+        FormConfig::default()
+    }
+
+    /// Default values of the missing parameters (see `FormHashMap::with_defaults()`), merged with the previous ones
+    pub fn defaults(mut self, defaults: Value) -> FormConfig {
+        match defaults {
+            Value::Object(defaults) => self.defaults.extend(defaults),
+            _ => warn!("::AMIWO::CONTRIB::ROCKET::FORM_CONFIG::DEFAULTS::WARNING Ignoring non object defaults {}", defaults),
+        }
+        self
+    }
+
+    /// Accept `alias` as an alternate name of the parameter `name` (see `FormHashMap::alias()`)
+    pub fn alias<N: Into<String>, A: Into<String>>(mut self, name: N, alias: A) -> FormConfig {
+        self.aliases.push((name.into(), alias.into()));
+        self
+    }
+
+    /// Sanitize the values of every parameter with `sanitizer` (see `FormHashMap::sanitized()`), unless overridden by `sanitize_field()`
+    pub fn sanitize(mut self, sanitizer: Sanitizer) -> FormConfig {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// Sanitize the values of the parameter `name` with `sanitizer` instead of the one set by `sanitize()`
+    /// (e.g. `Sanitizer::new()` to keep a password untouched)
+    pub fn sanitize_field<N: Into<String>>(mut self, name: N, sanitizer: Sanitizer) -> FormConfig {
+        let name = name.into();
+        self.field_sanitizers.retain(|&(ref field, _)| *field != name);
+        self.field_sanitizers.push((name, sanitizer));
+        self
+    }
+
+    /// Apply the aliases, the sanitization then the defaults to `form`
+    pub fn apply<'s>(&self, form: FormHashMap<'s>) -> FormHashMap<'s> {
+        let mut form = self.aliases.iter().fold(form, |form, &(ref name, ref alias)| form.alias(name.as_str(), alias));
+        form.map = ::std::mem::replace(&mut form.map, Map::new()).into_iter()
+            .map(|(key, value)| {
+                let value = self.field_sanitizers.iter()
+                    .find(|&&(ref field, _)| *field == key)
+                    .map_or(&self.sanitizer, |&(_, ref sanitizer)| sanitizer)
+                    .sanitize_value(value);
+                (key, value)
+            }).collect();
+        form.with_defaults(Value::Object(self.defaults.clone()))
+    }
+}
+
+impl Sanitizer {
+    /// Create a sanitizer leaving the values untouched
+    pub fn new() -> Sanitizer {
+        Sanitizer::default()
+    }
+
+    /// Remove the leading & trailing whitespace
+    pub fn trim(mut self, enabled: bool) -> Sanitizer {
+        self.trim = enabled;
+        self
+    }
+
+    /// Replace every run of whitespace (spaces, tabs, new lines, ...) by a single space
+    pub fn collapse_whitespace(mut self, enabled: bool) -> Sanitizer {
+        self.collapse_whitespace = enabled;
+        self
+    }
+
+    /// Remove the control characters other than whitespace (e.g. `\0`, `\u{1b}`)
+    pub fn strip_control_chars(mut self, enabled: bool) -> Sanitizer {
+        self.strip_control_chars = enabled;
+        self
+    }
+
+    /// Remove the HTML tags (`<b>`, `</p>`, `<!-- -->`, ...), keeping their text
+    pub fn strip_html(mut self, enabled: bool) -> Sanitizer {
+        self.strip_html = enabled;
+        self
+    }
+
+    /// Returns the sanitized `value`
+    pub fn sanitize(&self, value: &str) -> String {
+        let mut value = if self.strip_html { strip_html_tags(value) } else { value.to_string() };
+        if self.strip_control_chars {
+            value = value.chars().filter(|c| !c.is_control() || c.is_whitespace()).collect();
+        }
+        if self.collapse_whitespace {
+            value = collapse_whitespace(&value);
+        }
+        if self.trim {
+            value = value.trim().to_string();
+        }
+        value
+    }
+
+    // Sanitize the strings of `value`, recursively in arrays & objects
+    fn sanitize_value(&self, value: Value) -> Value {
+        match value {
+            Value::String(string) => Value::String(self.sanitize(&string)),
+            Value::Array(vect) => Value::Array(vect.into_iter().map(|value| self.sanitize_value(value)).collect()),
+            Value::Object(map) => Value::Object(map.into_iter().map(|(key, value)| (key, self.sanitize_value(value))).collect()),
+            value => value,
+        }
+    }
+}
+
+impl<'s, L: FormLimit> LimitedFormHashMap<'s, L> {
+    /// Returns the wrapped FormHashMap
+    pub fn into_inner(self) -> FormHashMap<'s> {
+        self.form
+    }
+}
+
+impl ResponseJSON {
+    /// Prune `data` down to the fields requested through the `fields` parameter of `params` (see `ResponseJSON::filter_fields()`)
+    ///
+    /// ```rust,ignore
+    /// #[get("/users?<params>")]
+    /// fn users(params: FormHashMap) -> ResponseJSON { 
+    ///     ResponseJSON::ok().data(...).filter_fields_from(&params)
+    /// }
+    /// ```
+    pub fn filter_fields_from(self, params: &FormHashMap) -> ResponseJSON {
+        let fields = params.fields();
+        let fields : Vec<&str> = fields.iter().map(|field| field.as_str()).collect();
+        self.filter_fields(&fields)
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// `application/xml`, `text/xml` or a `+xml` suffixed content type (e.g. `application/soap+xml`)
+fn is_xml(content_type: &ContentType) -> bool {
+    content_type.is_xml()
+        || (content_type.ttype == "application" && content_type.subtype == "xml")
+        || content_type.subtype.as_str().to_lowercase().ends_with("+xml")
+}
+
+// Parse the strings of `value` (recursively in arrays) as JSON, keeping those which aren't valid JSON
+fn parse_strings(value: &Value) -> Value {
+    match *value {
+        Value::String(ref string) => serde_json::from_str(string).unwrap_or_else(|_| value.clone()),
+        Value::Array(ref vect) => Value::Array(vect.iter().map(parse_strings).collect()),
+        _ => value.clone(),
+    }
+}
+
+// Convert the strings of `value` (recursively) holding a JSON boolean or number into that boolean or number,
+// if it is written back as the same string
+fn coerce(value: Value) -> Value {
+    match value {
+        Value::String(string) => match serde_json::from_str::<Value>(&string) {
+            Ok(ref coerced) if (coerced.is_boolean() || coerced.is_number()) && coerced.to_string() == string => coerced.clone(),
+            _ => Value::String(string),
+        },
+        Value::Array(vect) => Value::Array(vect.into_iter().map(coerce).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(key, value)| (key, coerce(value))).collect()),
+        value => value,
+    }
+}
+
+// Insert the values of `defaults` missing (or `null`) in `map`, recursively in nested objects
+fn fill_defaults(map: &mut Map<String, Value>, defaults: &Map<String, Value>) {
+    for (key, default) in defaults.iter() {
+        let missing = map.get(key).map_or(true, |value| value.is_null());
+        if missing {
+            map.insert(key.clone(), default.clone());
+        } else if let (Some(&mut Value::Object(ref mut nested)), &Value::Object(ref nested_defaults)) = (map.get_mut(key), default) {
+            fill_defaults(nested, nested_defaults);
+        }
+    }
+}
+
+
+// Remove the HTML tags of `value`: a `<` followed by a letter, `/`, `!` or `?` up to the next `>` (a lone `<` is kept)
+fn strip_html_tags(value: &str) -> String {
+    let mut stripped = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        stripped.push_str(&rest[..start]);
+        let is_tag = rest[start + 1..].chars().next().map_or(false, |c| c.is_alphabetic() || c == '/' || c == '!' || c == '?');
+        match rest[start..].find('>') {
+            Some(end) if is_tag => rest = &rest[start + end + 1..],
+            _ => {
+                stripped.push('<');
+                rest = &rest[start + 1..];
+            },
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+// Replace the runs of whitespace of `value` by a single space
+fn collapse_whitespace(value: &str) -> String {
+    let mut collapsed = String::with_capacity(value.len());
+    let mut in_whitespace = false;
+    for c in value.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace { collapsed.push(' '); }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    collapsed
+}
+
+// String of a string, boolean or number `value`, `None` for other values
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match *value {
+        Value::String(ref string) => Some(string.clone()),
+        Value::Bool(_) | Value::Number(_) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+// Convert the booleans & numbers of `value` (recursively in arrays) into strings
+fn stringify_scalars(value: &Value) -> Value {
+    match *value {
+        Value::Bool(_) | Value::Number(_) => Value::String(value.to_string()),
+        Value::Array(ref vect) => Value::Array(vect.iter().map(stringify_scalars).collect()),
+        _ => value.clone(),
+    }
+}
+
 // =======================================================================
 // EXTERNAL TRAITS IMPLEMENTATION
 // =======================================================================
 /// Parses a `FormHashMap` from incoming POST/... form data.
 ///
-/// - If the content type of the request data is not
-/// `application/x-www-form-urlencoded` or `application/json`, `Forward`s the request.
+/// - If the content type of the request data is not `application/x-www-form-urlencoded`,
+/// `application/json`, `application/xml` (`text/xml`, `+xml`) or `multipart/form-data`, `Forward`s the request.
+/// - If the body is bigger than the size limit of its content type (see `FormLimits`), a `Failure` with status code
+/// `PayloadTooLarge` is returned.
 /// - If the form string is malformed, a `Failure` with status code 
 /// `BadRequest` is returned. 
 /// - Finally, if reading the incoming stream fails, returns a `Failure` with status code
@@ -162,29 +857,49 @@ impl<'f> FromData for FormHashMap<'f> {
     type Error = GenericError;
 
     fn from_data(request: &Request, data: Data) -> rocket::data::Outcome<Self, Self::Error> {
-        if !request.content_type().map_or(false, |ct| ct.is_form() || ct.is_json()) {
-            error!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_DATA::WARNING Form data does not have application/x-www-form-urlencoded or application/json content type.");
-            return rocket::Outcome::Forward(data);
+        FormHashMap::from_request_data(request, data, None)
+    }
+}
+
+/// Parses a `FormHashMap` like its `FromData` implementation, whatever the content type the size limit being `L::limit()`
+impl<'f, L: FormLimit> FromData for LimitedFormHashMap<'f, L> {
+    type Error = GenericError;
+
+    fn from_data(request: &Request, data: Data) -> rocket::data::Outcome<Self, Self::Error> {
+        match FormHashMap::from_request_data(request, data, Some(L::limit())) {
+            rocket::Outcome::Success(form) => rocket::Outcome::Success(LimitedFormHashMap { form: form, _limit: PhantomData }),
+            rocket::Outcome::Failure(failure) => rocket::Outcome::Failure(failure),
+            rocket::Outcome::Forward(data) => rocket::Outcome::Forward(data),
         }
+    }
+}
+
+impl<'s, L: FormLimit> Deref for LimitedFormHashMap<'s, L> {
+    type Target = FormHashMap<'s>;
 
-        let content_type = request.content_type().map_or("unsupported content type", |ct| if ct.is_form() { "application" } else { "json" });
+    fn deref(&self) -> &FormHashMap<'s> {
+        &self.form
+    }
+}
 
-        let size_limit = rocket::config::active()
-            .and_then(|c| c.extras.get(&("limits.".to_string() + content_type))) // TODO: remove placeholder when upgrading to rocket version > 0.2.6
-            // .and_then(|c| c.limits.get("application") // In next version
-            .and_then(|limit| limit.as_integer())
-            .unwrap_or_else(|| if content_type == "json" { 1<<20 } else { 32768 }) as u64;
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            // the limit is reached => make sure there is nothing left to read
+            let mut probe = [0; 1];
+            return match self.inner.read(&mut probe)? {
+                0 => Ok(0),
+                _ => {
+                    self.exceeded = true;
+                    Err(io::Error::new(io::ErrorKind::Other, "payload too large"))
+                },
+            };
+        }
 
-        let mut buffer = String::new();
-        data.open()
-            .take(size_limit)
-            .read_to_string(&mut buffer)
-            .or_else(|err| amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::FORM_HASHMAP::FROM_DATA::ERROR IO Error: {}", err.description())) )
-            .and_then(|_| FormHashMap::new(content_type, buffer))
-            .or_else(|error_message| {
-                error!("{}", error_message);
-                Err(error_message)
-            }).into_outcome() // Note: trait implemented by Rocket FromData for Result<S,E> `fn into_outcome(self, status: Status) -> Outcome<S, E>`
+        let max_size = cmp::min(buf.len() as u64, self.remaining) as usize;
+        let size = self.inner.read(&mut buf[..max_size])?;
+        self.remaining -= size as u64;
+        Ok(size)
     }
 }
 
@@ -220,6 +935,33 @@ impl<'f> Debug for FormHashMap<'f> {
     }
 }
 
+/// Iterate over the (name, value) pairs of the parameters
+impl<'a, 's> IntoIterator for &'a FormHashMap<'s> {
+    type Item = (&'a String, &'a Value);
+    type IntoIter = map::Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter()
+    }
+}
+
+/// Consume the FormHashMap, iterating over the (name, value) pairs of the parameters (uploaded files are dropped)
+impl<'s> IntoIterator for FormHashMap<'s> {
+    type Item = (String, Value);
+    type IntoIter = map::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+/// Serialize the parameters as a map (see `to_json_value()`)
+impl<'s> Serialize for FormHashMap<'s> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.map.serialize(serializer)
+    }
+}
+
 /// Access an element of this type. Panics if the key is not defined
 impl<'a, I: AsRef<str>> Index<I> for FormHashMap<'a> {
     type Output = Value;
@@ -237,7 +979,10 @@ mod tests {
     #![allow(unmounted_route)]
     #![allow(non_snake_case)]
 
-    use super::FormHashMap;
+    use super::{ FormConfig, FormHashMap, FormLimit, FormLimits, LimitedFormHashMap, LimitedReader, Sanitizer };
+    use contrib::rocket::MultipartConfig;
+    use serde_json::Value;
+    use types::{ OneOrMany, ResponseJSON };
 
     use rocket;
     use rocket::testing::MockRequest;
@@ -324,5 +1069,418 @@ mod tests {
         assert_eq!(body_str, Some("It's working !".to_string()));
     }
 
+    #[test]
+    fn FormHashMap_test_multipart() {
+        let body = "--XyZ\r\n\
+            Content-Disposition: form-data; name=\"name\"\r\n\r\n\
+            Boris\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"tags[]\"\r\n\r\n\
+            a\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"avatar\"; filename=\"me.txt\"\r\n\
+            Content-Type: text/plain\r\n\r\n\
+            Hello\r\n\
+            --XyZ--\r\n";
+
+        let map = FormHashMap::from_multipart_data("XyZ", body.as_bytes(), &MultipartConfig::new()).unwrap();
+        assert_eq!(map.get("name"), Some(&json!("Boris")));
+        assert_eq!(map.get("tags"), Some(&json!(["a"])));
+        assert_eq!(map.get("avatar"), None);
+        assert_eq!(map.files().len(), 1);
+        assert_eq!(map.file("avatar").map(|file| file.filename()), Some("me.txt"));
+        assert_eq!(map.file("avatar").and_then(|file| file.bytes()), Some(&b"Hello"[..]));
+        assert!(map.file("name").is_none());
+    }
+
+    #[test]
+    fn FormHashMap_test_multipart_route() {
+        #[post("/test", data= "<params>")]
+        fn test_route(params: FormHashMap) -> String {
+            assert_eq!(params.get("a"), Some(&json!("b")));
+            params.file("upload").map_or("no file".to_string(), |file| String::from_utf8_lossy(file.bytes().unwrap()).into_owned())
+        }
+
+        let rocket = rocket::ignite()
+            .mount("/post", routes![test_route]);
+
+        let mut req = MockRequest::new(Method::Post, "/post/test")
+            .header(ContentType::with_params("multipart", "form-data", Some("boundary=XyZ")))
+            .body("--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nb\r\n\
+                --XyZ\r\nContent-Disposition: form-data; name=\"upload\"; filename=\"a.txt\"\r\n\r\nIt's working !\r\n--XyZ--");
+
+        let mut response = req.dispatch_with(&rocket);
+        let body_str = response.body().and_then(|b| b.into_string());
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(body_str, Some("It's working !".to_string()));
+    }
+
+    #[test]
+    fn FormHashMap_test_get_as() {
+        let map = FormHashMap::from_application_data("page=2&name=Boris&ids=1&ids=2&active=true&zip=00123".to_string()).unwrap();
+        assert_eq!(map.get_as::<u32>("page").unwrap(), 2);
+        assert_eq!(map.get_as::<String>("name").unwrap(), "Boris");
+        assert_eq!(map.get_as::<Vec<u32>>("ids").unwrap(), vec![1, 2]);
+        assert_eq!(map.get_as::<bool>("active").unwrap(), true);
+        assert_eq!(map.get_as::<String>("zip").unwrap(), "00123");
+
+        let err = map.get_as::<u32>("missing").unwrap_err();
+        assert_eq!(err.http_code(), 400);
+        assert!(map.get_as::<u32>("name").is_err());
+
+        assert_eq!(map.get_opt_as::<u32>("page").unwrap(), Some(2));
+        assert_eq!(map.get_opt_as::<u32>("missing").unwrap(), None);
+        assert!(map.get_opt_as::<u32>("name").is_err());
+
+        let map = FormHashMap::from_json_data(r#"{ "page": 3, "limit": null }"#.to_string()).unwrap();
+        assert_eq!(map.get_as::<u8>("page").unwrap(), 3);
+        assert_eq!(map.get_opt_as::<u8>("limit").unwrap(), None);
+    }
+
+    #[test]
+    fn FormHashMap_test_brackets() {
+        let map = FormHashMap::from_application_data("user[name]=bob&user[tags][]=x&user[tags][]=y&user[address][city]=Cotonou".to_string()).unwrap();
+        assert_eq!(map["user"], json!({ "name": "bob", "tags": ["x", "y"], "address": { "city": "Cotonou" } }));
+
+        let map = FormHashMap::from_application_data("user%5Bname%5D=bob+b&list[][id]=1&list[][name]=a&list[][id]=2".to_string()).unwrap();
+        assert_eq!(map["user"], json!({ "name": "bob b" }));
+        assert_eq!(map["list"], json!([{ "id": "1", "name": "a" }, { "id": "2" }]));
+
+        // not the bracket notation => literal keys
+        let map = FormHashMap::from_application_data("a[b=1&[c]=2&d[e]f=3".to_string()).unwrap();
+        assert_eq!(map.get("a[b"), Some(&json!("1")));
+        assert_eq!(map.get("[c]"), Some(&json!("2")));
+        assert_eq!(map.get("d[e]f"), Some(&json!("3")));
+
+        assert!(FormHashMap::from_application_data("a=1&a[b]=2".to_string()).is_err());
+    }
+
+    #[test]
+    fn FormHashMap_test_arrays() {
+        let map = FormHashMap::from_application_data("ids[]=1&id=2&tags[]=a&tags[]=b&names=x&names=y".to_string()).unwrap();
+        assert_eq!(map["ids"], json!(["1"]));
+        assert_eq!(map["id"], json!("2"));
+        assert_eq!(map["tags"], json!(["a", "b"]));
+
+        assert_eq!(map.get_all("ids"), vec![&json!("1")]);
+        assert_eq!(map.get_all("ids[]"), vec![&json!("1")]);
+        assert_eq!(map.get_all("id"), vec![&json!("2")]);
+        assert_eq!(map.get_all("names"), vec![&json!("x"), &json!("y")]);
+        assert!(map.get_all("missing").is_empty());
+    }
+
+    #[test]
+    fn FormHashMap_test_limited_reader() {
+        use std::io::Read;
+
+        let mut buffer = String::new();
+        let mut reader = LimitedReader { inner: "a=b&c=d".as_bytes(), remaining: 7, exceeded: false };
+        assert!(reader.read_to_string(&mut buffer).is_ok());
+        assert_eq!(buffer, "a=b&c=d");
+        assert_eq!(reader.exceeded, false);
+
+        let mut buffer = String::new();
+        let mut reader = LimitedReader { inner: "a=b&c=d".as_bytes(), remaining: 6, exceeded: false };
+        assert!(reader.read_to_string(&mut buffer).is_err());
+        assert_eq!(reader.exceeded, true);
+    }
+
+    #[test]
+    fn FormHashMap_test_form_limits() {
+        let limits = FormLimits::new().json(64).multipart(1024);
+        assert_eq!(limits.get("application"), None);
+        assert_eq!(limits.get("json"), Some(64));
+        assert_eq!(limits.get("multipart"), Some(1024));
+        assert_eq!(limits.get("xml"), None);
+        assert_eq!(FormLimits::new().xml(256).get("xml"), Some(256));
+    }
+
+    #[test]
+    fn FormHashMap_test_xml() {
+        let map = FormHashMap::new("xml", r#"<?xml version="1.0"?>
+            <callback>
+                <status>paid</status>
+                <ref>T&amp;1</ref>
+                <item>a</item>
+                <item>b</item>
+            </callback>"#.to_string()).unwrap();
+        assert_eq!(map["status"], json!("paid"));
+        assert_eq!(map["ref"], json!("T&1"));
+        assert_eq!(map.get_all("item"), vec![&json!("a"), &json!("b")]);
+        assert!(map.raw_form_string().starts_with("<?xml"));
+
+        assert!(FormHashMap::new("xml", "<callback><status>paid</callback>".to_string()).is_err());
+
+        assert!(super::is_xml(&ContentType::XML));
+        assert!(super::is_xml(&"application/xml".parse().unwrap()));
+        assert!(super::is_xml(&"application/soap+xml; charset=utf-8".parse().unwrap()));
+        assert!(!super::is_xml(&ContentType::JSON));
+    }
+
+    #[test]
+    fn FormHashMap_test_xml_route() {
+        #[post("/test", data= "<params>")]
+        fn test_route(params: FormHashMap) -> &'static str {
+            assert_eq!(params.get("status"), Some(&json!("paid")));
+            "It's working !"
+        }
+
+        let rocket = rocket::ignite()
+            .mount("/post", routes![test_route]);
+
+        let mut req = MockRequest::new(Method::Post, "/post/test")
+            .header(ContentType::new("application", "xml"))
+            .body("<callback><status>paid</status></callback>");
+        assert_eq!(req.dispatch_with(&rocket).status(), Status::Ok);
+    }
+
+    #[test]
+    fn FormHashMap_test_limit_route() {
+        struct TinyLimit;
+
+        impl FormLimit for TinyLimit {
+            fn limit() -> u64 { 8 }
+        }
+
+        #[post("/test", data= "<params>")]
+        fn test_route(params: LimitedFormHashMap<TinyLimit>) -> &'static str {
+            assert_eq!(params.get("a"), Some(&json!("b")));
+            "It's working !"
+        }
+
+        let rocket = rocket::ignite()
+            .mount("/post", routes![test_route]);
+
+        let mut req = MockRequest::new(Method::Post, "/post/test")
+            .header(ContentType::Form)
+            .body("a=b");
+        assert_eq!(req.dispatch_with(&rocket).status(), Status::Ok);
+
+        let mut req = MockRequest::new(Method::Post, "/post/test")
+            .header(ContentType::Form)
+            .body("a=b&c=0123456789");
+        assert_eq!(req.dispatch_with(&rocket).status(), Status::PayloadTooLarge);
+    }
+
+    #[test]
+    fn FormHashMap_test_get_one_or_many() {
+        let map = FormHashMap::from_application_data("a=b1&a=b2&b=c&ids[]=1&user[name]=bob".to_string()).unwrap();
+        assert_eq!(map.get_one_or_many("a"), Some(OneOrMany::Many(vec!["b1".to_string(), "b2".to_string()])));
+        assert_eq!(map.get_one_or_many("b"), Some(OneOrMany::One("c".to_string())));
+        assert_eq!(map.get_one_or_many("ids[]"), Some(OneOrMany::Many(vec!["1".to_string()])));
+        assert_eq!(map.get_one_or_many("user"), None);
+        assert_eq!(map.get_one_or_many("missing"), None);
+
+        let map = FormHashMap::from_json_data(r#"{ "page": 3, "flags": [true, "x"], "nested": [{}], "limit": null }"#.to_string()).unwrap();
+        assert_eq!(map.get_one_or_many("page"), Some(OneOrMany::One("3".to_string())));
+        assert_eq!(map.get_one_or_many("flags"), Some(OneOrMany::Many(vec!["true".to_string(), "x".to_string()])));
+        assert_eq!(map.get_one_or_many("nested"), None);
+        assert_eq!(map.get_one_or_many("limit"), None);
+    }
+
+    #[test]
+    fn FormHashMap_test_push() {
+        let mut map = FormHashMap::from_application_data("a=b1".to_string()).unwrap();
+        map.push("a", "b2").unwrap()
+            .push("ids[]", 1).unwrap()
+            .push("user[name]", "bob").unwrap()
+            .push("user[tags][]", "x").unwrap();
+        assert_eq!(map.to_json_value(), json!({ "a": ["b1", "b2"], "ids": [1], "user": { "name": "bob", "tags": ["x"] } }));
+        assert!(map.push("a[b]", "c").is_err());
+    }
+
+    #[test]
+    fn FormHashMap_test_take() {
+        let mut map = FormHashMap::from_application_data("api_key=secret&a=b".to_string()).unwrap();
+        assert_eq!(map.take("api_key"), Some(json!("secret")));
+        assert_eq!(map.take("api_key"), None);
+        assert_eq!(map.to_query_string(), "a=b");
+    }
+
+    #[test]
+    fn FormHashMap_test_iter() {
+        let map = FormHashMap::from_application_data("a=b1&a=b2&b=c".to_string()).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.is_empty(), false);
+        assert_eq!(map.contains_key("a"), true);
+        assert_eq!(map.contains_key("c"), false);
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(map.values().collect::<Vec<_>>(), vec![&json!(["b1", "b2"]), &json!("c")]);
+        assert_eq!(map.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+
+        let mut count = 0;
+        for (_, value) in &map {
+            assert!(!value.is_null());
+            count += 1;
+        }
+        assert_eq!(count, 2);
+
+        let pairs : Vec<(String, Value)> = map.into_iter().collect();
+        assert_eq!(pairs[1], ("b".to_string(), json!("c")));
+
+        assert!(FormHashMap::from_application_data("".to_string()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn FormHashMap_test_move() {
+        let maps : Vec<FormHashMap> = vec!["a=b", "a=c"].into_iter()
+            .map(|form_string| FormHashMap::from_application_data(form_string.to_string()).unwrap())
+            .collect();
+        let boxed = Box::new(maps);
+        assert_eq!(boxed[0]["a"], json!("b"));
+        assert_eq!(boxed[1]["a"], json!("c"));
+        assert_eq!(boxed[1].raw_form_string(), "a=c");
+
+        let map = FormHashMap::from_json_data(r#"{ "a": { "b": 1 } }"#.to_string()).unwrap();
+        let moved = Some(map).unwrap();
+        assert_eq!(moved["a"], json!({ "b": 1 }));
+    }
+
+    #[test]
+    fn FormHashMap_test_coerced() {
+        let map = FormHashMap::from_application_data("page=2&ratio=-1.5&active=true&zip=00123&plus=%2B1&name=Boris&ids[]=1&ids[]=x&user[admin]=false".to_string())
+            .unwrap()
+            .coerced();
+        assert_eq!(map["page"], json!(2));
+        assert_eq!(map["ratio"], json!(-1.5));
+        assert_eq!(map["active"], json!(true));
+        assert_eq!(map["zip"], json!("00123"));
+        assert_eq!(map["plus"], json!("+1"));
+        assert_eq!(map["name"], json!("Boris"));
+        assert_eq!(map["ids"], json!([1, "x"]));
+        assert_eq!(map["user"], json!({ "admin": false }));
+
+        let map = FormHashMap::from_application_data("price=1.50&kilo=1e3&id=123456789012345678901234567890&max=18446744073709551615&zero=-0".to_string())
+            .unwrap()
+            .coerced();
+        assert_eq!(map["price"], json!("1.50"));
+        assert_eq!(map["kilo"], json!("1e3"));
+        assert_eq!(map["id"], json!("123456789012345678901234567890"));
+        assert_eq!(map["max"], json!(18446744073709551615u64));
+        assert_eq!(map["zero"], json!("-0"));
+
+        assert_eq!(map.get_as::<u32>("page").unwrap(), 2);
+        assert_eq!(map.get_as::<String>("page").unwrap(), "2");
+        assert_eq!(map.get_as::<Vec<String>>("ids").unwrap(), vec!["1", "x"]);
+    }
+
+    #[test]
+    fn FormHashMap_test_to_query_string() {
+        let form_string = "a=b1&a=b2&name=Boris+B%C3%A9&user[tags][]=x&user[address][city]=New+York&ids[]=1&list[][id]=1&list[][id]=2&empty=";
+        let map = FormHashMap::from_application_data(form_string.to_string()).unwrap();
+        let query_string = map.to_query_string();
+        assert_eq!(query_string, "a[]=b1&a[]=b2&empty=&ids[]=1&list[][id]=1&list[][id]=2&name=Boris+B%C3%A9&user[address][city]=New+York&user[tags][]=x");
+        assert_eq!(FormHashMap::from_application_data(query_string).unwrap().to_json_value(), map.to_json_value());
+
+        let map = FormHashMap::from_json_data(r#"{ "page": 2, "active": true, "q": "a&b=c", "none": null }"#.to_string()).unwrap();
+        assert_eq!(map.to_query_string(), "active=true&none=&page=2&q=a%26b%3Dc");
+    }
+
+    #[test]
+    fn FormHashMap_test_serialize() {
+        let map = FormHashMap::from_application_data("a=b&c[d]=e".to_string()).unwrap();
+        assert_eq!(map.to_json_value(), json!({ "a": "b", "c": { "d": "e" } }));
+        assert_eq!(::serde_json::to_string(&map).unwrap(), r#"{"a":"b","c":{"d":"e"}}"#);
+    }
+
+    #[test]
+    fn FormHashMap_test_defaults() {
+        let map = FormHashMap::from_application_data("page=3&filter[name]=bob".to_string()).unwrap()
+            .with_defaults(json!({ "page": 1, "per_page": 20, "filter": { "name": "", "active": true } }));
+        assert_eq!(map["page"], json!("3"));
+        assert_eq!(map["per_page"], json!(20));
+        assert_eq!(map["filter"], json!({ "name": "bob", "active": true }));
+
+        let map = FormHashMap::from_json_data(r#"{ "page": null }"#.to_string()).unwrap().with_defaults(json!({ "page": 1 }));
+        assert_eq!(map["page"], json!(1));
+    }
+
+    #[test]
+    fn FormHashMap_test_alias() {
+        let map = FormHashMap::from_application_data("limit=50".to_string()).unwrap().alias("per_page", "limit");
+        assert_eq!(map.get("per_page"), Some(&json!("50")));
+        assert_eq!(map.get("limit"), None);
+
+        let map = FormHashMap::from_application_data("limit=50&per_page=10".to_string()).unwrap().alias("per_page", "limit");
+        assert_eq!(map.get("per_page"), Some(&json!("10")));
+        assert_eq!(map.get("limit"), None);
+    }
+
+    #[test]
+    fn FormHashMap_test_form_config() {
+        let config = FormConfig::new()
+            .defaults(json!({ "page": 1 }))
+            .defaults(json!({ "per_page": 20 }))
+            .alias("per_page", "limit");
+
+        let map = config.apply(FormHashMap::from_application_data("limit=50".to_string()).unwrap());
+        assert_eq!(map.to_json_value(), json!({ "page": 1, "per_page": "50" }));
+
+        let map = config.apply(FormHashMap::from_application_data("page=2".to_string()).unwrap());
+        assert_eq!(map.to_json_value(), json!({ "page": "2", "per_page": 20 }));
+    }
+
+    #[test]
+    fn FormHashMap_test_sanitizer() {
+        let sanitizer = Sanitizer::new();
+        assert_eq!(sanitizer.sanitize("  a \u{0} <b>b</b>  "), "  a \u{0} <b>b</b>  ");
+
+        let sanitizer = Sanitizer::new().trim(true).collapse_whitespace(true).strip_control_chars(true).strip_html(true);
+        assert_eq!(sanitizer.sanitize("  Hello\t\n <b>Boris</b>\u{0}!<!-- x --> "), "Hello Boris!");
+        assert_eq!(sanitizer.sanitize("1 < 2 <3 & a<b"), "1 < 2 <3 & a<b");
+        assert_eq!(Sanitizer::new().strip_html(true).sanitize("<script>alert(1)</script> x < y"), "alert(1) x < y");
+    }
+
+    #[test]
+    fn FormHashMap_test_sanitized() {
+        let form_string = "name=+Boris++B+&tags[]=+a+&password=+secret+";
+        let sanitizer = Sanitizer::new().trim(true).collapse_whitespace(true);
+
+        let sanitized = FormHashMap::from_application_data(form_string.to_string()).unwrap().sanitized(&sanitizer);
+        assert_eq!(sanitized.to_json_value(), json!({ "name": "Boris B", "tags": ["a"], "password": "secret" }));
+
+        let sanitized = FormHashMap::from_application_data(form_string.to_string()).unwrap().sanitized_field("name", &sanitizer);
+        assert_eq!(sanitized.to_json_value(), json!({ "name": "Boris B", "tags": [" a "], "password": " secret " }));
+    }
+
+    #[test]
+    fn FormHashMap_test_form_config_sanitize() {
+        let config = FormConfig::new()
+            .defaults(json!({ "page": " 1 " }))
+            .sanitize(Sanitizer::new().trim(true))
+            .sanitize_field("password", Sanitizer::new())
+            .sanitize_field("bio", Sanitizer::new().trim(true).strip_html(true));
+
+        let map = config.apply(FormHashMap::from_application_data("name=+bob+&password=+pw+&bio=<p>hi</p>+".to_string()).unwrap());
+        assert_eq!(map.to_json_value(), json!({ "name": "bob", "password": " pw ", "bio": "hi", "page": " 1 " }));
+    }
+
+    #[test]
+    fn FormHashMap_test_fields() {
+        let map = FormHashMap::from_application_data("fields=id,address.city&fields=name".to_string()).unwrap();
+        assert_eq!(map.fields(), vec!["id", "address.city", "name"]);
+
+        let json = ResponseJSON::ok()
+            .data(json!({ "id": 17, "name": "Boris", "age": 42, "address": { "city": "Cotonou", "country": "Benin" } }))
+            .filter_fields_from(&map);
+        assert_eq!(json.data, json!({ "id": 17, "name": "Boris", "address": { "city": "Cotonou" } }));
+
+        let map = FormHashMap::from_application_data("a=b".to_string()).unwrap();
+        assert!(map.fields().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "amiwo_schema")]
+    fn FormHashMap_test_validate() {
+        use contrib::schema::Validator;
+
+        let validator = Validator::new(json!({ "type": "object", "required": ["name"], "properties": { "name": { "type": "string" } } }));
+        assert!(FormHashMap::from_application_data("name=Boris".to_string()).unwrap().validate(&validator).is_ok());
+
+        let violations = FormHashMap::from_application_data("age=42".to_string()).unwrap().validate(&validator).unwrap_err();
+        assert_eq!(violations[0].to_string(), "missing required property `name`");
+    }
+
     // TODO: add test lifetime
 }
\ No newline at end of file
@@ -1,3 +1,24 @@
+#[cfg(feature = "amiwo_gzip")]
+mod compression;
+mod catchers;
+mod csv_response;
+mod etag;
 mod form_hashmap;
+#[cfg(feature = "amiwo_msgpack")]
+mod msgpack;
+mod multipart;
+mod ndjson_response;
+mod request_id;
+mod xml;
 
-pub use self::form_hashmap::FormHashMap;
\ No newline at end of file
+#[cfg(feature = "amiwo_gzip")]
+pub use self::compression::{ AcceptEncoding, Compressed, Encoding };
+pub use self::catchers::{ catchers, error_response };
+pub use self::csv_response::CsvResponse;
+pub use self::etag::{ ETagged, IfNoneMatch };
+pub use self::form_hashmap::{ FormConfig, FormHashMap, FormLimit, FormLimits, LimitedFormHashMap, Sanitizer };
+#[cfg(feature = "amiwo_msgpack")]
+pub use self::msgpack::{ MsgPack, MSGPACK_SIZE_LIMIT };
+pub use self::multipart::{ FormFile, MultipartConfig };
+pub use self::ndjson_response::NdJsonResponse;
+pub use self::request_id::RequestId;
\ No newline at end of file
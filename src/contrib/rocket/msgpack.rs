@@ -0,0 +1,178 @@
+//! File holding the MessagePack encoding / decoding of ResponseJSON and the associated Rocket types
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.1
+//!
+//! ## Release notes
+//! - v1.1 : uses `rmp-serde` 0.14 (every 0.13 release being yanked)
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::io::{ Cursor, Read };
+
+use rmp_serde;
+
+use rocket;
+use rocket::{ Data, Request, Response };
+use rocket::data::FromData;
+use rocket::http::{ ContentType, Status };
+use rocket::response::Responder;
+use serde::Serialize;
+use serde_json;
+use serde_json::Value;
+
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// CONSTANTS
+// =======================================================================
+/// Maximum size (in bytes) of a MessagePack request body
+pub const MSGPACK_SIZE_LIMIT: u64 = 1 << 20;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// A ResponseJSON exchanged as `application/msgpack` instead of JSON text.
+///
+/// - As a request guard (`FromData`) it decodes the MessagePack body into a ResponseJSON (see `ResponseJSON::from_msgpack()`)
+/// - As a responder it encodes the ResponseJSON (see `ResponseJSON::to_msgpack()`) and uses its `http_code` as HTTP status
+///
+/// ```rust,ignore
+/// #[post("/internal/users", data = "<payload>")]
+/// fn create(payload: MsgPack) -> MsgPack {
+///     MsgPack(ResponseJSON::ok().http_code(201).data(payload.0.data))
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MsgPack<T = Value>(pub ResponseJSON<T>);
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl<T: Serialize> ResponseJSON<T> {
+    /// Encode this ResponseJSON as MessagePack. Fields are encoded as a map, like in the JSON form
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, GenericError> {
+        serde_json::to_value(self)
+            .map_err(|serde_err| GenericError::Serde(serde_err))
+            .and_then(|value| {
+                rmp_serde::to_vec(&value)
+                    .or_else(|err| amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::MSGPACK::TO_MSGPACK::ERROR Unable to encode: {}", err)))
+            })
+    }
+}
+
+impl ResponseJSON {
+    /// Decode a ResponseJSON from MessagePack bytes.
+    /// Like `ResponseJSON::from_str()`, a valid MessagePack value which is not a ResponseJSON is wrapped into `data`
+    pub fn from_msgpack(bytes: &[u8]) -> Result<ResponseJSON, GenericError> {
+        rmp_serde::from_slice(bytes)
+            .or_else(|err| amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::MSGPACK::FROM_MSGPACK::ERROR Unable to decode: {}", err)))
+            .map(|value : Value| ResponseJSON::from_serde_value(value))
+    }
+}
+
+// =======================================================================
+// EXTERNAL TRAITS IMPLEMENTATION
+// =======================================================================
+/// Parses a `MsgPack` from an `application/msgpack` request body
+///
+/// - If the content type of the request data is not `application/msgpack`, `Forward`s the request.
+/// - If the body is larger than `MSGPACK_SIZE_LIMIT` or can't be decoded, returns a `Failure` with status code `BadRequest`.
+impl FromData for MsgPack {
+    type Error = GenericError;
+
+    fn from_data(request: &Request, data: Data) -> rocket::data::Outcome<Self, Self::Error> {
+        if !request.content_type().map_or(false, |ct| ct.ttype == "application" && ct.subtype == "msgpack") {
+            return rocket::Outcome::Forward(data);
+        }
+
+        let mut buffer = Vec::new();
+        let result = data.open()
+            .take(MSGPACK_SIZE_LIMIT + 1)
+            .read_to_end(&mut buffer)
+            .map_err(|io_err| GenericError::Io(io_err))
+            .and_then(|size| if size as u64 > MSGPACK_SIZE_LIMIT {
+                amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::MSGPACK::FROM_DATA::ERROR Body larger than {} bytes", MSGPACK_SIZE_LIMIT))
+            } else {
+                ResponseJSON::from_msgpack(&buffer)
+            });
+
+        match result {
+            Ok(rjson) => rocket::Outcome::Success(MsgPack(rjson)),
+            Err(err) => {
+                error!("{}", err);
+                rocket::Outcome::Failure((Status::BadRequest, err))
+            }
+        }
+    }
+}
+
+/// Responds with the MessagePack encoded ResponseJSON, Content-Type `application/msgpack`, `http_code` as HTTP status
+/// and the custom headers of the ResponseJSON
+impl<'r, T: Serialize> Responder<'r> for MsgPack<T> {
+    fn respond(self) -> Result<Response<'r>, Status> {
+        let status = Status::from_code(self.0.http_code).unwrap_or(Status::new(self.0.http_code, "<unknown code>"));
+        let bytes = self.0.to_msgpack().map_err(|err| {
+            error!("{}", err);
+            Status::InternalServerError
+        })?;
+
+        let mut response = Response::build()
+            .status(status)
+            .header(ContentType::new("application", "msgpack"))
+            .sized_body(Cursor::new(bytes))
+            .finalize();
+        for (name, value) in self.0.headers {
+            response.adjoin_raw_header(name, value);
+        }
+        Ok(response)
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use rocket::http::Status;
+    use rocket::response::Responder;
+
+    use types::{ IsResponseJSON, ResponseJSON };
+
+    use super::MsgPack;
+
+    #[test]
+    fn MsgPack_test_roundtrip() {
+        let json = ResponseJSON::ok()
+            .http_code(201)
+            .warning("partial result")
+            .data(json!({ "id": 17, "tags": ["a", "b"] }));
+        let decoded = ResponseJSON::from_msgpack(&json.to_msgpack().unwrap()).unwrap();
+        assert_eq!(decoded.http_code, 201);
+        assert_eq!(decoded.warnings, vec!["partial result"]);
+        assert_eq!(decoded.data, json!({ "id": 17, "tags": ["a", "b"] }));
+
+        let json = ResponseJSON::error().http_code(404).message("not found".to_string());
+        let decoded = ResponseJSON::from_msgpack(&json.to_msgpack().unwrap()).unwrap();
+        assert_eq!(decoded.is_error_json(), true);
+        assert_eq!(decoded.message, Some("not found".to_string()));
+
+        assert!(ResponseJSON::from_msgpack(&[0xc1]).is_err());
+    }
+
+    #[test]
+    fn MsgPack_test_responder() {
+        let json = ResponseJSON::ok().http_code(201).data(json!([1, 2]));
+        let bytes = json.to_msgpack().unwrap();
+
+        let mut response = MsgPack(json).respond().unwrap();
+        assert_eq!(response.status(), Status::Created);
+        assert_eq!(response.header_values("Content-Type").next(), Some("application/msgpack"));
+        assert_eq!(response.body().and_then(|b| b.into_bytes()), Some(bytes));
+    }
+}
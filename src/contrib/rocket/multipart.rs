@@ -0,0 +1,614 @@
+//! File holding the FormFile & MultipartConfig types and the streaming `multipart/form-data` (RFC 7578) parser used by FormHashMap
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.3
+//!
+//! ## Release notes
+//! - v1.3 : the temp files get a random name & are readable by their owner only (mode `0600` on Unix)
+//! - v1.2 : text fields are returned in order as (name, value) pairs, to be inserted like urlencoded parameters
+//! - v1.1 : the body is parsed as a stream, files over `MultipartConfig::spool_threshold()` being written to a temp directory
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fs;
+use std::fs::{ File, OpenOptions };
+use std::hash::{ BuildHasher, Hasher };
+use std::io;
+use std::io::{ Read, Write };
+use std::path::{ Path, PathBuf };
+use std::sync::atomic::{ AtomicUsize, Ordering, ATOMIC_USIZE_INIT };
+use std::time::{ Duration, SystemTime, UNIX_EPOCH };
+
+use rocket::http::ContentType;
+
+use error::GenericError;
+
+// =======================================================================
+// STATIC VARIABLES
+// =======================================================================
+static TEMP_FILE_COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// Size of the chunks read from the body
+const CHUNK_SIZE: usize = 8192;
+
+// Maximum size of the headers of a part
+const MAX_HEADERS_SIZE: usize = 16384;
+
+// Number of random names tried to create a temp file
+const MAX_TEMP_FILE_ATTEMPTS: usize = 8;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Configuration of the `multipart/form-data` parsing, to be managed by Rocket.
+/// By default files are buffered in memory; set a spool threshold to stream bigger files to a temp directory
+///
+/// ```rust,ignore
+/// rocket::ignite()
+///     .manage(MultipartConfig::new().spool_threshold(1 << 20).temp_dir("/var/tmp/uploads"))
+///     .mount("/", routes![upload])
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultipartConfig {
+    spool_threshold: Option<u64>,
+    temp_dir: PathBuf,
+}
+
+/// A file uploaded through a `multipart/form-data` form, held in memory or spooled to a temp file.
+/// Spooled files are deleted when the FormFile is dropped, use `persist()` to keep them
+#[derive(Debug)]
+pub struct FormFile {
+    name: String,
+    filename: String,
+    content_type: Option<ContentType>,
+    len: u64,
+    content: Content,
+}
+
+// Content of a FormFile
+#[derive(Debug)]
+enum Content {
+    Memory(Vec<u8>),
+    Disk(TempFile),
+}
+
+// A temp file deleted when dropped
+#[derive(Debug)]
+struct TempFile {
+    path: PathBuf,
+}
+
+// Destination of the content of a part while it is being read
+enum Sink {
+    Memory(Vec<u8>),
+    Disk(TempFile, File),
+}
+
+// Buffered reader over the body
+struct Stream<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    eof: bool,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl MultipartConfig {
+    /// Create a config buffering all files in memory, `env::temp_dir()` being the temp directory
+    pub fn new() -> MultipartConfig {
+        MultipartConfig {
+            spool_threshold: None,
+            temp_dir: env::temp_dir(),
+        }
+    }
+
+    /// Files bigger than `bytes` are streamed to the temp directory instead of being buffered in memory
+    pub fn spool_threshold(mut self, bytes: u64) -> MultipartConfig {
+        self.spool_threshold = Some(bytes);
+        self
+    }
+
+    /// Directory where the spooled files are written
+    pub fn temp_dir<P: Into<PathBuf>>(mut self, directory: P) -> MultipartConfig {
+        self.temp_dir = directory.into();
+        self
+    }
+
+    /// Returns the spool threshold, if any
+    pub fn get_spool_threshold(&self) -> Option<u64> {
+        self.spool_threshold
+    }
+
+    /// Returns the directory where the spooled files are written
+    pub fn get_temp_dir(&self) -> &Path {
+        &self.temp_dir
+    }
+}
+
+impl FormFile {
+    /// Returns the name of the form field holding the file
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the file on the client side (can be empty if no file was selected)
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Returns the content type of the file, if the client sent one
+    pub fn content_type(&self) -> Option<&ContentType> {
+        self.content_type.as_ref()
+    }
+
+    /// Returns the content of the file, `None` if it was spooled to disk (see `path()`)
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match self.content {
+            Content::Memory(ref bytes) => Some(bytes),
+            Content::Disk(_) => None,
+        }
+    }
+
+    /// Returns the path of the temp file holding the content, `None` if it is held in memory (see `bytes()`)
+    pub fn path(&self) -> Option<&Path> {
+        match self.content {
+            Content::Memory(_) => None,
+            Content::Disk(ref temp_file) => Some(&temp_file.path),
+        }
+    }
+
+    /// Returns `true` if the content was spooled to disk
+    pub fn is_spooled(&self) -> bool {
+        self.path().is_some()
+    }
+
+    /// Returns the size of the file in bytes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the file is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Consume the FormFile, returning its content (read from the temp file if it was spooled to disk)
+    pub fn into_bytes(self) -> io::Result<Vec<u8>> {
+        match self.content {
+            Content::Memory(bytes) => Ok(bytes),
+            Content::Disk(temp_file) => {
+                let mut bytes = Vec::new();
+                File::open(&temp_file.path).and_then(|mut file| file.read_to_end(&mut bytes))?;
+                Ok(bytes)
+            },
+        }
+    }
+
+    /// Save the content of the file to `path`, returning it.
+    /// A spooled file is moved (or copied then deleted if it can't be renamed), so it isn't deleted when the FormFile is dropped
+    pub fn persist<P: AsRef<Path>>(self, path: P) -> io::Result<PathBuf> {
+        let path = path.as_ref().to_path_buf();
+        match self.content {
+            Content::Memory(bytes) => File::create(&path).and_then(|mut file| file.write_all(&bytes))?,
+            Content::Disk(temp_file) => {
+                if fs::rename(&temp_file.path, &path).is_err() {
+                    fs::copy(&temp_file.path, &path)?;
+                }
+            },
+        }
+        Ok(path)
+    }
+}
+
+impl TempFile {
+    // Create a new empty temp file with a random name in `directory`, failing if the file already exists (e.g. a symlink
+    // planted by another user of a shared temp directory). On Unix, the file is readable & writable by its owner only
+    fn create(directory: &Path) -> io::Result<(TempFile, File)> {
+        let mut attempts = 0;
+        loop {
+            let path = directory.join(format!("amiwo-upload-{:016x}{:016x}", random_u64(), random_u64()));
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            owner_only(&mut options);
+
+            match options.open(&path) {
+                Ok(file) => return Ok((TempFile { path: path }, file)),
+                Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists && attempts + 1 < MAX_TEMP_FILE_ATTEMPTS => attempts += 1,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl Sink {
+    // Append `bytes`, moving the content to a temp file once it gets over `threshold`
+    fn write(&mut self, bytes: &[u8], threshold: Option<(u64, &Path)>) -> io::Result<()> {
+        let spool = match (&*self, threshold) {
+            (&Sink::Memory(ref buffer), Some((threshold, directory))) if (buffer.len() + bytes.len()) as u64 > threshold => Some(directory),
+            _ => None,
+        };
+
+        if let Some(directory) = spool {
+            let (temp_file, mut file) = TempFile::create(directory)?;
+            if let Sink::Memory(ref buffer) = *self {
+                file.write_all(buffer)?;
+            }
+            *self = Sink::Disk(temp_file, file);
+        }
+
+        match *self {
+            Sink::Memory(ref mut buffer) => { buffer.extend_from_slice(bytes); Ok(()) },
+            Sink::Disk(_, ref mut file) => file.write_all(bytes),
+        }
+    }
+
+    // Returns the content & its size
+    fn finish(self) -> io::Result<(Content, u64)> {
+        match self {
+            Sink::Memory(buffer) => {
+                let len = buffer.len() as u64;
+                Ok((Content::Memory(buffer), len))
+            },
+            Sink::Disk(temp_file, mut file) => {
+                file.flush()?;
+                let len = file.metadata()?.len();
+                Ok((Content::Disk(temp_file), len))
+            },
+        }
+    }
+}
+
+impl<R: Read> Stream<R> {
+    // Read the next chunk of the body into the buffer, returns `false` at the end of the body
+    fn fill(&mut self) -> Result<bool, GenericError> {
+        if self.eof { return Ok(false); }
+
+        let mut chunk = [0; CHUNK_SIZE];
+        match self.reader.read(&mut chunk) {
+            Ok(0) => { self.eof = true; Ok(false) },
+            Ok(size) => { self.buffer.extend_from_slice(&chunk[..size]); Ok(true) },
+            Err(err) => amiwo_error!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE::ERROR Unable to read the body", GenericError::Io(err)),
+        }
+    }
+
+    // Returns the position of `needle` in the buffer, reading the body until it is found or the buffer holds more than `max_size` bytes
+    fn find(&mut self, needle: &[u8], max_size: usize) -> Result<Option<usize>, GenericError> {
+        loop {
+            if let Some(position) = find(&self.buffer, needle, 0) {
+                return Ok(Some(position));
+            }
+            if self.buffer.len() > max_size || !self.fill()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Make sure the buffer holds at least `size` bytes (unless the body is over)
+    fn ensure(&mut self, size: usize) -> Result<(), GenericError> {
+        while self.buffer.len() < size && self.fill()? {}
+        Ok(())
+    }
+
+    // Remove the first `size` bytes of the buffer
+    fn consume(&mut self, size: usize) {
+        self.buffer.drain(..size);
+    }
+}
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Returns the boundary of a `multipart/form-data` content type
+pub fn boundary(content_type: &ContentType) -> Option<String> {
+    content_type.params()
+        .find(|&(key, _)| key == "boundary")
+        .map(|(_, value)| value.trim_matches('"').to_string())
+        .and_then(|value| if value.is_empty() { None } else { Some(value) })
+}
+
+/// Parse a `multipart/form-data` body delimited by `boundary`, reading it chunk by chunk.
+/// Returns the text fields as (name, value) pairs, in order, and the files (parts with a `filename`),
+/// files bigger than the spool threshold of `config` being written to its temp directory
+pub fn parse<R: Read>(boundary: &str, reader: R, config: &MultipartConfig) -> Result<(Vec<(String, String)>, Vec<FormFile>), GenericError> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let separator = format!("\r\n--{}", boundary).into_bytes();
+    let threshold = config.spool_threshold.map(|threshold| (threshold, config.temp_dir.as_path()));
+
+    let mut stream = Stream { reader: reader, buffer: Vec::new(), eof: false };
+    let mut fields = Vec::new();
+    let mut files = Vec::new();
+
+    // Skip the preamble
+    loop {
+        if let Some(position) = find(&stream.buffer, &delimiter, 0) {
+            stream.consume(position + delimiter.len());
+            break;
+        }
+        let keep = delimiter.len() - 1;
+        if stream.buffer.len() > keep {
+            let skipped = stream.buffer.len() - keep;
+            stream.consume(skipped);
+        }
+        if !stream.fill()? {
+            return amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE::ERROR Boundary {} not found", boundary));
+        }
+    }
+
+    loop {
+        stream.ensure(2)?;
+        if stream.buffer.starts_with(b"--") {
+            return Ok((fields, files));
+        }
+        if !stream.buffer.starts_with(b"\r\n") {
+            return amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE::ERROR Invalid boundary line {}", boundary));
+        }
+        stream.consume(2);
+
+        let headers_end = match stream.find(b"\r\n\r\n", MAX_HEADERS_SIZE)? {
+            Some(headers_end) => headers_end,
+            None => return amiwo_error!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE::ERROR Unterminated or too long part headers".to_string()),
+        };
+        let headers = String::from_utf8_lossy(&stream.buffer[..headers_end]).into_owned();
+        stream.consume(headers_end + 4);
+
+        let (name, filename, content_type) = parse_headers(&headers)?;
+        let part_threshold = if filename.is_some() { threshold } else { None };
+
+        // Stream the content until the separator, keeping in the buffer what could be the beginning of the separator
+        let mut sink = Sink::Memory(Vec::new());
+        loop {
+            if let Some(position) = find(&stream.buffer, &separator, 0) {
+                sink.write(&stream.buffer[..position], part_threshold)
+                    .or_else(|err| amiwo_error!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE::ERROR Unable to spool the file", GenericError::Io(err)))?;
+                stream.consume(position + separator.len());
+                break;
+            }
+
+            let safe = stream.buffer.len().saturating_sub(separator.len() - 1);
+            sink.write(&stream.buffer[..safe], part_threshold)
+                .or_else(|err| amiwo_error!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE::ERROR Unable to spool the file", GenericError::Io(err)))?;
+            stream.consume(safe);
+
+            if !stream.fill()? {
+                return amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE::ERROR Missing closing boundary {}", boundary));
+            }
+        }
+
+        let (content, len) = sink.finish()
+            .or_else(|err| amiwo_error!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE::ERROR Unable to spool the file", GenericError::Io(err)))?;
+
+        match (filename, content) {
+            (Some(filename), content) => files.push(FormFile {
+                name: name,
+                filename: filename,
+                content_type: content_type,
+                len: len,
+                content: content,
+            }),
+            (None, Content::Memory(bytes)) => match String::from_utf8(bytes) {
+                Ok(value) => fields.push((name, value)),
+                Err(_) => return amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE::ERROR Field {} is not valid UTF-8", name)),
+            },
+            (None, Content::Disk(_)) => unreachable!(), // text fields are never spooled
+        }
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Random value, hashing a counter & the time with the random keys of a new `RandomState` (seeded by the OS)
+fn random_u64() -> u64 {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_usize(TEMP_FILE_COUNTER.fetch_add(1, Ordering::SeqCst));
+    hasher.write_u64(elapsed.as_secs());
+    hasher.write_u32(elapsed.subsec_nanos());
+    hasher.finish()
+}
+
+// Restrict the permissions of the files created with `options` to their owner (`0600`)
+#[cfg(unix)]
+fn owner_only(options: &mut OpenOptions) {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600);
+}
+
+#[cfg(not(unix))]
+fn owner_only(_options: &mut OpenOptions) {
+}
+
+// Returns the position of the first occurrence of `needle` in `haystack`, starting at `from`
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from > haystack.len() { return None; }
+    haystack[from..].windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| from + position)
+}
+
+// Extract the field name, the filename & the content type from the headers of a part
+fn parse_headers(headers: &str) -> Result<(String, Option<String>, Option<ContentType>), GenericError> {
+    let mut name = None;
+    let mut filename = None;
+    let mut content_type = None;
+
+    for line in headers.split("\r\n") {
+        let mut parts = line.splitn(2, ':');
+        let (key, value) = match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => (key.trim().to_lowercase(), value.trim()),
+            _ => continue,
+        };
+
+        if key == "content-disposition" {
+            for param in value.split(';').map(|param| param.trim()) {
+                let mut param = param.splitn(2, '=');
+                match (param.next(), param.next()) {
+                    (Some("name"), Some(value)) => name = Some(value.trim_matches('"').to_string()),
+                    (Some("filename"), Some(value)) => filename = Some(value.trim_matches('"').to_string()),
+                    _ => (),
+                }
+            }
+        } else if key == "content-type" {
+            content_type = value.parse::<ContentType>().ok();
+        }
+    }
+
+    match name {
+        Some(name) => Ok((name, filename, content_type)),
+        None => amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::MULTIPART::PARSE_HEADERS::ERROR Part without a name [headers = {:?}]", headers)),
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Default for MultipartConfig {
+    fn default() -> MultipartConfig {
+        MultipartConfig::new()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!("::AMIWO::CONTRIB::ROCKET::MULTIPART::TEMP_FILE::DROP::WARNING Unable to delete temp file {:?}: {}", self.path, err);
+            }
+        }
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::env;
+    use std::fs;
+    use std::fs::File;
+    use std::io::Read;
+    use std::path::Path;
+
+    use rocket::http::ContentType;
+
+    use super::{ boundary, parse, MultipartConfig };
+
+    const BODY: &'static str = "preamble\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"a\"\r\n\r\n\
+        b1\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"a\"\r\n\r\n\
+        b2\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"avatar\"; filename=\"me.png\"\r\n\
+        Content-Type: image/png\r\n\r\n\
+        \x01\x02\r\n\x03\r\n\
+        --XyZ--\r\n";
+
+    // Reader returning the body one byte at a time, to test chunk boundaries
+    struct SlowReader<'a>(&'a [u8]);
+
+    impl<'a> Read for SlowReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() { return Ok(0); }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+
+    fn read_file(path: &Path) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        File::open(path).unwrap().read_to_end(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn multipart_test_boundary() {
+        let content_type = ContentType::with_params("multipart", "form-data", Some("boundary=\"XyZ\""));
+        assert_eq!(boundary(&content_type), Some("XyZ".to_string()));
+        assert_eq!(boundary(&ContentType::DataForm), None);
+    }
+
+    #[test]
+    fn multipart_test_parse() {
+        for &slow in &[false, true] {
+            let (fields, files) = if slow {
+                parse("XyZ", SlowReader(BODY.as_bytes()), &MultipartConfig::new()).unwrap()
+            } else {
+                parse("XyZ", BODY.as_bytes(), &MultipartConfig::new()).unwrap()
+            };
+            assert_eq!(fields, vec![("a".to_string(), "b1".to_string()), ("a".to_string(), "b2".to_string())]);
+
+            assert_eq!(files.len(), 1);
+            assert_eq!(files[0].name(), "avatar");
+            assert_eq!(files[0].filename(), "me.png");
+            assert_eq!(files[0].content_type(), Some(&ContentType::PNG));
+            assert_eq!(files[0].bytes(), Some(&b"\x01\x02\r\n\x03"[..]));
+            assert_eq!(files[0].len(), 5);
+            assert_eq!(files[0].is_spooled(), false);
+        }
+    }
+
+    #[test]
+    fn multipart_test_parse_errors() {
+        let config = MultipartConfig::new();
+        assert!(parse("other", BODY.as_bytes(), &config).is_err());
+        assert!(parse("XyZ", &b"--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nb"[..], &config).is_err());
+        assert!(parse("XyZ", &b"--XyZ\r\nContent-Disposition: form-data\r\n\r\nb\r\n--XyZ--"[..], &config).is_err());
+    }
+
+    #[test]
+    fn multipart_test_spooling() {
+        let directory = env::temp_dir().join("amiwo-multipart-test-spooling");
+        fs::create_dir_all(&directory).unwrap();
+        let config = MultipartConfig::new().spool_threshold(4).temp_dir(directory.clone());
+
+        let (fields, mut files) = parse("XyZ", SlowReader(BODY.as_bytes()), &config).unwrap();
+        assert_eq!(fields, vec![("a".to_string(), "b1".to_string()), ("a".to_string(), "b2".to_string())]);
+
+        let file = files.remove(0);
+        assert_eq!(file.is_spooled(), true);
+        assert_eq!(file.bytes(), None);
+        assert_eq!(file.len(), 5);
+
+        let path = file.path().unwrap().to_path_buf();
+        assert!(path.starts_with(&directory));
+        assert_eq!(read_file(&path), b"\x01\x02\r\n\x03");
+
+        // temp file deleted when the FormFile is dropped
+        drop(file);
+        assert!(!path.exists());
+
+        // unless it is persisted
+        let (_, mut files) = parse("XyZ", BODY.as_bytes(), &config).unwrap();
+        let persisted = files.remove(0).persist(directory.join("me.png")).unwrap();
+        assert_eq!(read_file(&persisted), b"\x01\x02\r\n\x03");
+
+        let (_, mut files) = parse("XyZ", BODY.as_bytes(), &MultipartConfig::new().spool_threshold(5).temp_dir(directory.clone())).unwrap();
+        assert_eq!(files[0].is_spooled(), false);
+        assert_eq!(files.remove(0).into_bytes().unwrap(), b"\x01\x02\r\n\x03");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn multipart_test_temp_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = env::temp_dir();
+        let (first, _) = TempFile::create(&directory).unwrap();
+        let (second, _) = TempFile::create(&directory).unwrap();
+        assert!(first.path != second.path);
+        assert_eq!(fs::metadata(&first.path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,144 @@
+//! File holding the NdJsonResponse type streaming a collection as newline-delimited JSON
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::cmp;
+use std::io;
+use std::io::Read;
+
+use rocket::Response;
+use rocket::http::{ ContentType, Status };
+use rocket::response::{ Responder, Stream };
+use serde::Serialize;
+use serde_json;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// A Rocket responder streaming the items of an iterator as [newline-delimited JSON](http://ndjson.org/),
+/// one record per line, with Content-Type `application/x-ndjson`
+///
+/// ```rust,ignore
+/// #[get("/export")]
+/// fn export() -> NdJsonResponse<impl Iterator<Item = Value>> { 
+///     NdJsonResponse::new(database.rows().map(|row| row.to_json()))
+/// }
+/// ```
+pub struct NdJsonResponse<I> {
+    items: I,
+    buffer: Vec<u8>,
+    position: usize,
+    done: bool,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl<I> NdJsonResponse<I> 
+    where
+        I: Iterator,
+        I::Item: Serialize
+{
+    /// Create a NdJsonResponse from anything that can be turned into an iterator of `Serialize` items
+    pub fn new<C: IntoIterator<Item = I::Item, IntoIter = I>>(items: C) -> NdJsonResponse<I> {
+        NdJsonResponse {
+            items: items.into_iter(),
+            buffer: Vec::new(),
+            position: 0,
+            done: false,
+        }
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl<I> Read for NdJsonResponse<I> 
+    where
+        I: Iterator,
+        I::Item: Serialize
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position >= self.buffer.len() {
+            if self.done {
+                return Ok(0);
+            }
+
+            self.buffer.clear();
+            self.position = 0;
+            match self.items.next() {
+                Some(item) => {
+                    serde_json::to_writer(&mut self.buffer, &item)
+                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                    self.buffer.push(b'\n');
+                },
+                None => self.done = true,
+            }
+        }
+
+        let size = cmp::min(buf.len(), self.buffer.len() - self.position);
+        buf[..size].copy_from_slice(&self.buffer[self.position..self.position + size]);
+        self.position += size;
+        Ok(size)
+    }
+}
+
+/// Responds with a chunked body & Content-Type `application/x-ndjson`
+impl<'r, I> Responder<'r> for NdJsonResponse<I> 
+    where
+        I: Iterator + 'r,
+        I::Item: Serialize
+{
+    fn respond(self) -> Result<Response<'r>, Status> {
+        Stream::from(self).respond()
+            .map(|mut response| {
+                response.set_header(ContentType::new("application", "x-ndjson"));
+                response
+            })
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::io::Read;
+
+    use super::NdJsonResponse;
+
+    use rocket::http::Status;
+    use rocket::response::Responder;
+
+    #[test]
+    fn NdJsonResponse_test_read() {
+        let mut body = String::new();
+        NdJsonResponse::new((1..4).map(|i| json!({ "id": i, "label": format!("item {}", i) })))
+            .read_to_string(&mut body)
+            .unwrap();
+        assert_eq!(body, "{\"id\":1,\"label\":\"item 1\"}\n{\"id\":2,\"label\":\"item 2\"}\n{\"id\":3,\"label\":\"item 3\"}\n");
+
+        let mut body = String::new();
+        NdJsonResponse::new(Vec::<u32>::new()).read_to_string(&mut body).unwrap();
+        assert_eq!(body, "");
+    }
+
+    #[test]
+    fn NdJsonResponse_test_responder() {
+        let mut response = NdJsonResponse::new(vec!["a", "b"]).respond().unwrap();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.header_values("Content-Type").next(), Some("application/x-ndjson"));
+
+        let body_str = response.body().and_then(|b| b.into_string()).unwrap();
+        assert_eq!(body_str, "\"a\"\n\"b\"\n");
+    }
+}
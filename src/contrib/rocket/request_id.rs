@@ -0,0 +1,129 @@
+//! File holding the RequestId request guard used to propagate a request/correlation id into ResponseJSON
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::fmt;
+use std::sync::atomic::{ AtomicUsize, Ordering, ATOMIC_USIZE_INIT };
+use std::time::{ Duration, SystemTime, UNIX_EPOCH };
+
+use rocket;
+use rocket::Request;
+use rocket::request::FromRequest;
+
+use types::ResponseJSON;
+
+// =======================================================================
+// STATIC VARIABLES
+// =======================================================================
+static REQUEST_COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// A request guard holding the id of the current request.
+/// The id is read from the `X-Request-Id` header, or generated if the header is missing.
+///
+/// Rocket 0.2 doesn't support fairings nor request local state, so the id has to be stamped 
+/// on the returned ResponseJSON explicitly
+///
+/// ```rust,ignore
+/// #[get("/")]
+/// fn index(request_id: RequestId) -> ResponseJSON { 
+///     request_id.stamp(ResponseJSON::ok())
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestId(String);
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl RequestId {
+    /// Name of the header holding the request id
+    pub const HEADER: &'static str = "X-Request-Id";
+
+    /// Generate a new (process wide) unique request id
+    pub fn generate() -> RequestId {
+        let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+        let counter = REQUEST_COUNTER.fetch_add(1, Ordering::SeqCst);
+        RequestId(format!("{:x}{:08x}-{:x}", elapsed.as_secs(), elapsed.subsec_nanos(), counter))
+    }
+
+    /// Returns the request id as a &str
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Set the `request_id` of `rjson` to this id
+    pub fn stamp<T>(&self, rjson: ResponseJSON<T>) -> ResponseJSON<T> {
+        rjson.request_id(self.0.clone())
+    }
+}
+
+// =======================================================================
+// EXTERNAL TRAITS IMPLEMENTATION
+// =======================================================================
+/// Reads the request id from the `X-Request-Id` header or generates a new one. Never fails nor forwards
+impl<'a, 'r> FromRequest<'a, 'r> for RequestId {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> rocket::request::Outcome<Self, Self::Error> {
+        let id = request.headers().get_one(RequestId::HEADER)
+            .map(|id| id.trim())
+            .and_then(|id| if id.is_empty() { None } else { Some(RequestId(id.to_string())) })
+            .unwrap_or_else(RequestId::generate);
+        rocket::Outcome::Success(id)
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::RequestId;
+
+    use rocket::Request;
+    use rocket::http::{ Header, Method };
+    use rocket::request::FromRequest;
+
+    use types::ResponseJSON;
+
+    #[test]
+    fn RequestId_test_from_header() {
+        let mut request = Request::new(Method::Get, "/");
+        request.add_header(Header::new("X-Request-Id", "abc-123"));
+
+        let id = RequestId::from_request(&request).unwrap();
+        assert_eq!(id.as_str(), "abc-123");
+
+        let json = id.stamp(ResponseJSON::ok());
+        assert_eq!(json.request_id, Some("abc-123".to_string()));
+        assert_eq!(json, json!({ "success": true, "http_code": 200, "request_id": "abc-123" }));
+    }
+
+    #[test]
+    fn RequestId_test_generated() {
+        let request = Request::new(Method::Get, "/");
+
+        let id1 = RequestId::from_request(&request).unwrap();
+        let id2 = RequestId::from_request(&request).unwrap();
+        assert!(!id1.as_str().is_empty());
+        assert!(id1 != id2);
+    }
+}
@@ -0,0 +1,314 @@
+//! File holding the simple XML parser used by FormHashMap to read `application/xml` & `text/xml` bodies
+//!
+//! The children of the root element become the keys of the map:
+//! - an element holding only text => string value (trimmed, entities & CDATA sections decoded)
+//! - an element holding elements => object, the text mixed with the elements (if any) being kept under `$text`
+//! - repeated elements => array
+//!
+//! Attributes, comments, processing instructions & the DOCTYPE are ignored, namespace prefixes are kept in the names.
+//! A document nesting more than `MAX_DEPTH` elements is rejected
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.1
+//!
+//! ## Release notes
+//! - v1.1 : the nesting depth is limited to `MAX_DEPTH`, the text mixed with child elements is kept under `$text`
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::char;
+
+use serde_json::Value;
+use serde_json::map::Map;
+
+use error::GenericError;
+use traits::Pushable;
+
+// =======================================================================
+// CONSTANTS
+// =======================================================================
+/// Maximum nesting depth of the elements of a document
+pub const MAX_DEPTH: usize = 128;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Parse the XML `document` into a map of the children of its root element
+/// (a root element holding only text is returned as `{ <root name>: <text> }`)
+pub fn parse(document: &str) -> Result<Map<String, Value>, GenericError> {
+    let mut parser = Parser { input: document, position: 0 };
+
+    parser.skip_misc()?;
+    let (name, value) = parser.parse_element(1)?;
+    parser.skip_misc()?;
+    if parser.position < parser.input.len() {
+        return parser.error("unexpected content after the root element");
+    }
+
+    match value {
+        Value::Object(map) => Ok(map),
+        value => {
+            let mut map = Map::new();
+            map.insert(name, value);
+            Ok(map)
+        },
+    }
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn error<T>(&self, reason: &str) -> Result<T, GenericError> {
+        amiwo_error!(format!("::AMIWO::CONTRIB::ROCKET::XML::PARSE::ERROR Invalid XML data at byte {}: {}", self.position, reason))
+    }
+
+    // Move after the next occurrence of `end`
+    fn skip_past(&mut self, end: &str) -> Result<(), GenericError> {
+        match self.rest().find(end) {
+            Some(index) => {
+                self.position += index + end.len();
+                Ok(())
+            },
+            None => self.error(&format!("missing `{}`", end)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_left().len();
+        self.position = self.input.len() - trimmed;
+    }
+
+    // Skip whitespace, comments, processing instructions (e.g. `<?xml ... ?>`) & DOCTYPE
+    fn skip_misc(&mut self) -> Result<(), GenericError> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!DOCTYPE") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<String, GenericError> {
+        let length = self.rest()
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=')
+            .unwrap_or(self.rest().len());
+        if length == 0 {
+            return self.error("missing element name");
+        }
+        let name = self.rest()[..length].to_string();
+        self.position += length;
+        Ok(name)
+    }
+
+    // Skip the attributes of the start tag, returns `true` if the element is empty (`<name/>`)
+    fn skip_attributes(&mut self) -> Result<bool, GenericError> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.position += 2;
+                return Ok(true);
+            } else if rest.starts_with('>') {
+                self.position += 1;
+                return Ok(false);
+            } else if rest.starts_with('"') || rest.starts_with('\'') {
+                let quote = &rest[..1];
+                self.position += 1;
+                self.skip_past(quote)?;
+            } else if let Some(c) = rest.chars().next() {
+                self.position += c.len_utf8();
+            } else {
+                return self.error("unterminated start tag");
+            }
+        }
+    }
+
+    // Parse the element starting at the current position, nested at `depth`, returns its name & value
+    fn parse_element(&mut self, depth: usize) -> Result<(String, Value), GenericError> {
+        if !self.rest().starts_with('<') {
+            return self.error("expected an element");
+        }
+        if depth > MAX_DEPTH {
+            return self.error(&format!("elements nested deeper than {}", MAX_DEPTH));
+        }
+        self.position += 1;
+        let name = self.parse_name()?;
+        if self.skip_attributes()? {
+            return Ok((name, Value::String(String::new())));
+        }
+
+        let mut text = String::new();
+        let mut children = Map::new();
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.position += 2;
+                let end_name = self.parse_name()?;
+                if end_name != name {
+                    return self.error(&format!("expected `</{}>`, found `</{}>`", name, end_name));
+                }
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return self.error("unterminated end tag");
+                }
+                self.position += 1;
+                break;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.position += "<![CDATA[".len();
+                let length = match self.rest().find("]]>") {
+                    Some(length) => length,
+                    None => return self.error("unterminated CDATA section"),
+                };
+                text.push_str(&self.rest()[..length]);
+                self.position += length + "]]>".len();
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                let (child_name, child_value) = self.parse_element(depth + 1)?;
+                children.entry(child_name).or_insert(Value::Null).push(child_value);
+            } else if rest.is_empty() {
+                return self.error(&format!("missing `</{}>`", name));
+            } else {
+                let length = rest.find('<').unwrap_or(rest.len());
+                text.push_str(&decode_entities(&rest[..length]).or_else(|reason| self.error(&reason))?);
+                self.position += length;
+            }
+        }
+
+        let text = text.trim();
+        if children.is_empty() {
+            Ok((name, Value::String(text.to_string())))
+        } else {
+            if !text.is_empty() {
+                children.insert("$text".to_string(), Value::String(text.to_string()));
+            }
+            Ok((name, Value::Object(children)))
+        }
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Decode the predefined & numeric character references of `text`
+fn decode_entities(text: &str) -> Result<String, String> {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let end = match rest[start..].find(';') {
+            Some(end) => start + end,
+            None => return Err("unterminated entity".to_string()),
+        };
+
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32),
+            _ if entity.starts_with('#') => entity[1..].parse::<u32>().ok().and_then(char::from_u32),
+            _ => None,
+        };
+        match c {
+            Some(c) => decoded.push(c),
+            None => return Err(format!("unknown entity `&{};`", entity)),
+        }
+        rest = &rest[end + 1..];
+    }
+    decoded.push_str(rest);
+    Ok(decoded)
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ parse, MAX_DEPTH };
+
+    #[test]
+    fn xml_test_parse() {
+        let map = parse(r#"<?xml version="1.0" encoding="UTF-8"?>
+            <!-- payment callback -->
+            <callback id="17">
+                <status>paid</status>
+                <amount currency="XOF"> 1500 </amount>
+                <item><sku>A1</sku></item>
+                <item><sku>B2</sku></item>
+                <note><![CDATA[<b>thanks</b>]]> &amp; bye &#233;&#x21;</note>
+                <empty/>
+            </callback>"#).unwrap();
+
+        assert_eq!(map.get("status"), Some(&json!("paid")));
+        assert_eq!(map.get("amount"), Some(&json!("1500")));
+        assert_eq!(map.get("item"), Some(&json!([{ "sku": "A1" }, { "sku": "B2" }])));
+        assert_eq!(map.get("note"), Some(&json!("<b>thanks</b> & bye é!")));
+        assert_eq!(map.get("empty"), Some(&json!("")));
+    }
+
+    #[test]
+    fn xml_test_parse_soap() {
+        let map = parse(r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope">
+            <soap:Body><Notify><Ref>abc</Ref></Notify></soap:Body>
+        </soap:Envelope>"#).unwrap();
+        assert_eq!(map.get("soap:Body"), Some(&json!({ "Notify": { "Ref": "abc" } })));
+
+        let map = parse("<status>ok</status>").unwrap();
+        assert_eq!(map.get("status"), Some(&json!("ok")));
+    }
+
+    #[test]
+    fn xml_test_parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("not xml").is_err());
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<a>").is_err());
+        assert!(parse("<a>&unknown;</a>").is_err());
+        assert!(parse("<a></a><b></b>").is_err());
+    }
+
+    #[test]
+    fn xml_test_parse_depth() {
+        let nested = |depth: usize| format!("{}x{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        match parse(&nested(100_000)) {
+            Err(err) => assert!(err.to_string().contains("::AMIWO::CONTRIB::ROCKET::XML::PARSE::ERROR")),
+            Ok(_) => panic!("nesting deeper than MAX_DEPTH accepted"),
+        }
+    }
+
+    #[test]
+    fn xml_test_parse_mixed_text() {
+        let map = parse("<callback>paid <ref>17</ref> twice</callback>").unwrap();
+        assert_eq!(map.get("ref"), Some(&json!("17")));
+        assert_eq!(map.get("$text"), Some(&json!("paid  twice")));
+    }
+}
@@ -0,0 +1,312 @@
+//! JSON Schema validation
+//!
+//! A `Validator` checks a JSON document against a [JSON Schema](http://json-schema.org/) and returns the list of
+//! `SchemaViolation` found. It can be used on inbound data (`FormHashMap::validate()`) as well as on outbound data
+//! (`Validator::validate_response()` checks the `data` of a `ResponseJSON`).
+//!
+//! Supported keywords: `type`, `enum`, `const`, `required`, `properties`, `additionalProperties`, `items`,
+//! `minItems`, `maxItems`, `minLength`, `maxLength`, `minimum`, `maximum`. Other keywords are ignored.
+//!
+//! ```rust,ignore
+//! let validator = Validator::new(json!({ "type": "object", "required": ["name"] }));
+//! match validator.validate_response(&response) {
+//!     Ok(()) => response,
+//!     Err(violations) => ResponseJSON::from(violations),
+//! }
+//! ```
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::fmt;
+
+use serde::Serialize;
+use serde_json;
+use serde_json::Value;
+
+use error::GenericError;
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// A compiled JSON Schema document
+#[derive(Clone, Debug, PartialEq)]
+pub struct Validator {
+    schema: Value,
+}
+
+/// A single schema violation, `path` being the JSON Pointer of the offending value (`""` for the root)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub message: String,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl Validator {
+    /// Create a Validator from a JSON Schema document
+    pub fn new(schema: Value) -> Validator {
+        Validator { schema: schema }
+    }
+
+    /// Create a Validator from a JSON Schema document held in a string of JSON text
+    pub fn from_str<'s>(s: &'s str) -> Result<Validator, GenericError> {
+        serde_json::from_str(s)
+            .map(|schema| Validator::new(schema))
+            .map_err(|serde_err| GenericError::Serde(serde_err))
+    }
+
+    /// Returns the schema document
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    /// Validate `value` against the schema
+    pub fn validate(&self, value: &Value) -> Result<(), Vec<SchemaViolation>> {
+        let mut violations = Vec::new();
+        validate_value(&self.schema, value, "", &mut violations);
+
+        if violations.is_empty() { Ok(()) } else { Err(violations) }
+    }
+
+    /// Validate the `data` of `response` against the schema
+    pub fn validate_response<T: Serialize>(&self, response: &ResponseJSON<T>) -> Result<(), Vec<SchemaViolation>> {
+        match serde_json::to_value(&response.data) {
+            Ok(data) => self.validate(&data),
+            Err(serde_err) => Err(vec![SchemaViolation::new("", format!("data can't be serialized: {}", serde_err))]),
+        }
+    }
+}
+
+impl SchemaViolation {
+    fn new<P: ToString, M: ToString>(path: P, message: M) -> SchemaViolation {
+        SchemaViolation {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Validate `value` (located at `path`) against `schema`, pushing any violation found into `violations`
+fn validate_value(schema: &Value, value: &Value, path: &str, violations: &mut Vec<SchemaViolation>) {
+    // `true` / `false` schemas accept respectively reject everything
+    let schema = match *schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => return violations.push(SchemaViolation::new(path, "no value allowed here")),
+        Value::Object(ref map) => map,
+        _ => return,
+    };
+
+    match schema.get("type") {
+        Some(&Value::String(ref expected)) if !has_type(value, expected) => {
+            return violations.push(SchemaViolation::new(path, format!("expected type `{}`", expected)));
+        },
+        Some(&Value::Array(ref expected)) if !expected.iter().any(|t| t.as_str().map_or(false, |t| has_type(value, t))) => {
+            let types : Vec<String> = expected.iter().map(|t| t.to_string()).collect();
+            return violations.push(SchemaViolation::new(path, format!("expected one of types [{}]", types.join(", "))));
+        },
+        _ => (),
+    }
+
+    if let Some(&Value::Array(ref allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            violations.push(SchemaViolation::new(path, "value is not one of the allowed values"));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            violations.push(SchemaViolation::new(path, format!("expected constant value {}", expected)));
+        }
+    }
+
+    match *value {
+        Value::Object(ref map) => {
+            if let Some(&Value::Array(ref required)) = schema.get("required") {
+                for key in required.iter().filter_map(|key| key.as_str()) {
+                    if !map.contains_key(key) {
+                        violations.push(SchemaViolation::new(path, format!("missing required property `{}`", key)));
+                    }
+                }
+            }
+
+            let properties = schema.get("properties").and_then(|properties| properties.as_object());
+            for (key, item) in map.iter() {
+                let item_path = format!("{}/{}", path, escape_pointer(key));
+                match (properties.and_then(|properties| properties.get(key)), schema.get("additionalProperties")) {
+                    (Some(item_schema), _) => validate_value(item_schema, item, &item_path, violations),
+                    (None, Some(&Value::Bool(false))) => violations.push(SchemaViolation::new(item_path, "additional property not allowed")),
+                    (None, Some(item_schema)) => validate_value(item_schema, item, &item_path, violations),
+                    (None, None) => (),
+                }
+            }
+        },
+        Value::Array(ref vect) => {
+            if let Some(min) = schema.get("minItems").and_then(|min| min.as_u64()) {
+                if (vect.len() as u64) < min {
+                    violations.push(SchemaViolation::new(path, format!("expected at least {} items", min)));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(|max| max.as_u64()) {
+                if (vect.len() as u64) > max {
+                    violations.push(SchemaViolation::new(path, format!("expected at most {} items", max)));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (idx, item) in vect.iter().enumerate() {
+                    validate_value(item_schema, item, &format!("{}/{}", path, idx), violations);
+                }
+            }
+        },
+        Value::String(ref string) => {
+            let len = string.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(|min| min.as_u64()) {
+                if len < min {
+                    violations.push(SchemaViolation::new(path, format!("expected at least {} characters", min)));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(|max| max.as_u64()) {
+                if len > max {
+                    violations.push(SchemaViolation::new(path, format!("expected at most {} characters", max)));
+                }
+            }
+        },
+        Value::Number(ref number) => {
+            let number = number.as_f64().unwrap_or(0.0);
+            if let Some(min) = schema.get("minimum").and_then(|min| min.as_f64()) {
+                if number < min {
+                    violations.push(SchemaViolation::new(path, format!("expected a value >= {}", min)));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(|max| max.as_f64()) {
+                if number > max {
+                    violations.push(SchemaViolation::new(path, format!("expected a value <= {}", max)));
+                }
+            }
+        },
+        _ => (),
+    }
+}
+
+// Test if `value` is of the JSON Schema type `expected`
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().map_or(false, |number| number.fract() == 0.0),
+        _ => true,
+    }
+}
+
+// Escape a key to be used as a JSON Pointer reference token
+fn escape_pointer(key: &str) -> String {
+    key.replace("~", "~0").replace("/", "~1")
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Build an error ResponseJSON (422 Unprocessable Entity) listing the violations in `data`
+impl From<Vec<SchemaViolation>> for ResponseJSON {
+    fn from(violations: Vec<SchemaViolation>) -> ResponseJSON {
+        ResponseJSON::error()
+            .http_code(422)
+            .message("Schema validation failed".to_string())
+            .data(serde_json::to_value(violations).unwrap_or(Value::Null))
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use types::ResponseJSON;
+
+    use super::{ SchemaViolation, Validator };
+
+    fn user_validator() -> Validator {
+        Validator::new(json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "additionalProperties": false,
+            "properties": {
+                "id": { "type": "integer", "minimum": 1 },
+                "name": { "type": "string", "minLength": 2, "maxLength": 16 },
+                "role": { "enum": ["admin", "user"] },
+                "tags": { "type": "array", "maxItems": 2, "items": { "type": "string" } }
+            }
+        }))
+    }
+
+    #[test]
+    fn Validator_test_valid() {
+        let validator = user_validator();
+        assert_eq!(validator.validate(&json!({ "id": 17, "name": "Boris" })), Ok(()));
+        assert_eq!(validator.validate(&json!({ "id": 17, "name": "Boris", "role": "admin", "tags": ["a", "b"] })), Ok(()));
+        assert!(Validator::new(json!({})).validate(&json!([1, "a", null])).is_ok());
+    }
+
+    #[test]
+    fn Validator_test_violations() {
+        let validator = user_validator();
+
+        assert_eq!(validator.validate(&json!("Boris")), Err(vec![SchemaViolation { path: "".to_string(), message: "expected type `object`".to_string() }]));
+
+        let violations = validator.validate(&json!({
+            "id": 0,
+            "role": "guest",
+            "tags": ["a", 2, "c"],
+            "age": 30
+        })).unwrap_err();
+        let violations : Vec<String> = violations.iter().map(|violation| violation.to_string()).collect();
+        assert_eq!(violations.len(), 6);
+        assert!(violations.contains(&"missing required property `name`".to_string()));
+        assert!(violations.contains(&"/id: expected a value >= 1".to_string()));
+        assert!(violations.contains(&"/role: value is not one of the allowed values".to_string()));
+        assert!(violations.contains(&"/tags: expected at most 2 items".to_string()));
+        assert!(violations.contains(&"/tags/1: expected type `string`".to_string()));
+        assert!(violations.contains(&"/age: additional property not allowed".to_string()));
+    }
+
+    #[test]
+    fn Validator_test_from_str() {
+        let validator = Validator::from_str(r#"{ "type": ["string", "null"] }"#).unwrap();
+        assert!(validator.validate(&json!(null)).is_ok());
+        assert!(validator.validate(&json!(12)).is_err());
+        assert!(Validator::from_str("{ invalid").is_err());
+    }
+
+    #[test]
+    fn Validator_test_validate_response() {
+        let validator = user_validator();
+        assert!(validator.validate_response(&ResponseJSON::ok().data(json!({ "id": 1, "name": "Boris" }))).is_ok());
+
+        let violations = validator.validate_response(&ResponseJSON::ok().data(json!({ "id": 1 }))).unwrap_err();
+        let response = ResponseJSON::from(violations);
+        assert_eq!(response.http_code, 422);
+        assert_eq!(response.success, false);
+        assert_eq!(response.data, json!([{ "path": "", "message": "missing required property `name`" }]));
+    }
+}
@@ -1,9 +1,20 @@
 //! File holding the GenericError type
 //!
 //! Author: [Boris](mailto:boris@humanenginuity.com)
-//! Version: 1.1
+//! Version: 1.12
 //!
 //! ## Release notes
+//! - v1.12 : implemented `From<reqwest::Error>` (feature `rest-reqwest`), mapping the errors to `Timeout` or `Io`
+//! - v1.11 : `http_code()` maps the `Serde` errors raised while serializing (or by an I/O error) to 500 instead of 400
+//! - v1.10 : added the `CircuitOpen` variant
+//! - v1.9 : added the `Timeout` variant, `From<HyperError>` mapping the timeouts to it
+//! - v1.8 : `From` implementations & `new_compound()` call the error hook (see `error::set_error_hook()`)
+//! - v1.7 : added the `Upstream` variant, `is_transient()` & `is_retryable()`
+//! - v1.6 : added `iter_chain()` & `root_cause()`
+//! - v1.5 : added `kind()` & `Serialize` implementation (kind, message & chain of causes)
+//! - v1.4 : added `UrlParse`, `Utf8` & `FromUtf8` variants
+//! - v1.3 : implemented Rocket's `Responder` so routes can return `Result<ResponseJSON, GenericError>`
+//! - v1.2 : added `http_code()` mapping each error to a sensible HTTP status code
 //! - v1.1 : added From implementation (as per book guideline to use with the `try!` macro)
 //! - v1.0 : creation
 
@@ -14,24 +25,69 @@ use std::boxed::Box;
 use std::error::Error;
 use std::fmt;
 use std::io::Error as IOError;
+use std::io::ErrorKind as IOErrorKind;
+use std::str::Utf8Error;
+use std::string::FromUtf8Error;
 
 use hyper::error::Error as HyperError;
+use hyper::error::ParseError as UrlParseError;
+#[cfg(feature = "rest-reqwest")]
+use reqwest::Error as ReqwestError;
 use rocket::Error as RocketError;
+use rocket::http::Status;
+use rocket::response::{ Responder, Response };
+use serde::ser::{ Serialize, Serializer, SerializeStruct };
 use serde_json::Error as SerdeError;
+use serde_json::error::Category as SerdeCategory;
+
+use error::hook;
+use types::ResponseJSON;
 
 // =======================================================================
 // STRUCT DEFINITION
 // =======================================================================
+/// Stable identifier of the kind of a `GenericError`, e.g. to be matched by the clients of an API
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Hyper,
+    Io,
+    Rocket,
+    Serde,
+    UrlParse,
+    Utf8,
+    FromUtf8,
+    Upstream,
+    Timeout,
+    CircuitOpen,
+    Compound,
+    Basic,
+}
+
 #[derive(Debug)]
 pub enum GenericError {
     Hyper(HyperError),
     Io(IOError),
     Rocket(RocketError),
     Serde(SerdeError),
+    UrlParse(UrlParseError),
+    Utf8(Utf8Error),
+    FromUtf8(FromUtf8Error),
+    /// An upstream service answered with an error HTTP status (status code, description)
+    Upstream((u16, String)),
+    /// An outbound request timed out
+    Timeout(IOError),
+    /// An outbound request was refused by an open circuit breaker (see `contrib::hyper::CircuitBreaker`)
+    CircuitOpen(String),
     Compound((String, Box<GenericError>)),
     Basic(String),
 }
 
+/// Iterator over an error & its chain of causes, see `GenericError::iter_chain()`
+pub struct ErrorChain<'e> {
+    next: Option<&'e Error>,
+}
+
 // =======================================================================
 // STRUCT IMPLEMENTATION
 // =======================================================================
@@ -40,7 +96,100 @@ impl GenericError {
         let mut description = desc.to_string();
         description.push_str(" caused by ");
         description.push_str(err.description());
-        GenericError::Compound((description, Box::new(err)))
+        hook::notify(GenericError::Compound((description, Box::new(err))))
+    }
+
+    /// Iterate over this error followed by its chain of causes (closest first), the causes of the `Compound` errors included
+    ///
+    /// ```rust,ignore
+    /// for err in err.iter_chain() {
+    ///     error!("{}", err);
+    /// }
+    /// ```
+    pub fn iter_chain<'e>(&'e self) -> ErrorChain<'e> {
+        ErrorChain { next: Some(self) }
+    }
+
+    /// Last error of the chain of causes (`self` if it has no cause)
+    pub fn root_cause<'e>(&'e self) -> &'e Error {
+        self.iter_chain().last().unwrap()
+    }
+
+    /// Kind of this error (the variant of `GenericError`)
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            GenericError::Hyper(_) => ErrorKind::Hyper,
+            GenericError::Io(_) => ErrorKind::Io,
+            GenericError::Rocket(_) => ErrorKind::Rocket,
+            GenericError::Serde(_) => ErrorKind::Serde,
+            GenericError::UrlParse(_) => ErrorKind::UrlParse,
+            GenericError::Utf8(_) => ErrorKind::Utf8,
+            GenericError::FromUtf8(_) => ErrorKind::FromUtf8,
+            GenericError::Upstream(_) => ErrorKind::Upstream,
+            GenericError::Timeout(_) => ErrorKind::Timeout,
+            GenericError::CircuitOpen(_) => ErrorKind::CircuitOpen,
+            GenericError::Compound(_) => ErrorKind::Compound,
+            GenericError::Basic(_) => ErrorKind::Basic,
+        }
+    }
+
+    /// `true` if the error is likely to go away by itself: connection refused / reset / aborted, timeouts, broken pipes
+    /// (for `Io` & `Hyper` errors), `Timeout` errors and an upstream service answering 502, 503 or 504
+    pub fn is_transient(&self) -> bool {
+        match *self {
+            GenericError::Hyper(HyperError::Io(ref err)) | GenericError::Io(ref err) => match err.kind() {
+                IOErrorKind::ConnectionRefused | IOErrorKind::ConnectionReset | IOErrorKind::ConnectionAborted
+                    | IOErrorKind::TimedOut | IOErrorKind::WouldBlock | IOErrorKind::Interrupted
+                    | IOErrorKind::BrokenPipe | IOErrorKind::UnexpectedEof => true,
+                _ => false,
+            },
+            GenericError::Upstream((code, _)) => code == 502 || code == 503 || code == 504,
+            GenericError::Timeout(_) => true,
+            GenericError::Compound((_, ref err)) => err.is_transient(),
+            _ => false,
+        }
+    }
+
+    /// `true` if the failed operation may succeed when retried: transient errors (see `is_transient()`),
+    /// an upstream service answering with a 5xx status or `429 Too Many Requests`
+    pub fn is_retryable(&self) -> bool {
+        match *self {
+            GenericError::Upstream((code, _)) => code >= 500 || code == 429,
+            GenericError::Compound((_, ref err)) => err.is_retryable(),
+            _ => self.is_transient(),
+        }
+    }
+
+    /// HTTP status code best describing this error
+    ///
+    /// - `UrlParse`, `Utf8` & `FromUtf8` => 400 (the input couldn't be parsed)
+    /// - `Serde` => 400 if the input couldn't be deserialized, 500 if the error was raised while serializing
+    ///   (e.g. a map key which isn't a string) or by an I/O error
+    /// - `Hyper` & `Upstream` => 502 (an upstream service couldn't be reached or failed)
+    /// - `Timeout` => 504 (an upstream service didn't answer in time)
+    /// - `CircuitOpen` => 503 (an upstream service is considered down)
+    /// - `Io`, `Rocket` & `Basic` => 500
+    /// - `Compound` => code of its cause
+    pub fn http_code(&self) -> u16 {
+        match *self {
+            GenericError::Hyper(_) => 502,
+            GenericError::Io(_) => 500,
+            GenericError::Rocket(_) => 500,
+            GenericError::Serde(ref err) => match err.classify() {
+                SerdeCategory::Io => 500,
+                // the syntax errors of the serializer have no position in the input
+                SerdeCategory::Syntax if err.line() == 0 => 500,
+                _ => 400,
+            },
+            GenericError::UrlParse(_) => 400,
+            GenericError::Utf8(_) => 400,
+            GenericError::FromUtf8(_) => 400,
+            GenericError::Upstream(_) => 502,
+            GenericError::Timeout(_) => 504,
+            GenericError::CircuitOpen(_) => 503,
+            GenericError::Compound((_, ref err)) => err.http_code(),
+            GenericError::Basic(_) => 500,
+        }
     }
 }
 
@@ -50,6 +199,12 @@ impl Error for GenericError {
             GenericError::Hyper(ref err) => err.description(),
             GenericError::Io(ref err) => err.description(),
             GenericError::Serde(ref err) => err.description(),
+            GenericError::UrlParse(ref err) => err.description(),
+            GenericError::Utf8(ref err) => err.description(),
+            GenericError::FromUtf8(ref err) => err.description(),
+            GenericError::Upstream((_, ref description)) => description,
+            GenericError::Timeout(ref err) => err.description(),
+            GenericError::CircuitOpen(ref description) => description,
             GenericError::Rocket(_) => "Rocket Error - not implementing Error yet",
             GenericError::Compound((ref description, _)) => description,
             GenericError::Basic(ref err) => err.as_ref(),
@@ -62,52 +217,159 @@ impl Error for GenericError {
             GenericError::Io(ref err) => err.cause(),
             GenericError::Rocket(_) => None, // Rocket Error doesn't implement Error trait yet
             GenericError::Serde(ref err) => err.cause(),
+            GenericError::UrlParse(ref err) => err.cause(),
+            GenericError::Utf8(ref err) => err.cause(),
+            GenericError::FromUtf8(ref err) => err.cause(),
+            GenericError::Upstream(_) => None,
+            GenericError::Timeout(ref err) => err.cause(),
+            GenericError::CircuitOpen(_) => None,
             GenericError::Compound((_,ref err)) => Some(err),
             GenericError::Basic(_) => None,
         }
     }
 }
 
+impl<'e> Iterator for ErrorChain<'e> {
+    type Item = &'e Error;
+
+    fn next(&mut self) -> Option<&'e Error> {
+        let current = self.next.take();
+        self.next = current.and_then(|err| err.cause());
+        current
+    }
+}
+
 impl fmt::Display for GenericError{
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             GenericError::Hyper(ref err) => fmt::Display::fmt(err, f),
             GenericError::Io(ref err) => fmt::Display::fmt(err, f),
+            GenericError::Timeout(ref err) => fmt::Display::fmt(err, f),
             GenericError::Serde(ref err) => fmt::Display::fmt(err, f),
+            GenericError::UrlParse(ref err) => fmt::Display::fmt(err, f),
+            GenericError::Utf8(ref err) => fmt::Display::fmt(err, f),
+            GenericError::FromUtf8(ref err) => fmt::Display::fmt(err, f),
             // GenericError::Rocket(ref err) => fmt::Display::fmt(err, f),
             _ => f.write_str(self.description()),
         }
     }
 }
 
+/// Serialize a `GenericError` as `{ "kind": ..., "message": ..., "causes": [...] }`,
+/// `causes` holding the messages of the chain of causes (closest first)
+///
+/// ```rust,ignore
+/// let err = GenericError::new_compound("Invalid body", GenericError::Serde(serde_err));
+/// assert_eq!(serde_json::to_value(&err)?, json!({
+///     "kind": "compound",
+///     "message": "Invalid body caused by key must be a string at line 1 column 3",
+///     "causes": ["key must be a string at line 1 column 3"]
+/// }));
+/// ```
+impl Serialize for GenericError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let causes : Vec<String> = self.iter_chain().skip(1).map(|err| err.to_string()).collect();
+
+        let mut state = serializer.serialize_struct("GenericError", 3)?;
+        state.serialize_field("kind", &self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("causes", &causes)?;
+        state.end()
+    }
+}
+
+/// Implements Rocket's `Responder` so a route can return `Result<ResponseJSON, GenericError>` and use `?` on any error.
+/// The error is logged and answered with an error ResponseJSON (see `ResponseJSON::from_error()`) whose status is `http_code()`
+///
+/// Rocket 0.2 doesn't give the request to `Responder::respond()`, so `method` & `resource` are left empty;
+/// use `ResponseJSON::from_error(err).method(...).resource(...)` inside the route if they are needed
+///
+/// ```rust,ignore
+/// #[get("/users/<id>")]
+/// fn get_user(id: u32) -> Result<ResponseJSON, GenericError> {
+///     let user = serde_json::to_value(find_user(id)?)?;
+///     Ok(ResponseJSON::ok().data(user))
+/// }
+/// ```
+impl<'r> Responder<'r> for GenericError {
+    fn respond(self) -> Result<Response<'r>, Status> {
+        error!("::AMIWO::ERROR::GENERIC_ERROR::RESPOND::ERROR {}", self);
+        ResponseJSON::from_error(self).respond()
+    }
+}
+
 // Implement `From` as per book guideline -> https://doc.rust-lang.org/book/error-handling.html#the-from-trait
+/// A `HyperError::Io` timing out (`TimedOut`, or `WouldBlock` returned by a socket whose read / write timeout expired)
+/// is converted into `GenericError::Timeout`
 impl From<HyperError> for GenericError {
     fn from(err: HyperError) -> GenericError {
-        GenericError::Hyper(err)
+        match err {
+            HyperError::Io(err) => match err.kind() {
+                IOErrorKind::TimedOut | IOErrorKind::WouldBlock => hook::notify(GenericError::Timeout(err)),
+                _ => hook::notify(GenericError::Hyper(HyperError::Io(err))),
+            },
+            err => hook::notify(GenericError::Hyper(err)),
+        }
     }
 }
 
 impl From<IOError> for GenericError {
     fn from(err: IOError) -> GenericError {
-        GenericError::Io(err)
+        hook::notify(GenericError::Io(err))
     }
 }
 
 impl From<RocketError> for GenericError {
     fn from(err: RocketError) -> GenericError {
-        GenericError::Rocket(err)
+        hook::notify(GenericError::Rocket(err))
     }
 }
 
 impl From<SerdeError> for GenericError {
     fn from(err: SerdeError) -> GenericError {
-        GenericError::Serde(err)
+        hook::notify(GenericError::Serde(err))
+    }
+}
+
+impl From<UrlParseError> for GenericError {
+    fn from(err: UrlParseError) -> GenericError {
+        hook::notify(GenericError::UrlParse(err))
+    }
+}
+
+impl From<Utf8Error> for GenericError {
+    fn from(err: Utf8Error) -> GenericError {
+        hook::notify(GenericError::Utf8(err))
+    }
+}
+
+impl From<FromUtf8Error> for GenericError {
+    fn from(err: FromUtf8Error) -> GenericError {
+        hook::notify(GenericError::FromUtf8(err))
     }
 }
 
 impl From<String> for GenericError {
     fn from(err: String) -> GenericError {
-        GenericError::Basic(err)
+        hook::notify(GenericError::Basic(err))
+    }
+}
+
+/// A `reqwest::Error` timing out is converted into `GenericError::Timeout`, an I/O or HTTP error into `GenericError::Io`
+/// (of the kind of the underlying `std::io::Error` if any), any other error into `GenericError::Basic`
+#[cfg(feature = "rest-reqwest")]
+impl From<ReqwestError> for GenericError {
+    fn from(err: ReqwestError) -> GenericError {
+        let description = format!("::AMIWO::CONTRIB::REQWEST::ERROR Unable to send the request: {}", err);
+        if err.is_timeout() {
+            return hook::notify(GenericError::Timeout(IOError::new(IOErrorKind::TimedOut, description)));
+        }
+
+        match err.get_ref().and_then(|cause| cause.downcast_ref::<IOError>()).map(|io_err| io_err.kind()) {
+            Some(kind) => hook::notify(GenericError::Io(IOError::new(kind, description))),
+            None if err.is_http() => hook::notify(GenericError::Io(IOError::new(IOErrorKind::Other, description))),
+            None => hook::notify(GenericError::Basic(description)),
+        }
     }
 }
 
@@ -119,7 +381,12 @@ mod tests {
     #![allow(non_snake_case)]
 
     use std::error::Error;
-    use super::GenericError;
+    use std::io;
+    use hyper::error::Error as HyperError;
+    use rocket::http::Status;
+    use rocket::response::Responder;
+    use serde_json;
+    use super::{ ErrorKind, GenericError };
 
     #[test]
     fn GenericError_test_compound() {
@@ -134,4 +401,120 @@ mod tests {
             _ => panic!("invalid cause"),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn GenericError_test_http_code() {
+        assert_eq!(GenericError::Basic("Test error".to_string()).http_code(), 500);
+
+        let err = serde_json::from_str::<serde_json::Value>("{ invalid").unwrap_err();
+        assert_eq!(GenericError::Serde(err).http_code(), 400);
+
+        let err = serde_json::from_str::<serde_json::Value>("{ invalid").unwrap_err();
+        let err = GenericError::new_compound("test description", GenericError::Serde(err));
+        assert_eq!(err.http_code(), 400);
+
+        let err : GenericError = String::from_utf8(vec![0xff]).unwrap_err().into();
+        assert_eq!(err.http_code(), 400);
+
+        let err = serde_json::from_value::<u32>(json!("17")).unwrap_err();
+        assert_eq!(GenericError::Serde(err).http_code(), 400);
+
+        let mut map = ::std::collections::BTreeMap::new();
+        map.insert(vec![1], 1);
+        let err = serde_json::to_value(&map).unwrap_err();
+        assert_eq!(GenericError::Serde(err).http_code(), 500);
+    }
+
+    #[test]
+    fn GenericError_test_from() {
+        match GenericError::from(::hyper::Url::parse("not a url").unwrap_err()) {
+            GenericError::UrlParse(_) => (),
+            err => panic!("unexpected error {:?}", err),
+        }
+        let bytes = vec![b'a', 0xff];
+        match GenericError::from(::std::str::from_utf8(&bytes).unwrap_err()) {
+            GenericError::Utf8(_) => (),
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn GenericError_test_serialize() {
+        let err = GenericError::new_compound("Invalid body", GenericError::Basic("Test error".to_string()));
+        assert_eq!(err.kind(), ErrorKind::Compound);
+        assert_eq!(serde_json::to_value(&err).unwrap(), json!({
+            "kind": "compound",
+            "message": "Invalid body caused by Test error",
+            "causes": ["Test error"]
+        }));
+
+        let err = GenericError::from(String::from_utf8(vec![0xff]).unwrap_err());
+        assert_eq!(err.kind(), ErrorKind::FromUtf8);
+        assert_eq!(serde_json::to_value(&err).unwrap()["kind"], json!("from_utf8"));
+    }
+
+    #[test]
+    fn GenericError_test_iter_chain() {
+        let err = GenericError::new_compound("Invalid request",
+            GenericError::new_compound("Invalid body", GenericError::Basic("Test error".to_string())));
+        let chain : Vec<String> = err.iter_chain().map(|err| err.to_string()).collect();
+        assert_eq!(chain, vec![
+            "Invalid request caused by Invalid body caused by Test error".to_string(),
+            "Invalid body caused by Test error".to_string(),
+            "Test error".to_string(),
+        ]);
+        assert_eq!(err.root_cause().to_string(), "Test error");
+
+        let err = GenericError::Basic("Test error".to_string());
+        assert_eq!(err.iter_chain().count(), 1);
+        assert_eq!(err.root_cause().to_string(), "Test error");
+    }
+
+    #[test]
+    fn GenericError_test_is_retryable() {
+        let err = GenericError::Io(io::Error::new(io::ErrorKind::ConnectionReset, "reset"));
+        assert!(err.is_transient() && err.is_retryable());
+
+        let err = GenericError::new_compound("Unable to call upstream", GenericError::Hyper(HyperError::Io(io::Error::new(io::ErrorKind::TimedOut, "timeout"))));
+        assert!(err.is_transient() && err.is_retryable());
+
+        let err = GenericError::Upstream((500, "Internal Server Error".to_string()));
+        assert!(!err.is_transient() && err.is_retryable());
+        assert_eq!(err.http_code(), 502);
+        assert!(GenericError::Upstream((503, "Service Unavailable".to_string())).is_transient());
+        assert!(GenericError::Upstream((429, "Too Many Requests".to_string())).is_retryable());
+        assert!(!GenericError::Upstream((404, "Not Found".to_string())).is_retryable());
+
+        let err = GenericError::Io(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert!(!err.is_transient() && !err.is_retryable());
+        assert!(!GenericError::Hyper(HyperError::Method).is_retryable());
+
+        let err = GenericError::from(HyperError::Io(io::Error::new(io::ErrorKind::WouldBlock, "Resource temporarily unavailable")));
+        assert_eq!(err.kind(), ErrorKind::Timeout);
+        assert_eq!(err.http_code(), 504);
+        assert!(err.is_transient() && err.is_retryable());
+        assert_eq!(GenericError::from(HyperError::Io(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))).kind(), ErrorKind::Hyper);
+        assert!(!GenericError::Basic("Test error".to_string()).is_retryable());
+
+        let err = GenericError::CircuitOpen("Circuit open".to_string());
+        assert!(!err.is_transient() && !err.is_retryable());
+        assert_eq!(err.http_code(), 503);
+        assert_eq!(err.kind(), ErrorKind::CircuitOpen);
+    }
+
+    #[test]
+    fn GenericError_test_responder() {
+        let err = serde_json::from_str::<serde_json::Value>("{ invalid").unwrap_err();
+        let mut response = GenericError::new_compound("Invalid body", GenericError::Serde(err)).respond().unwrap();
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let body_str = response.body().and_then(|b| b.into_string()).unwrap();
+        let body : serde_json::Value = serde_json::from_str(&body_str).unwrap();
+        assert_eq!(body["success"], json!(false));
+        assert_eq!(body["http_code"], json!(400));
+        assert!(body["message"].as_str().unwrap().starts_with("Invalid body caused by"));
+
+        let response = GenericError::Basic("Test error".to_string()).respond().unwrap();
+        assert_eq!(response.status(), Status::InternalServerError);
+    }
+}
@@ -0,0 +1,100 @@
+//! File holding the global hook called on every `GenericError` created through the `From` implementations,
+//! `GenericError::new_compound()` or the `amiwo_error!` / `amiwo_bail!` / `amiwo_ensure!` macros
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::mem;
+use std::sync::atomic::{ AtomicUsize, Ordering, ATOMIC_USIZE_INIT };
+
+use error::GenericError;
+
+// =======================================================================
+// STATIC VARIABLES
+// =======================================================================
+// Address of the hook function, 0 if none is set
+static ERROR_HOOK: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Set the function called on every `GenericError` created, e.g. to emit metrics or structured logs, replacing the previous one.
+///
+/// A `Compound` error triggers the hook as well as its cause if both are created through the `From` implementations & the macros
+///
+/// ```rust,ignore
+/// fn log_error(err: &GenericError) {
+///     if let Ok(json) = serde_json::to_string(err) {
+///         error!("{}", json);
+///     }
+/// }
+///
+/// amiwo::error::set_error_hook(log_error);
+/// ```
+pub fn set_error_hook(hook: fn(&GenericError)) {
+    ERROR_HOOK.store(hook as usize, Ordering::SeqCst);
+}
+
+/// Remove the hook set by `set_error_hook()`
+pub fn clear_error_hook() {
+    ERROR_HOOK.store(0, Ordering::SeqCst);
+}
+
+/// Call the hook (if any) on `err` & return it
+pub(crate) fn notify(err: GenericError) -> GenericError {
+    let hook = ERROR_HOOK.load(Ordering::SeqCst);
+    if hook != 0 {
+        // Safe: only `set_error_hook()` stores a non-zero value, always a `fn(&GenericError)`
+        let hook : fn(&GenericError) = unsafe { mem::transmute(hook) };
+        hook(&err);
+    }
+    err
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::error::Error;
+    use std::sync::atomic::{ AtomicUsize, Ordering, ATOMIC_USIZE_INIT };
+
+    use super::{ clear_error_hook, set_error_hook };
+    use error::GenericError;
+
+    static HOOK_CALLS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+    // Only count the errors of this test as the hook is global
+    fn count_error(err: &GenericError) {
+        if err.description().starts_with("::AMIWO::ERROR::HOOK::TEST") {
+            HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn failing() -> Result<(), GenericError> {
+        amiwo_bail!("::AMIWO::ERROR::HOOK::TEST bail {}", 1);
+    }
+
+    #[test]
+    fn hook_test_set_error_hook() {
+        set_error_hook(count_error);
+
+        let _ = GenericError::from("::AMIWO::ERROR::HOOK::TEST from".to_string());
+        let _ = failing();
+        let _ = GenericError::new_compound("::AMIWO::ERROR::HOOK::TEST compound", GenericError::Basic("cause".to_string()));
+        let _ = GenericError::Basic("::AMIWO::ERROR::HOOK::TEST direct".to_string());
+        assert_eq!(HOOK_CALLS.load(Ordering::SeqCst), 3);
+
+        clear_error_hook();
+        let _ = failing();
+        assert_eq!(HOOK_CALLS.load(Ordering::SeqCst), 3);
+    }
+}
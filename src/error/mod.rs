@@ -1,3 +1,7 @@
 mod generic_error;
+mod hook;
+mod result_ext;
 
-pub use self::generic_error::GenericError;
\ No newline at end of file
+pub use self::generic_error::{ ErrorChain, ErrorKind, GenericError };
+pub use self::hook::{ clear_error_hook, set_error_hook };
+pub use self::result_ext::ResultExt;
\ No newline at end of file
@@ -0,0 +1,66 @@
+//! File holding the ResultExt trait attaching context to the errors of a `Result`
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use error::GenericError;
+
+// =======================================================================
+// TRAIT DEFINITION
+// =======================================================================
+/// Attach a description to the error of a `Result`, wrapping it into a `GenericError::Compound`
+///
+/// ```rust,ignore
+/// let file = File::open(path).context(format!("Unable to open config file {}", path))?;
+/// let config : Value = serde_json::from_reader(file).context("Invalid config file")?;
+/// ```
+pub trait ResultExt<T> {
+    fn context<S: ToString>(self, msg: S) -> Result<T, GenericError>;
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+/// Implements `ResultExt` for any `Result` whose error converts into a `GenericError`
+impl<T, E: Into<GenericError>> ResultExt<T> for Result<T, E> {
+    fn context<S: ToString>(self, msg: S) -> Result<T, GenericError> {
+        self.map_err(|err| GenericError::new_compound(msg, err.into()))
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::error::Error;
+    use serde_json;
+
+    use super::ResultExt;
+    use error::GenericError;
+
+    #[test]
+    fn ResultExt_test_context() {
+        let err = serde_json::from_str::<serde_json::Value>("{ invalid").context("Invalid body").unwrap_err();
+        assert!(err.description().starts_with("Invalid body caused by "));
+        assert_eq!(err.http_code(), 400);
+        match err.cause() {
+            Some(cause) => assert!(cause.description().len() > 0),
+            None => panic!("missing cause"),
+        }
+
+        let ok : Result<u8, GenericError> = Ok(1);
+        assert_eq!(ok.context("unused").unwrap(), 1);
+
+        let err : Result<(), String> = Err("Test error".to_string());
+        assert_eq!(err.context(17).unwrap_err().description(), "17 caused by Test error");
+    }
+}
@@ -33,14 +33,24 @@
 
 #[macro_use] extern crate log;
 
+#[cfg(feature = "amiwo_derive")]
+#[allow(unused_imports)]
+#[macro_use] extern crate amiwo_derive;
+#[cfg(any(feature = "amiwo_gzip", feature = "amiwo_hyper"))]
+extern crate flate2;
 extern crate hyper;
+#[cfg(feature = "rest-reqwest")]
+extern crate reqwest;
+#[cfg(feature = "amiwo_msgpack")]
+extern crate rmp_serde;
 extern crate rocket;
 extern crate serde;
+#[macro_use] extern crate serde_derive;
 #[macro_use] extern crate serde_json;
 
 // Amiwo specific modules
-pub mod error;
 #[macro_use] pub mod macros;
+pub mod error;
 pub mod util;
 pub mod traits;
 pub mod types;
@@ -48,13 +58,33 @@ pub mod types;
 pub mod contrib;
 
 // Errors, Types & Trait shortcuts
-pub use error::GenericError;
+pub use error::{ ErrorKind, GenericError, ResultExt };
+
+#[cfg(feature = "amiwo_derive")]
+pub use amiwo_derive::IntoResponseJSON;
 
 #[cfg(feature = "amiwo_rocket")]
 pub use contrib::rocket::FormHashMap;
+#[cfg(feature = "amiwo_rocket")]
+pub use contrib::rocket::RequestId;
 
+pub use types::BatchResponseJSON;
+pub use types::FieldError;
 pub use types::IsResponseJSON;
+pub use types::JsonDiff;
+pub use types::KeyCase;
 pub use types::OneOrMany;
+pub use types::ParseMode;
+pub use types::ProblemDetails;
 pub use types::ResponseJSON;
+pub use types::StreamedResponseJSON;
 
-pub use traits::Pushable;
\ No newline at end of file
+pub use traits::IntoResponseJSON;
+pub use traits::Mergeable;
+pub use traits::Poppable;
+pub use traits::PushSerialize;
+pub use traits::Pushable;
+pub use traits::PushableAt;
+pub use traits::PushableExt;
+pub use traits::Takeable;
+pub use traits::TryPushable;
\ No newline at end of file
@@ -5,7 +5,10 @@
 //! - hyper_request! : pseudo function `fn hyper_request(hyper::method::Method, url: hyper::client::IntoUrl, [headers: hyper::header::Headers], [body: Into<hyper::body::Body<'a>>]) -> Result<amiwo::contrib::rocket::ResponseJSON, GenericError>
 //! - amiwo_macro : pseudo functions
 //!      `fn amiwo_macro(description: ToString, cause: GenericError) -> Result<_, amiwo::GenericError::Compound>`
-//!      `fn amiwo_macro(error) -> Result<_, amiwo::GenericError::Basic>`
+//!      `fn amiwo_macro(error: Into<String>) -> Result<_, amiwo::GenericError::Basic>`
+//!   the errors being passed to the error hook (see `amiwo::error::set_error_hook()`)
+//! - amiwo_bail! : pseudo function `fn amiwo_bail(format: &str, args...) -> !` returning early with `Err(GenericError::Basic(format!(format, args...)))`
+//! - amiwo_ensure! : pseudo function `fn amiwo_ensure(condition: bool, format: &str, args...)` calling `amiwo_bail!(format, args...)` if `condition` is false
 
 // =======================================================================
 // MACRO DEFINITIONS
@@ -15,7 +18,21 @@ macro_rules! amiwo_error {
         Err(GenericError::new_compound($description, $cause))
     };
     ($error:expr) => {
-        Err(GenericError::Basic($error))
+        Err(GenericError::from(String::from($error)))
+    };
+}
+
+macro_rules! amiwo_bail {
+    ($($arg:tt)+) => {
+        return Err(GenericError::from(format!($($arg)+)))
+    };
+}
+
+macro_rules! amiwo_ensure {
+    ($condition:expr, $($arg:tt)+) => {
+        if !$condition {
+            amiwo_bail!($($arg)+);
+        }
     };
 }
 
@@ -29,6 +46,20 @@ mod tests {
     use std::error::Error;
     use error::GenericError;
 
+    #[test]
+    fn macros_test_basic() {
+        let err : Result<(), GenericError> = amiwo_error!("Test error");
+        match err {
+            Err(GenericError::Basic(msg)) => assert_eq!(msg, "Test error"),
+            other => panic!("unexpected result {:?}", other),
+        }
+        let err : Result<(), GenericError> = amiwo_error!(format!("Test error {}", 17));
+        match err {
+            Err(GenericError::Basic(msg)) => assert_eq!(msg, "Test error 17"),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
     #[test]
     fn macros_test_compound() {
         let err : Result<(), _> = amiwo_error!("test description", GenericError::Basic("Test error".to_string()));
@@ -43,4 +74,22 @@ mod tests {
             _ => panic!("invalid cause"),
         }
     }
-}
\ No newline at end of file
+
+    fn check_page(page: i64) -> Result<i64, GenericError> {
+        amiwo_ensure!(page > 0, "::AMIWO::TEST::ERROR Invalid page {}, it must be positive", page);
+        if page > 100 {
+            amiwo_bail!("::AMIWO::TEST::ERROR Page {} is out of range", page);
+        }
+        Ok(page)
+    }
+
+    #[test]
+    fn macros_test_bail_ensure() {
+        assert_eq!(check_page(2).unwrap(), 2);
+        assert_eq!(check_page(0).unwrap_err().to_string(), "::AMIWO::TEST::ERROR Invalid page 0, it must be positive");
+        match check_page(101) {
+            Err(GenericError::Basic(msg)) => assert_eq!(msg, "::AMIWO::TEST::ERROR Page 101 is out of range"),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! File holding the IntoResponseJSON trait and associated tests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use serde::Serialize;
+
+use types::ResponseJSON;
+
+// =======================================================================
+// TRAIT DEFINITION
+// =======================================================================
+/// Types that can be wrapped into an OK ResponseJSON, using themselves as `data`.
+///
+/// Usually implemented with `#[derive(IntoResponseJSON)]` (feature `amiwo_derive`), which also implements
+/// Rocket's `Responder` so that the type can be returned directly from a route
+pub trait IntoResponseJSON: Serialize + Sized {
+    /// HTTP code of the envelope (200 by default)
+    fn http_code() -> u16 {
+        200
+    }
+
+    /// Wrap `self` into an OK ResponseJSON with `Self::http_code()` as HTTP code
+    fn into_response_json(self) -> ResponseJSON<Self> {
+        ResponseJSON::ok().http_code(Self::http_code()).with_data(self)
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::IntoResponseJSON;
+
+    #[derive(Serialize)]
+    struct User {
+        id: u32,
+    }
+
+    impl IntoResponseJSON for User {}
+
+    #[derive(Serialize)]
+    struct CreatedUser {
+        id: u32,
+    }
+
+    impl IntoResponseJSON for CreatedUser {
+        fn http_code() -> u16 { 201 }
+    }
+
+    #[test]
+    fn IntoResponseJSON_test_into_response_json() {
+        let json = User { id: 17 }.into_response_json();
+        assert_eq!(json.success, true);
+        assert_eq!(json.http_code, 200);
+        assert_eq!(json.data.id, 17);
+
+        let json = CreatedUser { id: 18 }.into_response_json();
+        assert_eq!(json.success, true);
+        assert_eq!(json.http_code, 201);
+        assert_eq!(json.data.id, 18);
+    }
+}
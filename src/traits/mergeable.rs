@@ -0,0 +1,143 @@
+//! File holding the Mergeable trait and associated tests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use serde_json::Value;
+use serde_json::map::Map;
+
+// =======================================================================
+// STRUCT & TRAIT DEFINITION
+// =======================================================================
+/// How `Mergeable::merge_with()` combines two arrays found at the same key
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArrayMerge {
+    /// The array of `other` replaces the existing one (default)
+    Replace,
+    /// The items of `other` are appended to the existing ones
+    Concat,
+    /// The items of `other` not already present are appended to the existing ones
+    Union,
+}
+
+/// Deep merge of JSON objects, e.g. to overlay the request parameters on top of defaults.
+///
+/// Unlike `Pushable` which gathers the values into arrays, objects are merged key by key (recursively)
+/// and any other value of `other` replaces the existing one
+///
+/// ```rust,ignore
+/// let mut params = json!({ "page": 1, "filter": { "status": "active", "tags": ["a"] } });
+/// params.merge(json!({ "filter": { "tags": ["b"] } }));
+/// assert_eq!(params, json!({ "page": 1, "filter": { "status": "active", "tags": ["b"] } }));
+/// ```
+pub trait Mergeable<T> {
+    /// Deep merge `other` into `self`, arrays being replaced
+    fn merge(&mut self, other: T) -> &mut Self {
+        self.merge_with(other, ArrayMerge::Replace)
+    }
+
+    /// Deep merge `other` into `self`, arrays being combined according to `arrays`
+    fn merge_with(&mut self, other: T, arrays: ArrayMerge) -> &mut Self;
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Default for ArrayMerge {
+    fn default() -> ArrayMerge {
+        ArrayMerge::Replace
+    }
+}
+
+/// Implements `Mergeable` for `serde_json::Value`
+///
+/// - If both `self` & `other` are objects => merges them key by key
+/// - If both are arrays => combines them according to the `ArrayMerge` strategy
+/// - Otherwise => `other` replaces `self`
+impl Mergeable<Value> for Value {
+    fn merge_with(&mut self, other: Value, arrays: ArrayMerge) -> &mut Self {
+        match (self as &mut Value, other) {
+            (&mut Value::Object(ref mut map), Value::Object(other)) => {
+                map.merge_with(other, arrays);
+            },
+            (&mut Value::Array(ref mut vect), Value::Array(other)) => match arrays {
+                ArrayMerge::Replace => *vect = other,
+                ArrayMerge::Concat => vect.extend(other),
+                ArrayMerge::Union => for item in other {
+                    if !vect.contains(&item) { vect.push(item); }
+                },
+            },
+            (this, other) => *this = other,
+        }
+        self
+    }
+}
+
+/// Implements `Mergeable` for `serde_json::Map`, merging the values of the keys present in both maps
+impl Mergeable<Map<String, Value>> for Map<String, Value> {
+    fn merge_with(&mut self, other: Map<String, Value>, arrays: ArrayMerge) -> &mut Self {
+        for (key, value) in other {
+            if self.contains_key(&key) {
+                self.get_mut(&key).unwrap().merge_with(value, arrays);
+            } else {
+                self.insert(key, value);
+            }
+        }
+        self
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ ArrayMerge, Mergeable };
+
+    #[test]
+    fn Mergeable_test_value() {
+        let mut x = json!({ "page": 1, "filter": { "status": "active", "tags": ["a"] } });
+        x.merge(json!({ "per_page": 50, "filter": { "tags": ["b"], "deleted": null } }));
+        assert_eq!(x, json!({ "page": 1, "per_page": 50, "filter": { "status": "active", "tags": ["b"], "deleted": null } }));
+
+        let mut x = json!({ "a": { "b": 1 } });
+        x.merge(json!({ "a": "flat" }));
+        assert_eq!(x, json!({ "a": "flat" }));
+
+        let mut x = json!("a");
+        x.merge(json!({ "b": 1 })).merge(json!({ "c": 2 }));
+        assert_eq!(x, json!({ "b": 1, "c": 2 }));
+    }
+
+    #[test]
+    fn Mergeable_test_arrays() {
+        let mut x = json!({ "tags": ["a", "b"] });
+        x.merge_with(json!({ "tags": ["b", "c"] }), ArrayMerge::Concat);
+        assert_eq!(x, json!({ "tags": ["a", "b", "b", "c"] }));
+
+        let mut x = json!({ "tags": ["a", "b"] });
+        x.merge_with(json!({ "tags": ["b", "c"] }), ArrayMerge::Union);
+        assert_eq!(x, json!({ "tags": ["a", "b", "c"] }));
+
+        let mut x = json!(["a"]);
+        x.merge_with(json!(["b"]), ArrayMerge::default());
+        assert_eq!(x, json!(["b"]));
+    }
+
+    #[test]
+    fn Mergeable_test_map() {
+        let mut defaults = json!({ "page": 1, "sort": { "by": "name" } }).as_object().unwrap().clone();
+        let params = json!({ "sort": { "order": "desc" } }).as_object().unwrap().clone();
+        defaults.merge(params);
+        assert_eq!(defaults.get("page"), Some(&json!(1)));
+        assert_eq!(defaults.get("sort"), Some(&json!({ "by": "name", "order": "desc" })));
+    }
+}
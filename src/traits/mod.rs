@@ -1,3 +1,11 @@
+mod into_response_json;
+mod mergeable;
+mod poppable;
 mod pushable;
+mod pushable_at;
 
-pub use self::pushable::Pushable;
\ No newline at end of file
+pub use self::into_response_json::IntoResponseJSON;
+pub use self::mergeable::{ ArrayMerge, Mergeable };
+pub use self::poppable::{ Poppable, Takeable };
+pub use self::pushable::{ PushSerialize, Pushable, PushableExt, TryPushable };
+pub use self::pushable_at::PushableAt;
\ No newline at end of file
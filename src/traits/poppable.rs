@@ -0,0 +1,104 @@
+//! File holding the Poppable & Takeable traits, counterparts of Pushable, and associated tests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::mem;
+
+use serde_json::Value;
+use serde_json::map::Map;
+
+// =======================================================================
+// TRAIT DEFINITION
+// =======================================================================
+/// Remove the last pushed value, symmetrically to `Pushable::push()`
+pub trait Poppable<T> {
+    fn pop(&mut self) -> Option<T>;
+}
+
+/// Remove the value associated with a key, e.g. to strip a parameter (`api_key`) before forwarding the others upstream
+pub trait Takeable<K, V> {
+    fn take(&mut self, key: K) -> Option<V>;
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+/// Implements `Poppable<T>` for `Vec<T>`
+impl<T> Poppable<T> for Vec<T> {
+    fn pop(&mut self) -> Option<T> {
+        Vec::pop(self)
+    }
+}
+
+/// Implements `Poppable` for `serde_json::Value`, undoing `Pushable<Value> for Value`
+///
+/// - If `self` is a `Value::Array` => removes the last item, an array left with a single item being replaced by this item
+/// - If `self` is `Value::Null` => returns `None`
+/// - Otherwise => returns the value, `self` becoming `Value::Null`
+impl Poppable<Value> for Value {
+    fn pop(&mut self) -> Option<Value> {
+        match mem::replace(self, Value::Null) {
+            Value::Null => None,
+            Value::Array(mut vect) => {
+                let popped = vect.pop();
+                *self = if vect.len() == 1 { vect.pop().unwrap() } else { Value::Array(vect) };
+                popped
+            },
+            value => Some(value),
+        }
+    }
+}
+
+/// Implements `Takeable` for `serde_json::Map`, removing the key
+impl<'k> Takeable<&'k str, Value> for Map<String, Value> {
+    fn take(&mut self, key: &'k str) -> Option<Value> {
+        self.remove(key)
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::{ Poppable, Takeable };
+    use serde_json::Value;
+    use traits::Pushable;
+
+    #[test]
+    fn Poppable_test_value() {
+        let mut x = Value::Null;
+        x.push("a").push("b").push("c");
+        assert_eq!(x.pop(), Some(json!("c")));
+        assert_eq!(x, json!(["a", "b"]));
+        assert_eq!(x.pop(), Some(json!("b")));
+        assert_eq!(x, json!("a"));
+        assert_eq!(x.pop(), Some(json!("a")));
+        assert_eq!(x, Value::Null);
+        assert_eq!(x.pop(), None);
+
+        let mut x = json!([]);
+        assert_eq!(x.pop(), None);
+        assert_eq!(x, json!([]));
+
+        let mut vect = vec![1, 2];
+        assert_eq!(Poppable::pop(&mut vect), Some(2));
+    }
+
+    #[test]
+    fn Takeable_test_map() {
+        let mut map = json!({ "api_key": "secret", "page": 2 }).as_object().unwrap().clone();
+        assert_eq!(map.take("api_key"), Some(json!("secret")));
+        assert_eq!(map.take("api_key"), None);
+        assert_eq!(Value::Object(map), json!({ "page": 2 }));
+    }
+}
@@ -1,16 +1,24 @@
-//! File holding the Pushable trait
+//! File holding the Pushable, PushableExt, TryPushable & PushSerialize traits
 //!
 //! Author: [Boris](mailto:boris@humanenginuity.com)
-//! Version: 1.0
+//! Version: 1.4
 //!
 //! ## Release notes
+//! - v1.4 : added `PushableExt` (`push_unique()` & `push_front()`)
+//! - v1.3 : added `PushSerialize` to push any `Serialize` value into a `Value`
+//! - v1.2 : implemented `Pushable<(String, Value)>` for `serde_json::Map`
+//! - v1.1 : added `TryPushable`, deprecated pushing a `Result` with `Pushable` (panics on `Err`)
 //! - v1.0 : creation
 
 // =======================================================================
 // LIBRARY IMPORTS
 // =======================================================================
+use serde::Serialize;
 use serde_json;
 use serde_json::Value;
+use serde_json::map::Map;
+
+use error::GenericError;
 
 // =======================================================================
 // TRAIT DEFINITION
@@ -19,6 +27,30 @@ pub trait Pushable<T> {
     fn push(&mut self, value: T) -> &mut Self;
 }
 
+/// Variants of `Pushable::push()`, following the same `One` => `Many` rules
+pub trait PushableExt<T>: Pushable<T> {
+    /// Push `value` unless an equal value is already present (e.g. to merge defaults with user input without duplicates)
+    fn push_unique(&mut self, value: T) -> &mut Self;
+
+    /// Push `value` before the existing value(s)
+    fn push_front(&mut self, value: T) -> &mut Self;
+}
+
+/// Fallible version of `Pushable`, returning an error instead of panicking when the value can't be pushed
+pub trait TryPushable<T> {
+    fn try_push(&mut self, value: T) -> Result<&mut Self, GenericError>;
+}
+
+/// Push any `Serialize` value (numbers, booleans, structs, ...), converted with `serde_json::to_value()`
+///
+/// ```rust,ignore
+/// let mut data = json!({ "id": 1 });
+/// data.push_serialize(&user)?.push_serialize(42)?;
+/// ```
+pub trait PushSerialize {
+    fn push_serialize<T: Serialize>(&mut self, value: T) -> Result<&mut Self, GenericError>;
+}
+
 // =======================================================================
 // TRAIT IMPLEMENTATION
 // =======================================================================
@@ -65,19 +97,77 @@ impl Pushable<String> for Value {
 /// It first try to use `serde_json::from_str()` on the String. If it fails, it pushes a new `Value::String()` instead
 impl<'s> Pushable<&'s str> for Value {
     fn push(&mut self, new_value: &'s str) -> &mut Self {
-        let value : Result<Value, _> = serde_json::from_str(new_value);
+        self.push(parse_str(new_value))
+    }
+}
 
-        if value.is_err() {
-            self.push(Value::String(new_value.to_string()));
-        } else {
-            self.push(value.unwrap());
+impl<T: PartialEq> PushableExt<T> for Vec<T> {
+    fn push_unique(&mut self, new_value: T) -> &mut Self {
+        if !self.contains(&new_value) {
+            Vec::push(self, new_value);
         }
         self
     }
+
+    fn push_front(&mut self, new_value: T) -> &mut Self {
+        self.insert(0, new_value);
+        self
+    }
+}
+
+/// - `push_unique()` => a value equal to `self` (or to one of the items of an array) isn't pushed
+/// - `push_front()` => like `push()`, the new value being the first item of the array
+impl PushableExt<Value> for Value {
+    fn push_unique(&mut self, new_value: Value) -> &mut Self {
+        let exists = match *self {
+            Value::Array(ref vect) => vect.contains(&new_value),
+            ref existing_value => *existing_value == new_value,
+        };
+        if !exists {
+            self.push(new_value);
+        }
+        self
+    }
+
+    fn push_front(&mut self, new_value: Value) -> &mut Self {
+        match ::std::mem::replace(self, Value::Null) {
+            Value::Null => *self = new_value,
+            Value::Array(mut vect) => {
+                vect.insert(0, new_value);
+                *self = Value::Array(vect);
+            },
+            existing_value => *self = Value::Array(vec![new_value, existing_value]),
+        }
+        self
+    }
+}
+
+/// Parse the String like `Pushable<String> for Value`
+impl PushableExt<String> for Value {
+    fn push_unique(&mut self, new_value: String) -> &mut Self {
+        self.push_unique(new_value.as_str())
+    }
+
+    fn push_front(&mut self, new_value: String) -> &mut Self {
+        self.push_front(new_value.as_str())
+    }
+}
+
+/// Parse the &str like `Pushable<&str> for Value`
+impl<'s> PushableExt<&'s str> for Value {
+    fn push_unique(&mut self, new_value: &'s str) -> &mut Self {
+        self.push_unique(parse_str(new_value))
+    }
+
+    fn push_front(&mut self, new_value: &'s str) -> &mut Self {
+        self.push_front(parse_str(new_value))
+    }
 }
 
 /// Allow to push Result<Value, _> (sugar for pushing `serde_json::from_xxx()`)
 /// Panics if conversion failed
+///
+/// **Deprecated**: use `TryPushable::try_push()` which returns the error instead, so that malformed input can't crash the caller
 impl Pushable<Result<Value, serde_json::Error>> for Value {
     fn push(&mut self, new_value: Result<Value, serde_json::Error>) -> &mut Self {
         if new_value.is_err() {
@@ -89,6 +179,41 @@ impl Pushable<Result<Value, serde_json::Error>> for Value {
     }
 }
 
+/// Push a `(key, value)` pair into a map: inserts the value if the key is missing, appends it to the existing one otherwise
+/// (see `Pushable<Value> for Value`), i.e. a repeated key becomes an array like in a FormHashMap.
+/// The key is used as is (no bracket notation, see `FormHashMap::push()`)
+impl Pushable<(String, Value)> for Map<String, Value> {
+    fn push(&mut self, (key, value): (String, Value)) -> &mut Self {
+        self.entry(key).or_insert(Value::Null).push(value);
+        self
+    }
+}
+
+/// Push the value of `Ok` (see `Pushable<Value> for Value`), returns the error (`GenericError::Serde`) of `Err` leaving `self` untouched
+impl TryPushable<Result<Value, serde_json::Error>> for Value {
+    fn try_push(&mut self, new_value: Result<Value, serde_json::Error>) -> Result<&mut Self, GenericError> {
+        match new_value {
+            Ok(value) => Ok(self.push(value)),
+            Err(err) => Err(GenericError::Serde(err)),
+        }
+    }
+}
+
+/// Serialize `value` then push it (see `Pushable<Value> for Value`), returns the serialization error (`GenericError::Serde`) leaving `self` untouched
+impl PushSerialize for Value {
+    fn push_serialize<T: Serialize>(&mut self, value: T) -> Result<&mut Self, GenericError> {
+        self.try_push(serde_json::to_value(value))
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Parse `string` as JSON, falling back to a `Value::String`
+fn parse_str(string: &str) -> Value {
+    serde_json::from_str(string).unwrap_or_else(|_| Value::String(string.to_string()))
+}
+
 // =======================================================================
 // UNIT TESTS
 // =======================================================================
@@ -96,8 +221,10 @@ impl Pushable<Result<Value, serde_json::Error>> for Value {
 mod tests {
     #![allow(non_snake_case)]
 
-    use super::Pushable;
+    use super::{ PushSerialize, Pushable, PushableExt, TryPushable };
+    use serde_json;
     use serde_json::Value;
+    use serde_json::map::Map;
 
     #[test]
     fn Pushable_test_value() {
@@ -135,4 +262,79 @@ mod tests {
             "c"
         ]));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn Pushable_test_map() {
+        let mut map = Map::new();
+        map.push(("a".to_string(), json!("b1")))
+            .push(("a".to_string(), json!("b2")))
+            .push(("c".to_string(), json!(1)))
+            .push(("d[e]".to_string(), json!(true)));
+        assert_eq!(Value::Object(map), json!({ "a": ["b1", "b2"], "c": 1, "d[e]": true }));
+    }
+
+    #[test]
+    fn TryPushable_test_value() {
+        let mut x = json!("a");
+        assert!(x.try_push(serde_json::from_str(r#"{ "b": 1 }"#)).is_ok());
+        assert_eq!(x, json!(["a", { "b": 1 }]));
+
+        let result = x.try_push(serde_json::from_str("{ invalid")).map(|_| ());
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().http_code(), 400);
+        assert_eq!(x, json!(["a", { "b": 1 }]));
+    }
+
+    #[test]
+    fn PushSerialize_test_value() {
+        use std::collections::HashMap;
+
+        #[derive(Serialize)]
+        struct User {
+            id: u32,
+        }
+
+        let mut x = Value::Null;
+        x.push_serialize(1).unwrap()
+            .push_serialize(true).unwrap()
+            .push_serialize(&User { id: 17 }).unwrap()
+            .push_serialize(vec!["a"]).unwrap();
+        assert_eq!(x, json!([1, true, { "id": 17 }, ["a"]]));
+
+        let mut invalid = HashMap::new();
+        invalid.insert(vec![1], "non string key");
+        assert!(x.push_serialize(invalid).is_err());
+        assert_eq!(x.as_array().map(|vect| vect.len()), Some(4));
+    }
+
+    #[test]
+    fn PushableExt_test_push_unique() {
+        let mut x = Value::Null;
+        x.push_unique("a").push_unique("a").push_unique("b").push_unique(json!("a")).push_unique("b".to_string());
+        assert_eq!(x, json!(["a", "b"]));
+
+        let mut x = json!(1);
+        x.push_unique("1");
+        assert_eq!(x, json!(1));
+
+        let mut vect = vec![1, 2];
+        PushableExt::push_unique(&mut vect, 2);
+        PushableExt::push_unique(&mut vect, 3);
+        assert_eq!(vect, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn PushableExt_test_push_front() {
+        let mut x = Value::Null;
+        x.push_front("b");
+        assert_eq!(x, json!("b"));
+        x.push_front("a");
+        assert_eq!(x, json!(["a", "b"]));
+        x.push_front(json!(0));
+        assert_eq!(x, json!([0, "a", "b"]));
+
+        let mut vect = vec![2];
+        vect.push_front(1);
+        assert_eq!(vect, vec![1, 2]);
+    }
+}
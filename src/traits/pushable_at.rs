@@ -0,0 +1,137 @@
+//! File holding the PushableAt trait and associated tests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use serde_json::Value;
+use serde_json::map::Map;
+
+use error::GenericError;
+use traits::Pushable;
+
+// =======================================================================
+// TRAIT DEFINITION
+// =======================================================================
+/// Push a value at a [JSON Pointer](https://tools.ietf.org/html/rfc6901) path, creating the missing intermediate values
+///
+/// ```rust,ignore
+/// let mut data = Value::Null;
+/// data.push_at("/users/0/name", json!("Boris"))?
+///     .push_at("/users/0/roles/-", json!("admin"))?;
+/// assert_eq!(data, json!({ "users": [{ "name": "Boris", "roles": ["admin"] }] }));
+/// ```
+pub trait PushableAt<T> {
+    fn push_at(&mut self, pointer: &str, value: T) -> Result<&mut Self, GenericError>;
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+/// Implements `PushableAt` for `serde_json::Value`
+///
+/// - A missing (or `null`) intermediate value is created as an array if the next token is an index or `-`, an object otherwise
+/// - The value found at the end of the path is pushed into (see `Pushable<Value> for Value`), `-` appending a new array item
+///
+/// Returns an error if the pointer doesn't start with `/`, if an index is out of bounds
+/// or if the path goes through a scalar, leaving the values already created in place
+impl PushableAt<Value> for Value {
+    fn push_at(&mut self, pointer: &str, value: Value) -> Result<&mut Self, GenericError> {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return amiwo_error!(format!("::AMIWO::TRAITS::PUSHABLE_AT::PUSH_AT::ERROR Invalid JSON pointer `{}`, it must start with `/`", pointer));
+        }
+
+        let tokens : Vec<String> = pointer.split('/').skip(1)
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        push_at_tokens(self, &tokens, value)
+            .or_else(|reason| amiwo_error!(format!("::AMIWO::TRAITS::PUSHABLE_AT::PUSH_AT::ERROR Unable to push at `{}`: {}", pointer, reason)))?;
+        Ok(self)
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Push `value` in `target` following `tokens`
+fn push_at_tokens(target: &mut Value, tokens: &[String], value: Value) -> Result<(), String> {
+    let (token, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => {
+            target.push(value);
+            return Ok(());
+        },
+    };
+
+    if target.is_null() {
+        *target = if token == "-" || token.parse::<usize>().is_ok() { Value::Array(Vec::new()) } else { Value::Object(Map::new()) };
+    }
+
+    match *target {
+        Value::Object(ref mut map) => push_at_tokens(map.entry(token.as_str()).or_insert(Value::Null), rest, value),
+        Value::Array(ref mut vect) => {
+            let index = if token == "-" {
+                vect.len()
+            } else {
+                match token.parse::<usize>() {
+                    Ok(index) if index <= vect.len() => index,
+                    Ok(index) => return Err(format!("index {} is out of bounds (length {})", index, vect.len())),
+                    Err(_) => return Err(format!("`{}` isn't an array index", token)),
+                }
+            };
+            if index == vect.len() {
+                vect.push(Value::Null);
+            }
+            push_at_tokens(&mut vect[index], rest, value)
+        },
+        ref scalar => Err(format!("can't go through `{}` with `{}`", scalar, token)),
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::PushableAt;
+    use serde_json::Value;
+
+    #[test]
+    fn PushableAt_test_value() {
+        let mut x = Value::Null;
+        x.push_at("/users/0/name", json!("Boris")).unwrap()
+            .push_at("/users/0/roles/-", json!("admin")).unwrap()
+            .push_at("/users/0/roles/-", json!("dev")).unwrap()
+            .push_at("/users/-/name", json!("Bob")).unwrap()
+            .push_at("/a~1b/c~0d", json!(1)).unwrap();
+        assert_eq!(x, json!({
+            "a/b": { "c~d": 1 },
+            "users": [{ "name": "Boris", "roles": ["admin", "dev"] }, { "name": "Bob" }]
+        }));
+
+        let mut x = json!({ "tags": "a" });
+        x.push_at("/tags", json!("b")).unwrap();
+        assert_eq!(x, json!({ "tags": ["a", "b"] }));
+
+        let mut x = json!("a");
+        x.push_at("", json!("b")).unwrap();
+        assert_eq!(x, json!(["a", "b"]));
+    }
+
+    #[test]
+    fn PushableAt_test_errors() {
+        let mut x = json!({ "name": "Boris", "list": [] });
+        assert!(x.push_at("name", json!(1)).is_err());
+        assert!(x.push_at("/name/first", json!(1)).is_err());
+        assert!(x.push_at("/list/3", json!(1)).is_err());
+        assert!(x.push_at("/list/x", json!(1)).is_err());
+        assert_eq!(x, json!({ "name": "Boris", "list": [] }));
+    }
+}
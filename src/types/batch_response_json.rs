@@ -0,0 +1,228 @@
+//! File holding the BatchResponseJSON type aggregating the ResponseJSON of a bulk operation and associated tests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.1
+//!
+//! ## Release notes
+//! - v1.1 : `to_response_json()` returns the error of an item failing to serialize instead of panicking
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::iter::FromIterator;
+
+use rocket::Response;
+use rocket::http::Status;
+use rocket::response::Responder;
+use serde::{ Serialize, Serializer };
+use serde::ser::Error as SerError;
+use serde_json;
+use serde_json::Value;
+
+use error::GenericError;
+use traits::Pushable;
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// The results of a batch of operations, one ResponseJSON per operation (in order).
+///
+/// The overall status is:
+/// - `200 OK` if all operations succeeded (or the batch is empty)
+/// - `207 Multi-Status` if some operations failed
+/// - `500 Internal Server Error` if all operations failed
+///
+/// It is rendered as a ResponseJSON envelope with the overall status and
+/// `data` set to `{ "succeeded": <count>, "failed": <count>, "items": [<ResponseJSON>, ...] }`
+///
+/// ```rust,ignore
+/// #[post("/users/bulk", data = "<users>")]
+/// fn bulk_create(users: JSON<Vec<User>>) -> BatchResponseJSON {
+///     users.0.into_iter().map(|user| create(user).into()).collect()
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct BatchResponseJSON<T = Value> {
+    items: Vec<ResponseJSON<T>>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl<T> BatchResponseJSON<T> {
+    /// Create an empty batch
+    pub fn new() -> BatchResponseJSON<T> {
+        BatchResponseJSON { items: Vec::new() }
+    }
+
+    /// Returns the results of the operations
+    pub fn items(&self) -> &[ResponseJSON<T>] {
+        &self.items
+    }
+
+    /// Returns the number of operations in the batch
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the batch holds no operation
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the number of successful operations
+    pub fn succeeded(&self) -> usize {
+        self.items.iter().filter(|item| item.success).count()
+    }
+
+    /// Returns the number of failed operations
+    pub fn failed(&self) -> usize {
+        self.len() - self.succeeded()
+    }
+
+    /// Returns the overall HTTP code of the batch (200, 207 or 500)
+    pub fn http_code(&self) -> u16 {
+        match (self.succeeded(), self.failed()) {
+            (_, 0) => 200,
+            (0, _) => 500,
+            _ => 207,
+        }
+    }
+}
+
+impl<T: Serialize> BatchResponseJSON<T> {
+    /// Build the ResponseJSON envelope of the batch, fails if an item can't be serialized
+    pub fn to_response_json(&self) -> Result<ResponseJSON, GenericError> {
+        let data = json!({
+            "succeeded": self.succeeded(),
+            "failed": self.failed(),
+            "items": serde_json::to_value(&self.items)?,
+        });
+
+        Ok(match self.http_code() {
+            500 => ResponseJSON::error().message("All batch operations failed".to_string()).data(data),
+            code => ResponseJSON::ok().http_code(code).data(data),
+        })
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl<T> Pushable<ResponseJSON<T>> for BatchResponseJSON<T> {
+    fn push(&mut self, value: ResponseJSON<T>) -> &mut Self {
+        self.items.push(value);
+        self
+    }
+}
+
+impl<T> FromIterator<ResponseJSON<T>> for BatchResponseJSON<T> {
+    fn from_iter<I: IntoIterator<Item = ResponseJSON<T>>>(iter: I) -> Self {
+        BatchResponseJSON { items: iter.into_iter().collect() }
+    }
+}
+
+impl<T> From<Vec<ResponseJSON<T>>> for BatchResponseJSON<T> {
+    fn from(items: Vec<ResponseJSON<T>>) -> Self {
+        BatchResponseJSON { items: items }
+    }
+}
+
+/// Serialize the batch as its ResponseJSON envelope (see `to_response_json()`)
+impl<T: Serialize> Serialize for BatchResponseJSON<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.to_response_json() {
+            Ok(rjson) => rjson.serialize(serializer),
+            Err(err) => Err(S::Error::custom(err)),
+        }
+    }
+}
+
+/// Responds with the ResponseJSON envelope of the batch and its overall HTTP code as HTTP status,
+/// with a `500` error ResponseJSON if an item can't be serialized
+impl<'r, T: Serialize> Responder<'r> for BatchResponseJSON<T> {
+    fn respond(self) -> Result<Response<'r>, Status> {
+        match self.to_response_json() {
+            Ok(rjson) => rjson.respond(),
+            Err(err) => {
+                error!("::AMIWO::TYPES::BATCH_RESPONSE_JSON::RESPOND::ERROR Unable to serialize the batch => {}", err);
+                ResponseJSON::from_error(err).http_code(500).respond()
+            },
+        }
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::collections::BTreeMap;
+
+    use rocket::response::Responder;
+    use serde_json;
+
+    use traits::Pushable;
+    use types::ResponseJSON;
+
+    use super::BatchResponseJSON;
+
+    #[test]
+    fn BatchResponseJSON_test_status() {
+        let batch : BatchResponseJSON = BatchResponseJSON::new();
+        assert_eq!(batch.http_code(), 200);
+
+        let mut batch = BatchResponseJSON::new();
+        batch.push(ResponseJSON::ok().http_code(201)).push(ResponseJSON::ok());
+        assert_eq!(batch.succeeded(), 2);
+        assert_eq!(batch.http_code(), 200);
+
+        batch.push(ResponseJSON::error().http_code(409));
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.failed(), 1);
+        assert_eq!(batch.http_code(), 207);
+
+        let batch : BatchResponseJSON = vec![ResponseJSON::error(), ResponseJSON::error().http_code(404)].into_iter().collect();
+        assert_eq!(batch.http_code(), 500);
+    }
+
+    #[test]
+    fn BatchResponseJSON_test_serialize() {
+        let batch = BatchResponseJSON::from(vec![
+            ResponseJSON::ok().http_code(201).data(json!({ "id": 1 })),
+            ResponseJSON::error().http_code(409).message("duplicate".to_string()),
+        ]);
+        let json = batch.to_response_json().unwrap();
+        assert_eq!(json.success, true);
+        assert_eq!(json.http_code, 207);
+        assert_eq!(json.data["succeeded"], 1);
+        assert_eq!(json.data["failed"], 1);
+        assert_eq!(json.data["items"][1]["message"], "duplicate");
+
+        let json = BatchResponseJSON::from(vec![ResponseJSON::error()]).to_response_json().unwrap();
+        assert_eq!(json.success, false);
+        assert_eq!(json.http_code, 500);
+
+        let mut map = BTreeMap::new();
+        map.insert(vec![1], 1);
+        let batch = BatchResponseJSON::from(vec![ResponseJSON::ok_with_data(map)]);
+        assert!(batch.to_response_json().is_err());
+        assert!(serde_json::to_string(&batch).is_err());
+        assert_eq!(batch.respond().unwrap().status().code, 500);
+    }
+
+    #[test]
+    fn BatchResponseJSON_test_responder() {
+        let batch = BatchResponseJSON::from(vec![ResponseJSON::ok(), ResponseJSON::error()]);
+        let mut response = batch.respond().unwrap();
+        assert_eq!(response.status().code, 207);
+
+        let body_str = response.body().and_then(|b| b.into_string()).unwrap();
+        let json = ResponseJSON::from_str(&body_str).unwrap();
+        assert_eq!(json.data["items"].as_array().map(|items| items.len()), Some(2));
+    }
+}
@@ -0,0 +1,163 @@
+//! File holding the JsonDiff type reporting the differences between two ResponseJSON and associated tests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::fmt;
+
+use serde::Serialize;
+use serde_json;
+use serde_json::Value;
+
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// One difference between two JSON documents, `path` being the JSON Pointer of the value (`""` for the root)
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonDiff {
+    /// `value` only exists in the other document
+    Added { path: String, value: Value },
+    /// `value` only exists in this document
+    Removed { path: String, value: Value },
+    /// The value at `path` is `from` in this document and `to` in the other one
+    Changed { path: String, from: Value, to: Value },
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl JsonDiff {
+    /// Returns the JSON Pointer of the value that differs
+    pub fn path(&self) -> &str {
+        match *self {
+            JsonDiff::Added { ref path, .. } |
+            JsonDiff::Removed { ref path, .. } |
+            JsonDiff::Changed { ref path, .. } => path,
+        }
+    }
+
+    /// Returns the differences between `from` & `to` (key order is ignored, arrays are compared item by item)
+    pub fn between(from: &Value, to: &Value) -> Vec<JsonDiff> {
+        let mut diffs = Vec::new();
+        diff_values(from, to, "", &mut diffs);
+        diffs
+    }
+}
+
+impl<T: Serialize> ResponseJSON<T> {
+    /// Returns the differences between this ResponseJSON and `other` (envelope fields included), e.g. to report
+    /// precisely why a response doesn't match the expected one in contract tests.
+    /// An empty list means both serialize to the same JSON
+    ///
+    /// ```rust,ignore
+    /// let diffs = response.diff(&expected);
+    /// assert!(diffs.is_empty(), "unexpected response: {:#?}", diffs);
+    /// ```
+    pub fn diff<U: Serialize>(&self, other: &ResponseJSON<U>) -> Vec<JsonDiff> {
+        JsonDiff::between(
+            &serde_json::to_value(self).unwrap_or(Value::Null),
+            &serde_json::to_value(other).unwrap_or(Value::Null)
+        )
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+fn diff_values(from: &Value, to: &Value, path: &str, diffs: &mut Vec<JsonDiff>) {
+    match (from, to) {
+        (&Value::Object(ref from_map), &Value::Object(ref to_map)) => {
+            for (key, from_value) in from_map.iter() {
+                let key_path = format!("{}/{}", path, key.replace("~", "~0").replace("/", "~1"));
+                match to_map.get(key) {
+                    Some(to_value) => diff_values(from_value, to_value, &key_path, diffs),
+                    None => diffs.push(JsonDiff::Removed { path: key_path, value: from_value.clone() }),
+                }
+            }
+            for (key, to_value) in to_map.iter().filter(|&(key, _)| !from_map.contains_key(key)) {
+                let key_path = format!("{}/{}", path, key.replace("~", "~0").replace("/", "~1"));
+                diffs.push(JsonDiff::Added { path: key_path, value: to_value.clone() });
+            }
+        },
+        (&Value::Array(ref from_vect), &Value::Array(ref to_vect)) => {
+            for (idx, from_value) in from_vect.iter().enumerate() {
+                let idx_path = format!("{}/{}", path, idx);
+                match to_vect.get(idx) {
+                    Some(to_value) => diff_values(from_value, to_value, &idx_path, diffs),
+                    None => diffs.push(JsonDiff::Removed { path: idx_path, value: from_value.clone() }),
+                }
+            }
+            for (idx, to_value) in to_vect.iter().enumerate().skip(from_vect.len()) {
+                diffs.push(JsonDiff::Added { path: format!("{}/{}", path, idx), value: to_value.clone() });
+            }
+        },
+        _ => if from != to {
+            diffs.push(JsonDiff::Changed { path: path.to_string(), from: from.clone(), to: to.clone() });
+        },
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl fmt::Display for JsonDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonDiff::Added { ref path, ref value } => write!(f, "+ {}: {}", path, value),
+            JsonDiff::Removed { ref path, ref value } => write!(f, "- {}: {}", path, value),
+            JsonDiff::Changed { ref path, ref from, ref to } => write!(f, "~ {}: {} => {}", path, from, to),
+        }
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use types::ResponseJSON;
+
+    use super::JsonDiff;
+
+    #[test]
+    fn JsonDiff_test_identical() {
+        let json = ResponseJSON::ok().data(json!({ "id": 17, "tags": ["a", "b"] }));
+        assert!(json.diff(&json.clone()).is_empty());
+        assert!(ResponseJSON::ok().diff(&ResponseJSON::ok()).is_empty());
+        assert!(ResponseJSON::ok_with_data(vec![1, 2]).diff(&ResponseJSON::ok().data(json!([1, 2]))).is_empty());
+    }
+
+    #[test]
+    fn JsonDiff_test_diff() {
+        let actual = ResponseJSON::ok().data(json!({ "id": 17, "name": "Boris", "tags": ["a", "b"] }));
+        let expected = ResponseJSON::ok().http_code(201).data(json!({ "id": 18, "tags": ["a"], "address": { "city": "Cotonou" } }));
+
+        let diffs = actual.diff(&expected);
+        assert_eq!(diffs, vec![
+            JsonDiff::Changed { path: "/data/id".to_string(), from: json!(17), to: json!(18) },
+            JsonDiff::Removed { path: "/data/name".to_string(), value: json!("Boris") },
+            JsonDiff::Removed { path: "/data/tags/1".to_string(), value: json!("b") },
+            JsonDiff::Added { path: "/data/address".to_string(), value: json!({ "city": "Cotonou" }) },
+            JsonDiff::Changed { path: "/http_code".to_string(), from: json!(200), to: json!(201) },
+        ]);
+        assert_eq!(diffs[0].path(), "/data/id");
+        assert_eq!(diffs[0].to_string(), "~ /data/id: 17 => 18");
+        assert_eq!(diffs[3].to_string(), r#"+ /data/address: {"city":"Cotonou"}"#);
+    }
+
+    #[test]
+    fn JsonDiff_test_between() {
+        assert_eq!(JsonDiff::between(&json!(1), &json!("1")), vec![JsonDiff::Changed { path: "".to_string(), from: json!(1), to: json!("1") }]);
+        assert_eq!(JsonDiff::between(&json!({ "a/b": 1 }), &json!({})), vec![JsonDiff::Removed { path: "/a~1b".to_string(), value: json!(1) }]);
+    }
+}
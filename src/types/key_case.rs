@@ -0,0 +1,179 @@
+//! File holding the KeyCase type used to rewrite the keys of a ResponseJSON `data` and associated tests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use serde::Serialize;
+use serde_json;
+use serde_json::Value;
+
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Naming convention of object keys
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyCase {
+    /// `user_name`
+    Snake,
+    /// `userName`
+    Camel,
+    /// `UserName`
+    Pascal,
+    /// `user-name`
+    Kebab,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl KeyCase {
+    /// Convert `key` (in any of the supported cases) to this case.
+    /// Leading underscores (e.g. `_id`) are kept as is
+    pub fn convert(&self, key: &str) -> String {
+        let trimmed = key.trim_left_matches('_');
+        let prefix = &key[..key.len() - trimmed.len()];
+        let words = split_words(trimmed);
+
+        let converted = match *self {
+            KeyCase::Snake => words.join("_"),
+            KeyCase::Kebab => words.join("-"),
+            KeyCase::Camel => words.iter()
+                .enumerate()
+                .map(|(idx, word)| if idx == 0 { word.clone() } else { capitalize(word) })
+                .collect(),
+            KeyCase::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+        };
+        format!("{}{}", prefix, converted)
+    }
+
+    /// Recursively rewrite all the object keys of `value` to this case
+    pub fn rewrite_keys(&self, value: Value) -> Value {
+        match value {
+            Value::Array(vect) => Value::Array(vect.into_iter().map(|item| self.rewrite_keys(item)).collect()),
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, item)| (self.convert(&key), self.rewrite_keys(item)))
+                    .collect()
+            ),
+            scalar @ _ => scalar,
+        }
+    }
+}
+
+impl<T: Serialize> ResponseJSON<T> {
+    /// Serialize `data`, recursively rewriting all its object keys to `case` (e.g. snake_case Rust structs to a camelCase JS frontend).
+    /// The envelope fields are left untouched
+    ///
+    /// ```rust,ignore
+    /// ResponseJSON::ok().with_data(user).with_key_case(KeyCase::Camel)
+    /// ```
+    pub fn with_key_case(self, case: KeyCase) -> ResponseJSON {
+        self.map_data(|data| {
+            serde_json::to_value(data)
+                .map(|value| case.rewrite_keys(value))
+                .unwrap_or_else(|serde_err| {
+                    error!("::AMIWO::TYPES::KEYCASE::WITH_KEY_CASE::ERROR Unable to serialize data: {}", serde_err);
+                    Value::Null
+                })
+        })
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Split `key` into lowercase words, on `_` / `-` / ` ` separators and on case changes (`userName`, `HTTPCode`)
+fn split_words(key: &str) -> Vec<String> {
+    let chars : Vec<char> = key.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for (idx, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c == ' ' {
+            if !current.is_empty() {
+                words.push(current.clone());
+                current.clear();
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[idx - 1];
+            let next_is_lower = chars.get(idx + 1).map_or(false, |next| next.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                words.push(current.clone());
+                current.clear();
+            }
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+// Uppercase the first letter of `word`
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use types::ResponseJSON;
+
+    use super::KeyCase;
+
+    #[test]
+    fn KeyCase_test_convert() {
+        assert_eq!(KeyCase::Camel.convert("user_name"), "userName");
+        assert_eq!(KeyCase::Camel.convert("UserName"), "userName");
+        assert_eq!(KeyCase::Pascal.convert("user-name"), "UserName");
+        assert_eq!(KeyCase::Snake.convert("userName"), "user_name");
+        assert_eq!(KeyCase::Snake.convert("HTTPCode"), "http_code");
+        assert_eq!(KeyCase::Snake.convert("address2City"), "address2_city");
+        assert_eq!(KeyCase::Kebab.convert("created_at"), "created-at");
+        assert_eq!(KeyCase::Camel.convert("_id"), "_id");
+        assert_eq!(KeyCase::Camel.convert("__meta_data"), "__metaData");
+        assert_eq!(KeyCase::Snake.convert("id"), "id");
+    }
+
+    #[test]
+    fn KeyCase_test_with_key_case() {
+        #[derive(Serialize)]
+        struct User {
+            user_id: u32,
+            first_name: String,
+            home_address: Address,
+        }
+        #[derive(Serialize)]
+        struct Address {
+            zip_code: String,
+        }
+
+        let json = ResponseJSON::ok()
+            .request_id("abc".to_string())
+            .with_data(vec![User { user_id: 17, first_name: "Boris".to_string(), home_address: Address { zip_code: "01 BP".to_string() } }])
+            .with_key_case(KeyCase::Camel);
+        assert_eq!(json.data, json!([{ "userId": 17, "firstName": "Boris", "homeAddress": { "zipCode": "01 BP" } }]));
+        assert_eq!(json.to_string(), r#"{"data":[{"firstName":"Boris","homeAddress":{"zipCode":"01 BP"},"userId":17}],"http_code":200,"request_id":"abc","success":true}"#);
+    }
+}
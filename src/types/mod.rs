@@ -1,6 +1,20 @@
+mod batch_response_json;
+mod json_diff;
+mod key_case;
+mod null_probe;
 mod one_or_many;
+mod problem_details;
+mod raw_envelope;
 mod response_json;
+mod streamed_response_json;
 
-pub use self::one_or_many::OneOrMany;
+pub use self::batch_response_json::BatchResponseJSON;
+pub use self::json_diff::JsonDiff;
+pub use self::key_case::KeyCase;
+pub use self::one_or_many::{ OneOrMany, OneOrManyIter, OneOrManyIterMut };
+pub use self::problem_details::ProblemDetails;
+pub use self::response_json::FieldError;
 pub use self::response_json::IsResponseJSON;
-pub use self::response_json::ResponseJSON;
\ No newline at end of file
+pub use self::response_json::ParseMode;
+pub use self::response_json::ResponseJSON;
+pub use self::streamed_response_json::StreamedResponseJSON;
\ No newline at end of file
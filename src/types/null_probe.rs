@@ -0,0 +1,133 @@
+//! File holding the NullProbe serializer used to test if a value serializes to `null` without serializing it
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::error;
+use std::fmt;
+
+use serde::ser;
+use serde::ser::{ Impossible, Serialize, Serializer };
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Test if `value` serializes to `null` (i.e. `()`, `None`, unit structs...).
+/// Stops at the first serializer call, so the value is never actually serialized
+pub fn is_null<T: Serialize + ?Sized>(value: &T) -> bool {
+    value.serialize(NullProbe).unwrap_or(false)
+}
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+struct NullProbe;
+
+// Returned by the NullProbe as soon as the value is known not to be `null` (compound values)
+#[derive(Debug)]
+struct NotNull;
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl Serializer for NullProbe {
+    type Ok = bool;
+    type Error = NotNull;
+    type SerializeSeq = Impossible<bool, NotNull>;
+    type SerializeTuple = Impossible<bool, NotNull>;
+    type SerializeTupleStruct = Impossible<bool, NotNull>;
+    type SerializeTupleVariant = Impossible<bool, NotNull>;
+    type SerializeMap = Impossible<bool, NotNull>;
+    type SerializeStruct = Impossible<bool, NotNull>;
+    type SerializeStructVariant = Impossible<bool, NotNull>;
+
+    fn serialize_bool(self, _: bool) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_i8(self, _: i8) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_i16(self, _: i16) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_i32(self, _: i32) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_i64(self, _: i64) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_u8(self, _: u8) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_u16(self, _: u16) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_u32(self, _: u32) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_u64(self, _: u64) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_f32(self, _: f32) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_f64(self, _: f64) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_char(self, _: char) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_str(self, _: &str) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_bytes(self, _: &[u8]) -> Result<bool, NotNull> { Ok(false) }
+    fn serialize_none(self) -> Result<bool, NotNull> { Ok(true) }
+    fn serialize_unit(self) -> Result<bool, NotNull> { Ok(true) }
+    fn serialize_unit_struct(self, _: &'static str) -> Result<bool, NotNull> { Ok(true) }
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<bool, NotNull> { Ok(false) }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<bool, NotNull> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<bool, NotNull> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _: &'static str, _: u32, _: &'static str, _: &T) -> Result<bool, NotNull> {
+        Ok(false)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, NotNull> { Err(NotNull) }
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, NotNull> { Err(NotNull) }
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeTupleStruct, NotNull> { Err(NotNull) }
+    fn serialize_tuple_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeTupleVariant, NotNull> { Err(NotNull) }
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, NotNull> { Err(NotNull) }
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, NotNull> { Err(NotNull) }
+    fn serialize_struct_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeStructVariant, NotNull> { Err(NotNull) }
+}
+
+impl fmt::Display for NotNull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value is not null")
+    }
+}
+
+impl error::Error for NotNull {
+    fn description(&self) -> &str {
+        "value is not null"
+    }
+}
+
+impl ser::Error for NotNull {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        NotNull
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use serde_json::Value;
+
+    use super::is_null;
+
+    #[test]
+    fn is_null_test() {
+        assert_eq!(is_null(&()), true);
+        assert_eq!(is_null(&Value::Null), true);
+        assert_eq!(is_null(&None::<u32>), true);
+        assert_eq!(is_null(&Some(Value::Null)), true);
+
+        assert_eq!(is_null(&0), false);
+        assert_eq!(is_null(&""), false);
+        assert_eq!(is_null(&Some(1)), false);
+        assert_eq!(is_null(&json!([])), false);
+        assert_eq!(is_null(&json!({ "a": null })), false);
+        assert_eq!(is_null(&vec![1, 2]), false);
+    }
+}
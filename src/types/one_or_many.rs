@@ -1,35 +1,117 @@
 //! File holding the OneOrMany type and associated tests
 //!
 //! Author: [Boris](mailto:boris@humanenginuity.com)
-//! Version: 1.0
+//! Version: 1.17
 //!
 //! ## Release notes
+//! - v1.17 : removed `with_capacity()` & `reserve()`, a promoted `One` is allocated by `vec![]` again
+//! - v1.16 : `reserve()` leaves a `One` unchanged
+//! - v1.15 : implemented `Poppable`
+//! - v1.14 : implemented `PushableExt` (`push_unique()` & `push_front()`)
+//! - v1.13 : implemented `Display`, added `join()`
+//! - v1.12 : added `with_capacity()` & `reserve()`, promoting a `One` allocates room for 4 values
+//! - v1.11 : added the `Option` helpers `from_option()`, `from_opt_vec()`, `into_option()` & `value_or_default()`, an absent value being an empty `Many`
+//! - v1.10 : added `dedup()`, `sort()`, `retain()` & `collapse()`
+//! - v1.9 : added `OneOrMany::<Value>::from_value()` & `into_json_value()`
+//! - v1.8 : implemented Rocket's `FromFormValue` (comma separated values)
+//! - v1.7 : implemented `From<T>` (`One`) & `From<Vec<T>>` (`Many`)
+//! - v1.6 : derived `Clone`, `Eq` & `Hash`, implemented `Default` (an empty `Many`)
+//! - v1.5 : added `len()`, `is_empty()`, `contains()` & `as_slice()`
+//! - v1.4 : added `push()` (not requiring `T: Clone` anymore), implemented `Extend` & `FromIterator`
+//! - v1.3 : added the `map()`, `try_map()`, `filter()` & `and_then()` combinators
+//! - v1.2 : added borrowing iterators `iter()` & `iter_mut()`, implemented `IntoIterator` for `&OneOrMany` & `&mut OneOrMany`
+//! - v1.1 : implemented `Deserialize`, (de)serializing untagged (a single value or an array)
 //! - v1.0 : creation
 
 // =======================================================================
 // LIBRARY IMPORTS
 // =======================================================================
-use std::clone::Clone;
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
 use std::ops::{ Index, IndexMut };
+use std::slice;
 
-use serde::{ Serialize, Serializer };
+use rocket::request::FromFormValue;
 
-use traits::Pushable;
+use serde::{ Deserialize, Deserializer, Serialize, Serializer };
+use serde_json::Value;
+
+use traits::{ Poppable, Pushable, PushableExt };
 
 // =======================================================================
 // STRUCT & TRAIT DEFINITION
 // =======================================================================
 /// Type to encapsulate 'one or many' values
-#[derive(Debug, PartialEq)] 
+///
+/// `One(a)` & `Many(vec![a])` hold the same value but aren't equal (nor hash the same), compare `as_slice()` to ignore the shape
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum OneOrMany<T> {
     One(T),
     Many(Vec<T>),
 }
 
+/// Iterator over the references to the values of a OneOrMany (see `OneOrMany::iter()`)
+#[derive(Debug)]
+pub struct OneOrManyIter<'a, T: 'a> {
+    inner: IterInner<slice::Iter<'a, T>, &'a T>,
+}
+
+/// Iterator over the mutable references to the values of a OneOrMany (see `OneOrMany::iter_mut()`)
+#[derive(Debug)]
+pub struct OneOrManyIterMut<'a, T: 'a> {
+    inner: IterInner<slice::IterMut<'a, T>, &'a mut T>,
+}
+
+// State of the iterators: the single value (until consumed) or the iterator over the vector
+#[derive(Debug)]
+enum IterInner<I, R> {
+    One(Option<R>),
+    Many(I),
+}
+
+// Untagged representation used to deserialize OneOrMany. `Many` is tried first so that an array isn't
+// deserialized as `One` when `T` accepts arrays (e.g. `OneOrMany<Value>`)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UntaggedOneOrMany<T> {
+    Many(Vec<T>),
+    One(T),
+}
+
 // =======================================================================
 // STRUCT & TRAIT IMPLEMENTATION
 // =======================================================================
 impl<T> OneOrMany<T> {
+    /// `One` for `Some(value)`, an empty `Many` for `None`
+    pub fn from_option(value: Option<T>) -> OneOrMany<T> {
+        match value {
+            Some(value) => OneOrMany::One(value),
+            None => OneOrMany::Many(Vec::new()),
+        }
+    }
+
+    /// Collect the optional values (see `FromIterator`): `One` for a single value, `Many` otherwise, an empty `Many` for `None`
+    ///
+    /// ```rust,ignore
+    /// let tags = OneOrMany::from_opt_vec(params.tags); // instead of matching an `Option<OneOrMany<String>>`
+    /// ```
+    pub fn from_opt_vec(values: Option<Vec<T>>) -> OneOrMany<T> {
+        values.map_or(OneOrMany::Many(Vec::new()), |values| values.into_iter().collect())
+    }
+
+    /// `None` if there is no value (empty `Many`), `Some(self)` otherwise
+    pub fn into_option(self) -> Option<OneOrMany<T>> {
+        if self.is_empty() { None } else { Some(self) }
+    }
+
+    /// Consume `self` and return the first value (see `into_value()`), `T::default()` if there is none
+    pub fn value_or_default(self) -> T
+        where T: Default
+    {
+        self.into_value().unwrap_or_else(T::default)
+    }
+
     /// Return a reference to value (if is OneOrMany::One) or the first value of the vector (if is OneOrMany::Many)
     pub fn value<'v>(&'v self) -> Option<&'v T> {
         match *self {
@@ -92,6 +174,180 @@ impl<T> OneOrMany<T> {
         }
     }
 
+    /// Returns an iterator over the references to the value (One) or the values (Many)
+    pub fn iter<'v>(&'v self) -> OneOrManyIter<'v, T> {
+        OneOrManyIter {
+            inner: match *self {
+                OneOrMany::One(ref val) => IterInner::One(Some(val)),
+                OneOrMany::Many(ref vect) => IterInner::Many(vect.iter()),
+            }
+        }
+    }
+
+    /// Returns an iterator over the mutable references to the value (One) or the values (Many)
+    pub fn iter_mut<'v>(&'v mut self) -> OneOrManyIterMut<'v, T> {
+        OneOrManyIterMut {
+            inner: match *self {
+                OneOrMany::One(ref mut val) => IterInner::One(Some(val)),
+                OneOrMany::Many(ref mut vect) => IterInner::Many(vect.iter_mut()),
+            }
+        }
+    }
+
+    /// Convert the value(s) with `f`, keeping the One / Many shape
+    ///
+    /// ```rust,ignore
+    /// assert_eq!(OneOrMany::One("2").map(|val| val.len()), OneOrMany::One(1));
+    /// ```
+    pub fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> OneOrMany<U> {
+        match self {
+            OneOrMany::One(val) => OneOrMany::One(f(val)),
+            OneOrMany::Many(vect) => OneOrMany::Many(vect.into_iter().map(f).collect()),
+        }
+    }
+
+    /// Convert the value(s) with `f`, keeping the One / Many shape. Stops at the first error & returns it
+    ///
+    /// ```rust,ignore
+    /// let ids : Result<OneOrMany<u32>, _> = params.try_map(|id| id.parse::<u32>());
+    /// ```
+    pub fn try_map<U, E, F: FnMut(T) -> Result<U, E>>(self, mut f: F) -> Result<OneOrMany<U>, E> {
+        match self {
+            OneOrMany::One(val) => f(val).map(OneOrMany::One),
+            OneOrMany::Many(vect) => vect.into_iter().map(f).collect::<Result<Vec<U>, E>>().map(OneOrMany::Many),
+        }
+    }
+
+    /// Keep the value(s) for which `predicate` returns `true`. A `Many` stays a `Many` (possibly empty),
+    /// a filtered out `One` becomes an empty `Many`
+    pub fn filter<P: FnMut(&T) -> bool>(self, mut predicate: P) -> OneOrMany<T> {
+        match self {
+            OneOrMany::One(val) => if predicate(&val) { OneOrMany::One(val) } else { OneOrMany::Many(Vec::new()) },
+            OneOrMany::Many(vect) => OneOrMany::Many(vect.into_iter().filter(|val| predicate(val)).collect()),
+        }
+    }
+
+    /// Replace the value(s) by the OneOrMany returned by `f`: the result of `f` for a `One`,
+    /// the concatenation of the values returned by `f` for a `Many`
+    pub fn and_then<U, F: FnMut(T) -> OneOrMany<U>>(self, mut f: F) -> OneOrMany<U> {
+        match self {
+            OneOrMany::One(val) => f(val),
+            OneOrMany::Many(vect) => OneOrMany::Many(vect.into_iter().flat_map(|val| f(val).into_values()).collect()),
+        }
+    }
+
+    /// Append `value`, converting a `One(a)` into `Many(vec![a, value])`
+    pub fn push(&mut self, value: T) -> &mut Self {
+        let vect = match mem::replace(self, OneOrMany::Many(Vec::new())) {
+            OneOrMany::One(val) => vec![val, value],
+            OneOrMany::Many(mut vect) => {
+                vect.push(value);
+                vect
+            },
+        };
+        *self = OneOrMany::Many(vect);
+        self
+    }
+
+    /// Returns the number of values, 1 for a `One`
+    pub fn len(&self) -> usize {
+        match *self {
+            OneOrMany::One(_) => 1,
+            OneOrMany::Many(ref vect) => vect.len(),
+        }
+    }
+
+    /// Returns `true` if `self` has no value (i.e. is an empty `Many`)
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if one of the values is equal to `value`
+    pub fn contains(&self, value: &T) -> bool
+        where T: PartialEq
+    {
+        self.as_slice().contains(value)
+    }
+
+    /// Returns the value(s) as a slice, of length 1 for a `One`
+    pub fn as_slice(&self) -> &[T] {
+        match *self {
+            // same as the (unstable) `slice::from_ref()`: a reference is a valid slice of length 1
+            OneOrMany::One(ref val) => unsafe { slice::from_raw_parts(val, 1) },
+            OneOrMany::Many(ref vect) => vect.as_slice(),
+        }
+    }
+
+    /// Remove the duplicated values (not only the consecutive ones like `Vec::dedup()`), keeping the first occurrence
+    pub fn dedup(&mut self) -> &mut Self
+        where T: PartialEq
+    {
+        if let OneOrMany::Many(ref mut vect) = *self {
+            let mut idx = 1;
+            while idx < vect.len() {
+                if vect[..idx].contains(&vect[idx]) {
+                    vect.remove(idx);
+                } else {
+                    idx += 1;
+                }
+            }
+        }
+        self
+    }
+
+    /// Sort the values of a `Many`
+    pub fn sort(&mut self) -> &mut Self
+        where T: Ord
+    {
+        if let OneOrMany::Many(ref mut vect) = *self {
+            vect.sort();
+        }
+        self
+    }
+
+    /// Keep the values for which `predicate` returns `true` (see `filter()`), a removed `One` becoming an empty `Many`
+    pub fn retain<P: FnMut(&T) -> bool>(&mut self, mut predicate: P) -> &mut Self {
+        let keep_one = match *self {
+            OneOrMany::One(ref val) => predicate(val),
+            OneOrMany::Many(ref mut vect) => {
+                vect.retain(|val| predicate(val));
+                true
+            },
+        };
+        if !keep_one {
+            *self = OneOrMany::Many(Vec::new());
+        }
+        self
+    }
+
+    /// Convert a `Many` holding a single value into a `One`, e.g. after `dedup()` or `retain()`
+    ///
+    /// ```rust,ignore
+    /// tags.dedup().sort().collapse(); // ?tags=b&tags=a&tags=b => Many(["a", "b"]), ?tags=a&tags=a => One("a")
+    /// ```
+    pub fn collapse(&mut self) -> &mut Self {
+        let single = match *self {
+            OneOrMany::Many(ref mut vect) if vect.len() == 1 => vect.pop(),
+            _ => None,
+        };
+        if let Some(val) = single {
+            *self = OneOrMany::One(val);
+        }
+        self
+    }
+
+    /// Returns the values separated by `separator`, e.g. to build a header (`X-Tags: a,b,c`) with `join(",")`
+    pub fn join(&self, separator: &str) -> String
+        where T: fmt::Display
+    {
+        let mut joined = String::new();
+        for (idx, val) in self.iter().enumerate() {
+            if idx > 0 { joined.push_str(separator); }
+            joined.push_str(&val.to_string());
+        }
+        joined
+    }
+
     /// Returns `true` if `self` is OneOrMany::One
     pub fn is_one(&self) -> bool {
         match *self {
@@ -109,25 +365,129 @@ impl<T> OneOrMany<T> {
     }
 }
 
+impl OneOrMany<Value> {
+    /// Convert a JSON value, e.g. built by `Pushable`: an array into a `Many` of its items, any other value into a `One`.
+    /// Unlike `OneOrMany::from(value)` which always returns a `One`
+    pub fn from_value(value: Value) -> OneOrMany<Value> {
+        match value {
+            Value::Array(vect) => OneOrMany::Many(vect),
+            value => OneOrMany::One(value),
+        }
+    }
+
+    /// Convert back into a JSON value: the value of a `One`, an array for a `Many`
+    /// (`into_value()` returns the first value instead)
+    pub fn into_json_value(self) -> Value {
+        match self {
+            OneOrMany::One(value) => value,
+            OneOrMany::Many(vect) => Value::Array(vect),
+        }
+    }
+}
+
+/// An empty `Many`, i.e. no value (doesn't require `T: Default`)
+impl<T> Default for OneOrMany<T> {
+    fn default() -> Self {
+        OneOrMany::Many(Vec::new())
+    }
+}
+
+/// Wrap a single value into a `One`, so that APIs taking an `Into<OneOrMany<T>>` accept a `T`
+impl<T> From<T> for OneOrMany<T> {
+    fn from(value: T) -> Self {
+        OneOrMany::One(value)
+    }
+}
+
+/// Wrap a vector into a `Many` (whatever its length), so that APIs taking an `Into<OneOrMany<T>>` accept a `Vec<T>`
+impl<T> From<Vec<T>> for OneOrMany<T> {
+    fn from(vect: Vec<T>) -> Self {
+        OneOrMany::Many(vect)
+    }
+}
+
+/// Parse a form value of a Rocket `FromForm` struct or a route segment, e.g. `tags: OneOrMany<String>`:
+/// a value without comma is a `One`, a comma separated list (`tags=a,b`) a `Many`, each part being parsed by `T`.
+/// Encode the commas which are part of a value (`%2C`). Returns the error of the first part `T` fails to parse
+///
+/// Rocket 0.2 keeps only the last occurrence of a repeated key (`?tags=a&tags=b`), use `FormHashMap::get_one_or_many()` in that case
+impl<'v, T: FromFormValue<'v>> FromFormValue<'v> for OneOrMany<T> {
+    type Error = T::Error;
+
+    fn from_form_value(value: &'v str) -> Result<Self, T::Error> {
+        if value.contains(',') {
+            value.split(',').map(T::from_form_value).collect::<Result<Vec<T>, T::Error>>().map(OneOrMany::Many)
+        } else {
+            T::from_form_value(value).map(OneOrMany::One)
+        }
+    }
+}
+
 /// Allow to push a new value into a mutable reference of OneOrMany
 ///
 /// - If `self` was OneOrMany::One => converts it to OneOrMany::Many and appends the new value
 /// - If `self` was OneOrMany::Many => appends the new value
-impl<T> Pushable<T> for OneOrMany<T> 
-    where T: Clone
-{
+impl<T> Pushable<T> for OneOrMany<T> {
     fn push(&mut self, new_value: T) -> &mut Self {
-        let mut vect : Vec<T> = Vec::new();
+        OneOrMany::push(self, new_value)
+    }
+}
 
-        if self.is_one() {
-            vect.push(self.value().unwrap().clone());
-        } else {
-            vect.append(self.as_many_mut().unwrap());
+/// A `One` becomes a `Many` when a value is pushed, unless `push_unique()` finds it equal to the existing value
+impl<T: PartialEq> PushableExt<T> for OneOrMany<T> {
+    fn push_unique(&mut self, new_value: T) -> &mut Self {
+        if !self.contains(&new_value) {
+            OneOrMany::push(self, new_value);
         }
-        vect.push(new_value);
-        ::std::mem::replace(self, OneOrMany::Many(vect));
         self
     }
+
+    fn push_front(&mut self, new_value: T) -> &mut Self {
+        let vect = match mem::replace(self, OneOrMany::Many(Vec::new())) {
+            OneOrMany::One(val) => vec![new_value, val],
+            OneOrMany::Many(mut vect) => {
+                vect.insert(0, new_value);
+                vect
+            },
+        };
+        *self = OneOrMany::Many(vect);
+        self
+    }
+}
+
+/// Remove the last value, a `Many` left with a single value becoming a `One` & a `One` becoming an empty `Many`
+impl<T> Poppable<T> for OneOrMany<T> {
+    fn pop(&mut self) -> Option<T> {
+        match mem::replace(self, OneOrMany::Many(Vec::new())) {
+            OneOrMany::One(val) => Some(val),
+            OneOrMany::Many(mut vect) => {
+                let popped = vect.pop();
+                *self = if vect.len() == 1 { OneOrMany::One(vect.pop().unwrap()) } else { OneOrMany::Many(vect) };
+                popped
+            },
+        }
+    }
+}
+
+/// Push every value of the iterator (see `push()`), an empty iterator leaving `self` untouched
+impl<T> Extend<T> for OneOrMany<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+/// Collect the values into a `One` if there is exactly one value, a `Many` otherwise (an empty iterator giving an empty `Many`)
+impl<T> FromIterator<T> for OneOrMany<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vect : Vec<T> = iter.into_iter().collect();
+        if vect.len() == 1 {
+            OneOrMany::One(vect.remove(0))
+        } else {
+            OneOrMany::Many(vect)
+        }
+    }
 }
 
 /// Access an element of this type. Panics if the index is out of bounds
@@ -172,17 +532,118 @@ impl<T> IntoIterator for OneOrMany<T> {
     }
 }
 
+/// Display the value of a `One`, the values of a `Many` separated by `, ` (see `join()`)
+impl<T: fmt::Display> fmt::Display for OneOrMany<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, val) in self.iter().enumerate() {
+            if idx > 0 { f.write_str(", ")?; }
+            write!(f, "{}", val)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a OneOrMany<T> {
+    type Item = &'a T;
+    type IntoIter = OneOrManyIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut OneOrMany<T> {
+    type Item = &'a mut T;
+    type IntoIter = OneOrManyIterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T> Iterator for OneOrManyIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self.inner {
+            IterInner::One(ref mut val) => val.take(),
+            IterInner::Many(ref mut iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.inner {
+            IterInner::One(ref val) => if val.is_some() { (1, Some(1)) } else { (0, Some(0)) },
+            IterInner::Many(ref iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for OneOrManyIter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        match self.inner {
+            IterInner::One(ref mut val) => val.take(),
+            IterInner::Many(ref mut iter) => iter.next_back(),
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for OneOrManyIter<'a, T> {}
+
+impl<'a, T> Iterator for OneOrManyIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        match self.inner {
+            IterInner::One(ref mut val) => val.take(),
+            IterInner::Many(ref mut iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.inner {
+            IterInner::One(ref val) => if val.is_some() { (1, Some(1)) } else { (0, Some(0)) },
+            IterInner::Many(ref iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for OneOrManyIterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        match self.inner {
+            IterInner::One(ref mut val) => val.take(),
+            IterInner::Many(ref mut iter) => iter.next_back(),
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for OneOrManyIterMut<'a, T> {}
+
+/// Serialize as the value itself (`One`) or as an array (`Many`), e.g. `"a"` or `["a", "b"]`
 impl<T> Serialize for OneOrMany<T>
     where T: Serialize
 {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         match *self {
-            OneOrMany::One(ref val) => serializer.serialize_newtype_variant("OneOrMany", 0, "One", val),
-            OneOrMany::Many(ref vec) => serializer.serialize_newtype_variant("OneOrMany", 1, "Many", vec),
+            OneOrMany::One(ref val) => val.serialize(serializer),
+            OneOrMany::Many(ref vec) => vec.serialize(serializer),
         }
     }
 }
 
+/// Deserialize an array as `Many` and any other value as `One`, so that `{"tags": "a"}` and `{"tags": ["a", "b"]}`
+/// both deserialize into a `OneOrMany<String>`
+impl<'de, T> Deserialize<'de> for OneOrMany<T>
+    where T: Deserialize<'de>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        UntaggedOneOrMany::deserialize(deserializer).map(|value| match value {
+            UntaggedOneOrMany::Many(vect) => OneOrMany::Many(vect),
+            UntaggedOneOrMany::One(val) => OneOrMany::One(val),
+        })
+    }
+}
+
 /// Allow to compare a Vector with an instance from OneOrMany
 impl <T: PartialEq<U>, U> PartialEq<Vec<U>> for OneOrMany<T> {
     fn eq(&self, other: &Vec<U>) -> bool {
@@ -267,6 +728,8 @@ mod tests {
     #![allow(non_snake_case)]
 
     use super::OneOrMany;
+    use serde_json;
+    use serde_json::Value;
     use traits::Pushable;
 
     #[test]
@@ -406,4 +869,276 @@ mod tests {
         x.push("c").push("d");
         assert_eq!(x, OneOrMany::Many(vec!["a", "b", "c", "d"]));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn OneOrMany_test_serialize() {
+        assert_eq!(serde_json::to_string(&OneOrMany::One("a")).unwrap(), r#""a""#);
+        assert_eq!(serde_json::to_string(&OneOrMany::Many(vec!["a", "b"])).unwrap(), r#"["a","b"]"#);
+        assert_eq!(serde_json::to_value(&OneOrMany::Many(Vec::<u32>::new())).unwrap(), json!([]));
+    }
+
+    #[test]
+    fn OneOrMany_test_deserialize() {
+        #[derive(Debug, Deserialize)]
+        struct Params {
+            tags: OneOrMany<String>,
+        }
+
+        let params : Params = serde_json::from_str(r#"{ "tags": "a" }"#).unwrap();
+        assert_eq!(params.tags, OneOrMany::One("a".to_string()));
+        let params : Params = serde_json::from_str(r#"{ "tags": ["a", "b"] }"#).unwrap();
+        assert_eq!(params.tags, OneOrMany::Many(vec!["a".to_string(), "b".to_string()]));
+        assert!(serde_json::from_str::<Params>(r#"{ "tags": 1 }"#).is_err());
+        assert!(serde_json::from_str::<Params>(r#"{ "tags": ["a", 1] }"#).is_err());
+
+        let value : OneOrMany<Value> = serde_json::from_str("[1, 2]").unwrap();
+        assert_eq!(value, OneOrMany::Many(vec![json!(1), json!(2)]));
+
+        let roundtrip : OneOrMany<u32> = serde_json::from_value(serde_json::to_value(&OneOrMany::One(17)).unwrap()).unwrap();
+        assert_eq!(roundtrip, OneOrMany::One(17));
+    }
+
+    #[test]
+    fn OneOrMany_test_iter() {
+        let one = OneOrMany::One(17);
+        assert_eq!(one.iter().collect::<Vec<_>>(), vec![&17]);
+        assert_eq!(one.iter().len(), 1);
+
+        let many = OneOrMany::Many(vec![1, 2, 3]);
+        assert_eq!(many.iter().rev().collect::<Vec<_>>(), vec![&3, &2, &1]);
+        let mut sum = 0;
+        for val in &many {
+            sum += *val;
+        }
+        assert_eq!(sum, 6);
+        assert_eq!(OneOrMany::Many(Vec::<u32>::new()).iter().next(), None);
+    }
+
+    #[test]
+    fn OneOrMany_test_iter_mut() {
+        let mut one = OneOrMany::One(17);
+        for val in &mut one {
+            *val += 1;
+        }
+        assert_eq!(one, OneOrMany::One(18));
+
+        let mut many = OneOrMany::Many(vec!["a".to_string(), "b".to_string()]);
+        for val in many.iter_mut() {
+            val.push('!');
+        }
+        assert_eq!(many, OneOrMany::Many(vec!["a!".to_string(), "b!".to_string()]));
+        assert_eq!(many.iter_mut().len(), 2);
+    }
+
+    #[test]
+    fn OneOrMany_test_map() {
+        assert_eq!(OneOrMany::One("ab").map(|val| val.len()), OneOrMany::One(2));
+        assert_eq!(OneOrMany::Many(vec![1, 2]).map(|val| val * 10), OneOrMany::Many(vec![10, 20]));
+
+        assert_eq!(OneOrMany::One("17").try_map(|val| val.parse::<u32>()), Ok(OneOrMany::One(17)));
+        assert_eq!(OneOrMany::Many(vec!["1", "2"]).try_map(|val| val.parse::<u32>()), Ok(OneOrMany::Many(vec![1, 2])));
+        assert!(OneOrMany::Many(vec!["1", "x"]).try_map(|val| val.parse::<u32>()).is_err());
+    }
+
+    #[test]
+    fn OneOrMany_test_filter() {
+        assert_eq!(OneOrMany::One(2).filter(|val| *val > 1), OneOrMany::One(2));
+        assert_eq!(OneOrMany::One(0).filter(|val| *val > 1), OneOrMany::Many(vec![]));
+        assert_eq!(OneOrMany::Many(vec![1, 2, 3]).filter(|val| *val > 1), OneOrMany::Many(vec![2, 3]));
+    }
+
+    #[test]
+    fn OneOrMany_test_and_then() {
+        let split = |val: &'static str| OneOrMany::Many(val.split(',').collect());
+        assert_eq!(OneOrMany::One("a,b").and_then(split), OneOrMany::Many(vec!["a", "b"]));
+        assert_eq!(OneOrMany::Many(vec!["a,b", "c"]).and_then(split), OneOrMany::Many(vec!["a", "b", "c"]));
+        assert_eq!(OneOrMany::One(1).and_then(|val| OneOrMany::One(val + 1)), OneOrMany::One(2));
+    }
+
+    #[test]
+    fn OneOrMany_test_push_no_clone() {
+        #[derive(Debug, PartialEq)]
+        struct NoClone(u32);
+
+        let mut x = OneOrMany::One(NoClone(1));
+        x.push(NoClone(2));
+        assert_eq!(x, OneOrMany::Many(vec![NoClone(1), NoClone(2)]));
+
+        let mut y = OneOrMany::Many(vec![]);
+        Pushable::push(&mut y, NoClone(3));
+        assert_eq!(y, OneOrMany::Many(vec![NoClone(3)]));
+    }
+
+    #[test]
+    fn OneOrMany_test_extend() {
+        let mut x = OneOrMany::One(1);
+        x.extend(vec![]);
+        assert_eq!(x, OneOrMany::One(1));
+        x.extend(vec![2, 3]);
+        assert_eq!(x, OneOrMany::Many(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn OneOrMany_test_from_iter() {
+        assert_eq!(Vec::<u32>::new().into_iter().collect::<OneOrMany<u32>>(), OneOrMany::Many(vec![]));
+        assert_eq!(vec![1].into_iter().collect::<OneOrMany<u32>>(), OneOrMany::One(1));
+        assert_eq!((1..4).collect::<OneOrMany<u32>>(), OneOrMany::Many(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn OneOrMany_test_len() {
+        assert_eq!(OneOrMany::One(1).len(), 1);
+        assert_eq!(OneOrMany::One(1).is_empty(), false);
+        assert_eq!(OneOrMany::Many(vec![1, 2]).len(), 2);
+        assert_eq!(OneOrMany::Many(Vec::<u32>::new()).is_empty(), true);
+    }
+
+    #[test]
+    fn OneOrMany_test_as_slice() {
+        assert_eq!(OneOrMany::One(1).as_slice(), &[1]);
+        assert_eq!(OneOrMany::Many(vec![1, 2]).as_slice(), &[1, 2]);
+        assert_eq!(OneOrMany::One("a").contains(&"a"), true);
+        assert_eq!(OneOrMany::Many(vec!["a", "b"]).contains(&"b"), true);
+        assert_eq!(OneOrMany::Many(vec!["a", "b"]).contains(&"c"), false);
+    }
+
+    #[test]
+    fn OneOrMany_test_derives() {
+        use std::collections::HashSet;
+
+        let x = OneOrMany::Many(vec!["a".to_string()]);
+        assert_eq!(x.clone(), x);
+
+        let mut set = HashSet::new();
+        set.insert(OneOrMany::One(1));
+        set.insert(OneOrMany::One(1));
+        set.insert(OneOrMany::Many(vec![1]));
+        assert_eq!(set.len(), 2);
+
+        struct NoDefault;
+        let empty : OneOrMany<NoDefault> = OneOrMany::default();
+        assert!(empty.is_many() && empty.is_empty());
+    }
+
+    #[test]
+    fn OneOrMany_test_from() {
+        fn tags<T: Into<OneOrMany<&'static str>>>(tags: T) -> OneOrMany<&'static str> {
+            tags.into()
+        }
+
+        assert_eq!(tags("a"), OneOrMany::One("a"));
+        assert_eq!(tags(vec!["a", "b"]), OneOrMany::Many(vec!["a", "b"]));
+        let empty : OneOrMany<u32> = Vec::new().into();
+        assert_eq!(empty, OneOrMany::Many(vec![]));
+    }
+
+    #[test]
+    fn OneOrMany_test_from_form_value() {
+        use rocket::request::FromFormValue;
+
+        assert_eq!(OneOrMany::<String>::from_form_value("a+b"), Ok(OneOrMany::One("a b".to_string())));
+        assert_eq!(OneOrMany::<String>::from_form_value("a,b%2Cc"), Ok(OneOrMany::Many(vec!["a".to_string(), "b,c".to_string()])));
+        assert_eq!(OneOrMany::<u32>::from_form_value("1,2"), Ok(OneOrMany::Many(vec![1, 2])));
+        assert_eq!(OneOrMany::<u32>::from_form_value("1,x"), Err("x"));
+    }
+
+    #[test]
+    fn OneOrMany_test_from_value() {
+        let mut pushed = Value::Null;
+        pushed.push(json!("a"));
+        assert_eq!(OneOrMany::from_value(pushed.clone()), OneOrMany::One(json!("a")));
+        pushed.push(json!("b"));
+        assert_eq!(OneOrMany::from_value(pushed.clone()), OneOrMany::Many(vec![json!("a"), json!("b")]));
+        assert_eq!(OneOrMany::from_value(pushed.clone()).into_json_value(), pushed);
+
+        assert_eq!(OneOrMany::from_value(json!({ "a": 1 })), OneOrMany::One(json!({ "a": 1 })));
+        assert_eq!(OneOrMany::from_value(json!([])).into_json_value(), json!([]));
+    }
+
+    #[test]
+    fn OneOrMany_test_dedup_sort() {
+        let mut tags = OneOrMany::Many(vec!["b", "a", "b", "c", "a"]);
+        tags.dedup();
+        assert_eq!(tags, OneOrMany::Many(vec!["b", "a", "c"]));
+        tags.sort();
+        assert_eq!(tags, OneOrMany::Many(vec!["a", "b", "c"]));
+
+        let mut tags = OneOrMany::Many(vec!["a", "a"]);
+        tags.dedup().sort().collapse();
+        assert_eq!(tags, OneOrMany::One("a"));
+
+        let mut one = OneOrMany::One(1);
+        one.dedup().sort().collapse();
+        assert_eq!(one, OneOrMany::One(1));
+    }
+
+    #[test]
+    fn OneOrMany_test_retain() {
+        let mut x = OneOrMany::Many(vec![1, 2, 3, 4]);
+        x.retain(|val| val % 2 == 0);
+        assert_eq!(x, OneOrMany::Many(vec![2, 4]));
+        x.retain(|val| *val > 2).collapse();
+        assert_eq!(x, OneOrMany::One(4));
+        x.retain(|val| *val > 10);
+        assert_eq!(x, OneOrMany::Many(vec![]));
+        x.collapse();
+        assert_eq!(x, OneOrMany::Many(vec![]));
+    }
+
+    #[test]
+    fn OneOrMany_test_option() {
+        assert_eq!(OneOrMany::from_option(Some(1)), OneOrMany::One(1));
+        assert_eq!(OneOrMany::from_option(None::<u32>), OneOrMany::Many(vec![]));
+
+        assert_eq!(OneOrMany::from_opt_vec(Some(vec![1])), OneOrMany::One(1));
+        assert_eq!(OneOrMany::from_opt_vec(Some(vec![1, 2])), OneOrMany::Many(vec![1, 2]));
+        assert_eq!(OneOrMany::from_opt_vec(None::<Vec<u32>>), OneOrMany::Many(vec![]));
+
+        assert_eq!(OneOrMany::One(1).into_option(), Some(OneOrMany::One(1)));
+        assert_eq!(OneOrMany::Many(Vec::<u32>::new()).into_option(), None);
+
+        assert_eq!(OneOrMany::Many(vec![3, 4]).value_or_default(), 3);
+        assert_eq!(OneOrMany::Many(Vec::<String>::new()).value_or_default(), "");
+    }
+
+    #[test]
+    fn OneOrMany_test_display() {
+        assert_eq!(OneOrMany::One("a").to_string(), "a");
+        assert_eq!(OneOrMany::Many(vec![1, 2, 3]).to_string(), "1, 2, 3");
+        assert_eq!(OneOrMany::Many(Vec::<u32>::new()).to_string(), "");
+
+        assert_eq!(OneOrMany::Many(vec!["a", "b", "c"]).join(","), "a,b,c");
+        assert_eq!(OneOrMany::One(17).join(","), "17");
+    }
+
+    #[test]
+    fn OneOrMany_test_push_unique_front() {
+        use traits::PushableExt;
+
+        let mut x = OneOrMany::One("a");
+        x.push_unique("a");
+        assert_eq!(x, OneOrMany::One("a"));
+        x.push_unique("b").push_unique("a");
+        assert_eq!(x, OneOrMany::Many(vec!["a", "b"]));
+        x.push_front("z");
+        assert_eq!(x, OneOrMany::Many(vec!["z", "a", "b"]));
+
+        let mut y = OneOrMany::One(2);
+        y.push_front(1);
+        assert_eq!(y, OneOrMany::Many(vec![1, 2]));
+    }
+
+    #[test]
+    fn OneOrMany_test_pop() {
+        use traits::Poppable;
+
+        let mut x = OneOrMany::Many(vec![1, 2, 3]);
+        assert_eq!(x.pop(), Some(3));
+        assert_eq!(x, OneOrMany::Many(vec![1, 2]));
+        assert_eq!(x.pop(), Some(2));
+        assert_eq!(x, OneOrMany::One(1));
+        assert_eq!(x.pop(), Some(1));
+        assert_eq!(x, OneOrMany::Many(vec![]));
+        assert_eq!(x.pop(), None);
+    }
+}
@@ -0,0 +1,156 @@
+//! File holding the ProblemDetails type ([RFC 7807](https://tools.ietf.org/html/rfc7807)) and associated tests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use rocket::Response;
+use rocket::http::{ ContentType, Status };
+use rocket::response::content;
+use rocket::response::Responder;
+use serde_json;
+
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// RFC 7807 "Problem Details" representation of an error ResponseJSON
+/// Usually built with `ResponseJSON::to_problem_details()`
+///
+/// It derives Rocket's [Responder trait](https://api.rocket.rs/rocket/response/trait.Responder.html) 
+/// and responds with Content-Type `application/problem+json` and the HTTP status set to `status`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl ProblemDetails {
+    /// Create a ProblemDetails for `status`, the title is the standard reason phrase of the status code
+    /// and the type is `about:blank` (as per RFC 7807)
+    pub fn new(status: u16) -> ProblemDetails {
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: Status::from_code(status).map_or("Unknown error", |status| status.reason).to_string(),
+            status: status,
+            detail: None,
+            instance: None,
+        }
+    }
+
+    /// Set the URI identifying the problem type
+    pub fn problem_type(mut self, uri: String) -> ProblemDetails {
+        self.problem_type = uri;
+        self
+    }
+
+    /// Set the short human readable summary of the problem type
+    pub fn title(mut self, title: String) -> ProblemDetails {
+        self.title = title;
+        self
+    }
+
+    /// Set the human readable explanation specific to this occurrence of the problem
+    pub fn detail(mut self, detail: String) -> ProblemDetails {
+        self.detail = Some(detail);
+        self
+    }
+
+    /// Set the URI identifying this occurrence of the problem
+    pub fn instance(mut self, instance: String) -> ProblemDetails {
+        self.instance = Some(instance);
+        self
+    }
+}
+
+impl<T> ResponseJSON<T> {
+    /// Convert this ResponseJSON into RFC 7807 "Problem Details"
+    /// `http_code` => `status`, `message` => `detail` & `resource` => `instance`
+    pub fn to_problem_details(&self) -> ProblemDetails {
+        ProblemDetails {
+            detail: self.message.clone(),
+            instance: self.resource.clone(),
+            .. ProblemDetails::new(self.http_code)
+        }
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+/// Serializes the ProblemDetails with Content-Type `application/problem+json` and the `status` as HTTP status
+impl<'r> Responder<'r> for ProblemDetails {
+    fn respond(self) -> Result<Response<'r>, Status> {
+        let status = Status::from_code(self.status).unwrap_or(Status::new(self.status, "<unknown code>"));
+        serde_json::to_string(&self)
+            .map_err(|err| {
+                error!("::AMIWO::TYPES::PROBLEM_DETAILS::RESPOND::ERROR Unable to serialize problem details => {:?}", err);
+                Status::InternalServerError
+            })
+            .and_then(|body| content::Content(ContentType::new("application", "problem+json"), body).respond())
+            .map(|mut response| {
+                response.set_status(status);
+                response
+            })
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use super::ProblemDetails;
+    use types::ResponseJSON;
+
+    use rocket::http::Status;
+    use rocket::response::Responder;
+
+    #[test]
+    fn ProblemDetails_test_from_response_json() {
+        let problem = ResponseJSON::error()
+            .http_code(404)
+            .message("No user with id 17".to_string())
+            .resource("/users/17".to_string())
+            .to_problem_details();
+
+        assert_eq!(problem, ProblemDetails::new(404)
+            .detail("No user with id 17".to_string())
+            .instance("/users/17".to_string()));
+        assert_eq!(problem.title, "Not Found");
+        assert_eq!(::serde_json::to_value(&problem).unwrap(), json!({
+            "type": "about:blank",
+            "title": "Not Found",
+            "status": 404,
+            "detail": "No user with id 17",
+            "instance": "/users/17"
+        }));
+    }
+
+    #[test]
+    fn ProblemDetails_test_responder() {
+        let mut response = ProblemDetails::new(422).respond().unwrap();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.header_values("Content-Type").next(), Some("application/problem+json"));
+
+        let body_str = response.body().and_then(|b| b.into_string()).unwrap();
+        assert_eq!(body_str, r#"{"type":"about:blank","title":"Unprocessable Entity","status":422}"#);
+    }
+}
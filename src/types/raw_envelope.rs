@@ -0,0 +1,213 @@
+//! File holding the `IsResponseJSON` implementations for raw JSON bodies (`[u8]`, `RawValue`) and associated tests
+//!
+//! The envelope fields are checked without building the JSON tree: `data` and the values of unknown keys are skipped,
+//! only the kind (null, bool, number, ...) of the envelope fields is retained.
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.0
+//!
+//! ## Release notes
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::fmt;
+
+use serde::de::{ Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor };
+use serde_json;
+#[cfg(feature = "amiwo_raw_value")]
+use serde_json::value::RawValue;
+
+use types::IsResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+// Kind of a JSON value, its content being skipped
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Null,
+    Bool(bool),
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+// Envelope fields of a ResponseJSON, `None` meaning that the key is missing
+#[derive(Debug, Deserialize)]
+struct RawEnvelope {
+    #[serde(default, deserialize_with = "present")]
+    success: Option<Kind>,
+    #[serde(default, deserialize_with = "present")]
+    http_code: Option<Kind>,
+    #[serde(default, deserialize_with = "present")]
+    message: Option<Kind>,
+    #[serde(default, deserialize_with = "present")]
+    resource: Option<Kind>,
+    #[serde(default, deserialize_with = "present")]
+    method: Option<Kind>,
+    #[serde(default, deserialize_with = "present")]
+    error_code: Option<Kind>,
+    #[serde(default, deserialize_with = "present")]
+    errors: Option<Kind>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl RawEnvelope {
+    // Parse the envelope fields of `bytes`, `None` if it isn't a JSON object
+    fn parse(bytes: &[u8]) -> Option<RawEnvelope> {
+        let is_object = bytes.iter()
+            .find(|byte| !(**byte as char).is_whitespace())
+            .map_or(false, |byte| *byte == b'{');
+
+        if is_object { serde_json::from_slice(bytes).ok() } else { None }
+    }
+
+    fn is_valid_json(&self) -> bool {
+        self.success.is_some() && self.http_code.is_some()
+    }
+
+    fn is_ok_json(&self) -> bool {
+        let is_null = |kind: Option<Kind>| kind.map_or(true, |kind| kind == Kind::Null);
+
+        self.success == Some(Kind::Bool(true)) &&
+        self.http_code == Some(Kind::Number) &&
+        is_null(self.method) &&
+        is_null(self.resource) &&
+        is_null(self.message) &&
+        is_null(self.error_code) &&
+        is_null(self.errors)
+    }
+
+    fn is_error_json(&self) -> bool {
+        let is_none_or = |kind: Option<Kind>, expected: Kind| kind.map_or(true, |kind| kind == expected);
+
+        self.success == Some(Kind::Bool(false)) &&
+        self.http_code == Some(Kind::Number) &&
+        is_none_or(self.message, Kind::String) &&
+        is_none_or(self.error_code, Kind::String) &&
+        is_none_or(self.errors, Kind::Array) &&
+        is_none_or(self.resource, Kind::String) &&
+        is_none_or(self.method, Kind::String)
+    }
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Distinguish a `null` value (`Some(Kind::Null)`) from a missing key (`None`, thanks to `#[serde(default)]`)
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Kind>, D::Error> {
+    Kind::deserialize(deserializer).map(Some)
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl<'de> Deserialize<'de> for Kind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Kind, D::Error> {
+        struct KindVisitor;
+
+        impl<'de> Visitor<'de> for KindVisitor {
+            type Value = Kind;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "any JSON value")
+            }
+
+            fn visit_unit<E>(self) -> Result<Kind, E> { Ok(Kind::Null) }
+            fn visit_none<E>(self) -> Result<Kind, E> { Ok(Kind::Null) }
+            fn visit_bool<E>(self, value: bool) -> Result<Kind, E> { Ok(Kind::Bool(value)) }
+            fn visit_i64<E>(self, _: i64) -> Result<Kind, E> { Ok(Kind::Number) }
+            fn visit_u64<E>(self, _: u64) -> Result<Kind, E> { Ok(Kind::Number) }
+            fn visit_f64<E>(self, _: f64) -> Result<Kind, E> { Ok(Kind::Number) }
+            fn visit_str<E>(self, _: &str) -> Result<Kind, E> { Ok(Kind::String) }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Kind, A::Error> {
+                while let Some(IgnoredAny) = seq.next_element()? {}
+                Ok(Kind::Array)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Kind, A::Error> {
+                while let Some((IgnoredAny, IgnoredAny)) = map.next_entry()? {}
+                Ok(Kind::Object)
+            }
+        }
+
+        deserializer.deserialize_any(KindVisitor)
+    }
+}
+
+/// Classify a raw JSON body (e.g. an upstream response) without parsing it into a `Value`
+impl IsResponseJSON for [u8] {
+    fn is_valid_json(&self) -> bool {
+        RawEnvelope::parse(self).map_or(false, |envelope| envelope.is_valid_json())
+    }
+
+    fn is_ok_json(&self) -> bool {
+        RawEnvelope::parse(self).map_or(false, |envelope| envelope.is_ok_json())
+    }
+
+    fn is_error_json(&self) -> bool {
+        RawEnvelope::parse(self).map_or(false, |envelope| envelope.is_error_json())
+    }
+}
+
+#[cfg(feature = "amiwo_raw_value")]
+impl IsResponseJSON for RawValue {
+    fn is_valid_json(&self) -> bool {
+        self.get().as_bytes().is_valid_json()
+    }
+
+    fn is_ok_json(&self) -> bool {
+        self.get().as_bytes().is_ok_json()
+    }
+
+    fn is_error_json(&self) -> bool {
+        self.get().as_bytes().is_error_json()
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use types::IsResponseJSON;
+
+    #[test]
+    fn RawEnvelope_test_bytes() {
+        let ok : &[u8] = br#"{ "success": true, "http_code": 201, "data": { "nested": [1, { "message": 2 }] }, "extra": null }"#;
+        assert_eq!(ok.is_valid_json(), true);
+        assert_eq!(ok.is_ok_json(), true);
+        assert_eq!(ok.is_error_json(), false);
+
+        let error : &[u8] = br#"{ "success": false, "http_code": 404, "message": "not found", "errors": [] }"#;
+        assert_eq!(error.is_valid_json(), true);
+        assert_eq!(error.is_ok_json(), false);
+        assert_eq!(error.is_error_json(), true);
+
+        assert_eq!(br#"{ "success": true, "http_code": 200, "message": "error" }"#[..].is_ok_json(), false);
+        assert_eq!(br#"{ "success": false, "http_code": 500, "message": null }"#[..].is_error_json(), false);
+        assert_eq!(br#"{ "success": true, "http_code": "200" }"#[..].is_ok_json(), false);
+        assert_eq!(br#"{ "success": true }"#[..].is_valid_json(), false);
+        assert_eq!(br#"[true, 200]"#[..].is_valid_json(), false);
+        assert_eq!(b"{ invalid"[..].is_valid_json(), false);
+    }
+
+    #[test]
+    #[cfg(feature = "amiwo_raw_value")]
+    fn RawEnvelope_test_raw_value() {
+        use serde_json;
+        use serde_json::value::RawValue;
+
+        let raw : Box<RawValue> = serde_json::from_str(r#"{ "success": true, "http_code": 200, "data": [1, 2] }"#).unwrap();
+        assert_eq!(raw.is_ok_json(), true);
+        assert_eq!(raw.is_error_json(), false);
+    }
+}
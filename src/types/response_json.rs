@@ -1,9 +1,32 @@
 //! File holding the ResponseJSON type and associated tests
 //!
 //! Author: [Boris](mailto:boris@humanenginuity.com)
-//! Version: 1.1
+//! Version: 1.24
 //!
 //! ## Release notes
+//! - v1.24 : `From<Result<T, GenericError>>` answers a failure to serialize `T` with a 500
+//! - v1.23 : `and_then_data()` matches the result of `f` instead of unwrapping the transformed data
+//! - v1.22 : added `into_typed()`
+//! - v1.21 : implemented `From<reqwest::Response>` (feature `rest-reqwest`)
+//! - v1.20 : implemented `Serialize` manually to serialize straight to the output (`to_string()` no longer builds an intermediate `Value`)
+//! - v1.19 : `from_serde_value()` moves the fields of the input instead of cloning them
+//! - v1.18 : added field-level validation errors (`errors`, `field_error()`)
+//! - v1.17 : added HAL-style hypermedia links (`link()`), serialized as `_links`
+//! - v1.16 : implemented `From<Result<T, GenericError>>`
+//! - v1.15 : added `map_data()` & `and_then_data()` combinators
+//! - v1.14 : added custom response headers (`header()`)
+//! - v1.13 : added strict parsing mode (`ParseMode`, `from_str_strict()`)
+//! - v1.12 : added `ok_with_data()` & `error_with_message()` one-shot constructors
+//! - v1.11 : added `request_id`
+//! - v1.10 : added machine-readable `error_code`
+//! - v1.9 : added `warnings` list
+//! - v1.8 : added `filter_fields()` to prune `data` down to a list of (dotted) fields
+//! - v1.7 : added `data_as()` & `into_data_as()` to deserialize `data` into any type
+//! - v1.6 : added `from_error()` & `From<GenericError>` to build an error ResponseJSON from a `GenericError`
+//! - v1.5 : `Responder` implementation uses `http_code` as the HTTP status of the response
+//! - v1.4 : added pagination fields (`page`, `per_page`, `total`, `total_pages`, `next`, `prev`)
+//! - v1.3 : implemented serde's `Serialize` & `Deserialize` on `ResponseJSON`
+//! - v1.2 : made `ResponseJSON` generic over its `data` type (defaults to `Value`)
 //! - v1.1 : changed `data` to Value instead of &Value
 //! - v1.0 : creation
 
@@ -12,6 +35,7 @@
 // =======================================================================
 use std::error::Error;
 use std::io::Read;
+use std::mem;
 use std::string::ToString;
 
 use hyper;
@@ -23,17 +47,22 @@ use rocket::data::{ FromData, Outcome };
 use rocket::http::Status;
 use rocket::outcome::IntoOutcome;
 use rocket::response::Responder;
+use serde::{ Deserialize, Serialize, Serializer };
+use serde::ser::SerializeMap;
+use serde::de::DeserializeOwned;
 use serde_json;
+use serde_json::map::Map;
 use serde_json::Value;
 
 use error::GenericError;
+use types::null_probe::is_null;
 use util::ContainsKeys;
 
 // =======================================================================
 // STRUCT & TRAIT DEFINITION
 // =======================================================================
 /// JSON wrapper for a JSON response from a REST route
-/// It wraps an optional generic type `T` that just needs to implement [serde's Deserialize](https://docs.serde.rs/serde/de/trait.Deserializer.html)
+/// It wraps a generic type `T` (defaults to `serde_json::Value`) that just needs to implement [serde's Serialize](https://docs.serde.rs/serde/ser/trait.Serialize.html)
 ///
 /// It derives Rocket's [Responder trait](https://api.rocket.rs/rocket/response/trait.Responder.html) so it can be used as such in a Rocket's route as illustrated below
 ///
@@ -41,14 +70,64 @@ use util::ContainsKeys;
 /// #[get("/")]
 /// fn index() -> ResponseJSON<T> { ... }
 /// ```
-#[derive(Clone, Debug)]
-pub struct ResponseJSON {
+///
+/// It also implements serde's `Serialize` & `Deserialize` so it can be embedded in other serde structures
+/// or built with `serde_json::from_value()`. Contrary to `ResponseJSON::from_str()`, deserializing this way
+/// expects a proper envelope (i.e. `success` & `http_code` are mandatory) and won't wrap arbitrary JSON into `data`.
+/// Unset optional fields and a `null` data are omitted when serializing, keys are serialized in alphabetical order.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ResponseJSON<T = Value> {
     pub success: bool,
     pub http_code: u16,
-    pub data: Value,
+    #[serde(default)]
+    pub data: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub message: Option<String>, // required for error JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub resource: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>, // machine-readable error code (e.g. "USER_NOT_FOUND")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>, // field-level validation errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_page: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>, // link to the next page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>, // link to the previous page
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>, // deprecation notices, partial results, ...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>, // request / correlation id
+    #[serde(rename = "_links", default, skip_serializing_if = "Map::is_empty")]
+    pub links: Map<String, Value>, // HAL-style hypermedia links, e.g. { "self": { "href": "/users/17" } }
+    #[serde(skip_serializing, skip_deserializing)]
+    pub headers: Vec<(String, String)>, // extra HTTP headers set by the Responder, not part of the JSON body
+}
+
+/// A validation error on one field of the request (form / JSON body), listed in the `errors` of an error ResponseJSON
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String, // machine-readable error code (e.g. "TOO_SHORT")
+    pub message: String,
+}
+
+/// How `ResponseJSON::from_str_with_mode()` handles JSON which is not a proper ResponseJSON envelope
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParseMode {
+    /// Any valid JSON is accepted, improper envelopes are wrapped into `data` (behavior of `ResponseJSON::from_str()`)
+    Lenient,
+    /// Only proper envelopes are accepted, a `GenericError` describing the malformed field is returned otherwise
+    Strict,
 }
 
 /// Test if the underlying structure is a valid ResponseJSON
@@ -71,6 +150,18 @@ impl ResponseJSON {
             message: None,
             resource: None,
             method: None,
+            error_code: None,
+            errors: Vec::new(),
+            page: None,
+            per_page: None,
+            total: None,
+            total_pages: None,
+            next: None,
+            prev: None,
+            warnings: Vec::new(),
+            request_id: None,
+            links: Map::new(),
+            headers: Vec::new(),
         }
     }
 
@@ -83,24 +174,318 @@ impl ResponseJSON {
             message: Some("Unexpected error".to_string()),
             resource: None,
             method: None,
+            error_code: None,
+            errors: Vec::new(),
+            page: None,
+            per_page: None,
+            total: None,
+            total_pages: None,
+            next: None,
+            prev: None,
+            warnings: Vec::new(),
+            request_id: None,
+            links: Map::new(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Create an OK ResponseJSON holding `data`
+    pub fn ok_with_data<T: Serialize>(data: T) -> ResponseJSON<T> {
+        ResponseJSON::ok().with_data(data)
+    }
+
+    /// Create an error ResponseJSON with the given HTTP code & error message
+    pub fn error_with_message<S: ToString>(code: u16, msg: S) -> ResponseJSON {
+        ResponseJSON::error()
+            .http_code(code)
+            .message(msg.to_string())
+    }
+
+    /// Create an error ResponseJSON from a `GenericError`
+    /// The HTTP code is derived from the kind of error (see `GenericError::http_code()`) & the message from its description
+    pub fn from_error(err: GenericError) -> ResponseJSON {
+        ResponseJSON::error()
+            .http_code(err.http_code())
+            .message(err.description().to_string())
+    }
+
+    /// ResponseJSON<T> can be created from a `serde_json::Value`, consuming the original object
+    /// If the input is a valid ResponseJSON its fields are moved into the new ResponseJSON (no copy of `data`)
+    /// Else it creates an Ok ResponseJSON with it's data property set to the input JSON
+    pub fn from_serde_value(json: Value) -> ResponseJSON {
+        let success = if json.is_ok_json() {
+            true
+        } else if json.is_error_json() {
+            false
+        } else {
+            return ResponseJSON::ok().data(json);
+        };
+
+        let mut map = match json {
+            Value::Object(map) => map,
+            _ => unreachable!(), // a valid ResponseJSON is an object
+        };
+
+        let http_code = map["http_code"].as_u64().unwrap() as u16;
+        let data = map.remove("data").unwrap_or(Value::Null);
+        let mut rjson = (if success { ResponseJSON::ok() } else { ResponseJSON::error() })
+            .http_code(http_code)
+            .data(data);
+
+        if !success {
+            if let Some(message) = take_string(&mut map, "message") { rjson.message = Some(message); }
+            rjson.resource = take_string(&mut map, "resource");
+            rjson.method = take_string(&mut map, "method");
+            rjson.error_code = take_string(&mut map, "error_code");
+            if let Some(Value::Array(errors)) = map.remove("errors") {
+                rjson.errors = errors.into_iter().filter_map(|error| serde_json::from_value(error).ok()).collect();
+            }
+        }
+
+        rjson.optional_fields_from(map)
+    }
+
+    // Move the optional fields (pagination, warnings, links, ...) of an envelope `map` into this ResponseJSON
+    fn optional_fields_from(mut self, mut map: Map<String, Value>) -> ResponseJSON {
+        self.page = map.get("page").and_then(|v| v.as_u64());
+        self.per_page = map.get("per_page").and_then(|v| v.as_u64());
+        self.total = map.get("total").and_then(|v| v.as_u64());
+        self.total_pages = map.get("total_pages").and_then(|v| v.as_u64());
+        self.next = take_string(&mut map, "next");
+        self.prev = take_string(&mut map, "prev");
+        self.request_id = take_string(&mut map, "request_id");
+        self.warnings = match map.remove("warnings") {
+            Some(Value::Array(vect)) => vect.into_iter().filter_map(|v| match v { Value::String(string) => Some(string), _ => None }).collect(),
+            _ => Vec::new(),
+        };
+        self.links = match map.remove("_links") {
+            Some(Value::Object(links)) => links,
+            _ => Map::new(),
+        };
+        self
+    }
+
+    /// Deserialize the `data` of this ResponseJSON into any `U: DeserializeOwned`
+    pub fn data_as<U: DeserializeOwned>(&self) -> Result<U, GenericError> {
+        U::deserialize(&self.data)
+            .map_err( |serde_err| GenericError::Serde(serde_err) )
+    }
+
+    /// Consumes the ResponseJSON and deserialize its `data` into any `U: DeserializeOwned`
+    pub fn into_data_as<U: DeserializeOwned>(self) -> Result<U, GenericError> {
+        serde_json::from_value(self.data)
+            .map_err( |serde_err| GenericError::Serde(serde_err) )
+    }
+
+    /// Same as `into_data_as()` for a successful ResponseJSON, an error ResponseJSON (e.g. answered by an upstream service)
+    /// being converted into `GenericError::Upstream` with its `http_code` & `message`
+    pub fn into_typed<U: DeserializeOwned>(self) -> Result<U, GenericError> {
+        if self.success {
+            self.into_data_as()
+        } else {
+            let description = self.message.clone().unwrap_or(format!("Upstream service answered with status {}", self.http_code));
+            Err(GenericError::Upstream((self.http_code, description)))
+        }
+    }
+
+    /// Prune `data` down to the requested `fields`. Nested fields can be selected using a dotted path (e.g. `user.name`).
+    /// If `data` (or any intermediate value) is an array, the filter is applied to each of its elements.
+    /// An empty list of fields leaves `data` untouched
+    pub fn filter_fields(mut self, fields: &[&str]) -> ResponseJSON {
+        if !fields.is_empty() {
+            let paths : Vec<Vec<&str>> = fields.iter().map(|field| field.split('.').collect()).collect();
+            let data = ::std::mem::replace(&mut self.data, Value::Null);
+            self.data = filter_value(data, &paths);
+        }
+        self
+    }
+
+    /// Build a ResponseJSON from a `serde_json::Value` that must be a proper envelope
+    /// Returns a `GenericError` describing the first malformed field otherwise
+    pub fn from_serde_value_strict(json: Value) -> Result<ResponseJSON, GenericError> {
+        validate_envelope(&json)
+            .and_then(|_| serde_json::from_value(json).map_err( |serde_err| GenericError::Serde(serde_err) ))
+    }
+
+    /// Deserialize a ResponseJSON from a string of JSON text that must be a proper envelope (see `from_serde_value_strict()`)
+    pub fn from_str_strict<'s>(s: &'s str) -> Result<ResponseJSON, GenericError> {
+        serde_json::from_str(s)
+            .map_err( |serde_err| GenericError::Serde(serde_err) )
+            .and_then( |value : Value| Self::from_serde_value_strict(value) )
+    }
+
+    /// Deserialize a ResponseJSON from a string of JSON text using the given `ParseMode`
+    pub fn from_str_with_mode<'s>(s: &'s str, mode: ParseMode) -> Result<ResponseJSON, GenericError> {
+        match mode {
+            ParseMode::Lenient => Self::from_str(s),
+            ParseMode::Strict => Self::from_str_strict(s),
         }
     }
 
+    /// Deserialize a ResponseJSON from a string of JSON text
+    pub fn from_str<'s>(s: &'s str) -> Result<ResponseJSON, GenericError> {
+        serde_json::from_str(s)
+            .map( |value : Value| Self::from_serde_value(value) )
+            .map_err( |serde_err| GenericError::Serde(serde_err) )
+    }
+
+    /// Deserialize a ResponseJSON from an IO stream of JSON
+    pub fn from_reader<R: Read>(reader: R) -> Result<ResponseJSON, GenericError> {
+        serde_json::from_reader(reader)
+            .map( |value : Value| Self::from_serde_value(value) )
+            .map_err( |serde_err| GenericError::Serde(serde_err) )
+    }
+}
+
+impl<T> ResponseJSON<T> {
     /// Set the HTTP Code of this ResponseJSON
-    pub fn http_code(mut self, code: u16) -> ResponseJSON {
+    pub fn http_code(mut self, code: u16) -> ResponseJSON<T> {
         self.http_code = code;
         self
     }
 
     /// Set the data of this ResponseJSON
-    pub fn data(mut self, data: Value) -> ResponseJSON {
+    pub fn data(mut self, data: T) -> ResponseJSON<T> {
         self.data = data;
         self
     }
 
+    /// Replace the data of this ResponseJSON with a value of another type, keeping the rest of the envelope.
+    /// Useful to return a strongly typed payload, e.g. `ResponseJSON::ok().with_data(user)`
+    pub fn with_data<U>(self, data: U) -> ResponseJSON<U> {
+        self.map_data(|_| data)
+    }
+
+    /// Transform `data` with `f`, keeping the rest of the envelope
+    ///
+    /// ```rust,ignore
+    /// let users = amiwo::contrib::hyper::request("GET", url)?
+    ///     .map_data(|data| json!({ "users": data }));
+    /// ```
+    pub fn map_data<U, F: FnOnce(T) -> U>(self, f: F) -> ResponseJSON<U> {
+        let (data, rjson) = self.replace_data(());
+        rjson.replace_data(f(data)).1
+    }
+
+    /// Transform `data` with the fallible `f`, keeping the rest of the envelope.
+    /// Returns the error of `f` if it fails
+    pub fn and_then_data<U, F: FnOnce(T) -> Result<U, GenericError>>(self, f: F) -> Result<ResponseJSON<U>, GenericError> {
+        let (data, rjson) = self.replace_data(());
+        match f(data) {
+            Ok(data) => Ok(rjson.replace_data(data).1),
+            Err(err) => Err(err),
+        }
+    }
+
+    // Put `data` into the envelope, returning the previous data & the new envelope
+    fn replace_data<U>(self, data: U) -> (T, ResponseJSON<U>) {
+        (self.data, ResponseJSON {
+            success: self.success,
+            http_code: self.http_code,
+            data: data,
+            message: self.message,
+            resource: self.resource,
+            method: self.method,
+            error_code: self.error_code,
+            errors: self.errors,
+            page: self.page,
+            per_page: self.per_page,
+            total: self.total,
+            total_pages: self.total_pages,
+            next: self.next,
+            prev: self.prev,
+            warnings: self.warnings,
+            request_id: self.request_id,
+            links: self.links,
+            headers: self.headers,
+        })
+    }
+
+    /// Set `data` to one page of a collection and fill in the pagination fields.
+    /// `total` is the size of the whole collection, `total_pages` is computed from it
+    pub fn paginated<U>(self, data: U, page: u64, per_page: u64, total: u64) -> ResponseJSON<U> {
+        let mut rjson = self.with_data(data);
+        rjson.page = Some(page);
+        rjson.per_page = Some(per_page);
+        rjson.total = Some(total);
+        rjson.total_pages = Some(if per_page == 0 { 0 } else { (total + per_page - 1) / per_page });
+        rjson
+    }
+
+    /// Set the machine-readable error code (e.g. "USER_NOT_FOUND").
+    /// For Error JSON only (does nothing if `success == ok`)
+    pub fn code<S: ToString>(mut self, code: S) -> ResponseJSON<T> {
+        if !self.success {
+            self.error_code = Some(code.to_string());
+        } else {
+            warn!("::AMIWO::CONTRIB::ROCKET::RESPONSEJSON::CODE::WARNING Trying to set `error_code` on an Ok JSON => ignored")
+        }
+        self
+    }
+
+    /// Add a validation error on `field`, so that every invalid field can be reported in one response.
+    /// For Error JSON only (does nothing if `success == ok`)
+    pub fn field_error<F: ToString, C: ToString, M: ToString>(mut self, field: F, code: C, msg: M) -> ResponseJSON<T> {
+        if !self.success {
+            self.errors.push(FieldError {
+                field: field.to_string(),
+                code: code.to_string(),
+                message: msg.to_string(),
+            });
+        } else {
+            warn!("::AMIWO::CONTRIB::ROCKET::RESPONSEJSON::FIELD_ERROR::WARNING Trying to add a field error on an Ok JSON => ignored")
+        }
+        self
+    }
+
+    /// Set the link to the next page
+    pub fn next(mut self, url: String) -> ResponseJSON<T> {
+        self.next = Some(url);
+        self
+    }
+
+    /// Set the id of the request this ResponseJSON answers to (see `contrib::rocket::RequestId`)
+    pub fn request_id(mut self, id: String) -> ResponseJSON<T> {
+        self.request_id = Some(id);
+        self
+    }
+
+    /// Add an HTTP header (e.g. `X-Total-Count`, `Link`, `Cache-Control`) to the response built by the `Responder`.
+    /// Headers are not part of the JSON body; adding the same header name several times sends all the values
+    pub fn header<N: ToString, V: ToString>(mut self, name: N, value: V) -> ResponseJSON<T> {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add a HAL-style hypermedia link `{ "href": url }` under the relation `rel` (e.g. `self`, `next`, `author`).
+    /// Adding several links with the same relation turns it into an array of links
+    pub fn link<R: ToString, U: ToString>(mut self, rel: R, url: U) -> ResponseJSON<T> {
+        let link = json!({ "href": url.to_string() });
+        let rel = rel.to_string();
+        let links = match self.links.remove(&rel) {
+            Some(Value::Array(mut vect)) => { vect.push(link); Value::Array(vect) },
+            Some(existing) => Value::Array(vec![existing, link]),
+            None => link,
+        };
+        self.links.insert(rel, links);
+        self
+    }
+
+    /// Add a warning (e.g. deprecation notice, partial result) to this ResponseJSON
+    pub fn warning<S: ToString>(mut self, msg: S) -> ResponseJSON<T> {
+        self.warnings.push(msg.to_string());
+        self
+    }
+
+    /// Set the link to the previous page
+    pub fn prev(mut self, url: String) -> ResponseJSON<T> {
+        self.prev = Some(url);
+        self
+    }
+
     /// Set the error message.
     /// For Error JSON only (does nothing if `success == ok`)    
-    pub fn message(mut self, string: String) -> ResponseJSON {
+    pub fn message(mut self, string: String) -> ResponseJSON<T> {
         if !self.success {
             self.message = Some(string);
         } else {
@@ -111,7 +496,7 @@ impl ResponseJSON {
 
     /// Set the resource that we tried to access.
     /// For Error JSON only (does nothing if `success == ok`)
-    pub fn resource(mut self, string: String) -> ResponseJSON {
+    pub fn resource(mut self, string: String) -> ResponseJSON<T> {
         if !self.success {
             self.resource = Some(string);
         } else {
@@ -122,7 +507,7 @@ impl ResponseJSON {
 
     /// Set the method that was used (GET, POST, ...).
     /// For Error JSON only (does nothing if `success == ok`)
-    pub fn method(mut self, string: String) -> ResponseJSON {
+    pub fn method(mut self, string: String) -> ResponseJSON<T> {
         if !self.success {
             self.method = Some(string);
         } else {
@@ -130,54 +515,117 @@ impl ResponseJSON {
         }
         self
     }
+}
 
-    /// ResponseJSON<T> can be created from a `serde_json::Value`, consuming the original object
-    /// If the input is a valid ResponseJSON it duplicates it
-    /// Else it creates an Ok ResponseJSON with it's data property set to the input JSON
-    pub fn from_serde_value(json: Value) -> ResponseJSON {
-        if json.is_object() {
-            if json.is_ok_json() {
-                ResponseJSON::ok()
-                    .http_code(json["http_code"].as_u64().unwrap() as u16)
-                    .data(json.get("data").unwrap_or(&Value::Null).clone())
-            } else if json.is_error_json() {
-                let mut rjson = ResponseJSON::error()
-                    .http_code(json["http_code"].as_u64().unwrap() as u16)
-                    .data(json.get("data").unwrap_or(&Value::Null).clone());
-
-                if !json["message"].is_null() { rjson = rjson.message(json["message"].as_str().unwrap().to_string()); }
-                if !json["resource"].is_null() { rjson = rjson.resource(json["resource"].as_str().unwrap().to_string()); }
-                if !json["method"].is_null() { rjson = rjson.method(json["method"].as_str().unwrap().to_string()); }
-
-                rjson
-            } else {
-                ResponseJSON::ok()
-                    .data(json.pointer("").unwrap().clone())
-            }
-        } else {
-            ResponseJSON::ok()
-                .data(json.pointer("").unwrap().clone())
-        }
+impl<T: Serialize> ResponseJSON<T> {
+    /// Consumes the ResponseJSON wrapper and returns the wrapped item.
+    // Note: Contrary to `serde_json::to_string()`, serialization can't fail.
+    pub fn into_string(self) -> String {
+        self.to_string()
     }
+}
 
-    /// Deserialize a ResponseJSON from a string of JSON text
-    pub fn from_str<'s>(s: &'s str) -> Result<ResponseJSON, GenericError> {
-        serde_json::from_str(s)
-            .map( |value : Value| Self::from_serde_value(value) )
-            .map_err( |serde_err| GenericError::Serde(serde_err) )
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Remove `key` from `map`, returning its value if it is a string
+fn take_string(map: &mut Map<String, Value>, key: &str) -> Option<String> {
+    match map.remove(key) {
+        Some(Value::String(string)) => Some(string),
+        _ => None,
     }
+}
 
-    /// Deserialize a ResponseJSON from an IO stream of JSON
-    pub fn from_reader<R: Read>(reader: R) -> Result<ResponseJSON, GenericError> {
-        serde_json::from_reader(reader)
-            .map( |value : Value| Self::from_serde_value(value) )
-            .map_err( |serde_err| GenericError::Serde(serde_err) )
+// Check that `json` is a proper ResponseJSON envelope, describing the first malformed field otherwise
+fn validate_envelope(json: &Value) -> Result<(), GenericError> {
+    let map = match json.as_object() {
+        Some(map) => map,
+        None => return amiwo_error!("::AMIWO::TYPES::RESPONSEJSON::VALIDATE_ENVELOPE::ERROR ResponseJSON must be a JSON object".to_string()),
+    };
+
+    let invalid = |field: &str, reason: &str| amiwo_error!(format!("::AMIWO::TYPES::RESPONSEJSON::VALIDATE_ENVELOPE::ERROR Invalid field `{}`: {}", field, reason));
+
+    let success = match map.get("success") {
+        Some(&Value::Bool(success)) => success,
+        Some(_) => return invalid("success", "must be a boolean"),
+        None => return invalid("success", "missing"),
+    };
+
+    match map.get("http_code").map(|code| code.as_u64()) {
+        Some(Some(code)) if code >= 100 && code <= 999 => (),
+        Some(_) => return invalid("http_code", "must be an integer between 100 and 999"),
+        None => return invalid("http_code", "missing"),
     }
-  
-    /// Consumes the ResponseJSON wrapper and returns the wrapped item.
-    // Note: Contrary to `serde_json::to_string()`, serialization can't fail.
-    pub fn into_string(self) -> String {
-        self.to_string()
+
+    for (key, value) in map.iter() {
+        match key.as_str() {
+            "success" | "http_code" | "data" => (),
+            "message" | "resource" | "method" | "error_code" => {
+                if success && !value.is_null() {
+                    return invalid(key, "not allowed on an OK ResponseJSON");
+                }
+                if !value.is_null() && !value.is_string() {
+                    return invalid(key, "must be a string");
+                }
+            },
+            "errors" => {
+                if success && !value.is_null() {
+                    return invalid(key, "not allowed on an OK ResponseJSON");
+                }
+                if !value.is_null() && !value.as_array().map_or(false, |vect| vect.iter().all(|item| FieldError::deserialize(item).is_ok())) {
+                    return invalid(key, "must be an array of { field, code, message } objects");
+                }
+            },
+            "page" | "per_page" | "total" | "total_pages" => {
+                if !value.is_null() && !value.is_u64() {
+                    return invalid(key, "must be a positive integer");
+                }
+            },
+            "next" | "prev" | "request_id" => {
+                if !value.is_null() && !value.is_string() {
+                    return invalid(key, "must be a string");
+                }
+            },
+            "_links" => {
+                if !value.is_object() {
+                    return invalid(key, "must be an object");
+                }
+            },
+            "warnings" => {
+                if !value.as_array().map_or(false, |vect| vect.iter().all(|item| item.is_string())) {
+                    return invalid(key, "must be an array of strings");
+                }
+            },
+            _ => return invalid(key, "unknown field"),
+        }
+    }
+
+    Ok(())
+}
+
+// Keep only the `paths` of `value` (applied to each element of an array)
+fn filter_value(value: Value, paths: &[Vec<&str>]) -> Value {
+    match value {
+        Value::Array(vect) => Value::Array(vect.into_iter().map(|item| filter_value(item, paths)).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter_map(|(key, value)| {
+                    let sub_paths : Vec<Vec<&str>> = paths.iter()
+                        .filter(|path| path[0] == key.as_str())
+                        .map(|path| path[1..].to_vec())
+                        .collect();
+
+                    if sub_paths.is_empty() {
+                        None
+                    } else if sub_paths.iter().any(|path| path.is_empty()) {
+                        Some((key, value))
+                    } else {
+                        Some((key, filter_value(value, &sub_paths)))
+                    }
+                })
+                .collect()
+        ),
+        scalar @ _ => scalar,
     }
 }
 
@@ -185,28 +633,42 @@ impl ResponseJSON {
 // TRAIT IMPLEMENTATION
 // ======================================================================
 /// Serialize the given ResponseJSON as a String
-impl ToString for ResponseJSON {
+impl<T: Serialize> ToString for ResponseJSON<T> {
     // Note: Contrary to `serde_json::to_string()`, serialization can't fail.
     fn to_string(&self) -> String {
-        json!({
-            "success": self.success,
-            "http_code": self.http_code,
-            "data": &self.data,
-            "message": &self.message,
-            "resource": &self.resource,
-            "method": &self.method
-        }).as_object_mut()
-        .map_or(
-            "{\"http_code\":500,\"message\":\"Invalid ResponseJSON\",\"success\":false}".to_string(),
-            |map| {
-                if map["data"].is_null() { map.remove("data"); };
-                if map["message"].is_null() { map.remove("message"); };
-                if map["resource"].is_null() { map.remove("resource"); };
-                if map["method"].is_null() { map.remove("method"); };
-
-                serde_json::to_string(map).unwrap()
-            }
-        )
+        serde_json::to_string(self)
+            .unwrap_or("{\"http_code\":500,\"message\":\"Invalid ResponseJSON\",\"success\":false}".to_string())
+    }
+}
+
+/// Serialize the ResponseJSON as a map with its keys in alphabetical order, omitting unset optional fields and a `null` data
+impl<T: Serialize> Serialize for ResponseJSON<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let has_data = !is_null(&self.data);
+        let len = 2 + 
+            [has_data, !self.links.is_empty(), !self.errors.is_empty(), !self.warnings.is_empty()].iter().filter(|&&set| set).count() +
+            [&self.error_code, &self.message, &self.method, &self.next, &self.prev, &self.request_id, &self.resource].iter().filter(|field| field.is_some()).count() +
+            [&self.page, &self.per_page, &self.total, &self.total_pages].iter().filter(|field| field.is_some()).count();
+
+        let mut map = serializer.serialize_map(Some(len))?;
+        if !self.links.is_empty() { map.serialize_entry("_links", &self.links)?; }
+        if has_data { map.serialize_entry("data", &self.data)?; }
+        if let Some(ref error_code) = self.error_code { map.serialize_entry("error_code", error_code)?; }
+        if !self.errors.is_empty() { map.serialize_entry("errors", &self.errors)?; }
+        map.serialize_entry("http_code", &self.http_code)?;
+        if let Some(ref message) = self.message { map.serialize_entry("message", message)?; }
+        if let Some(ref method) = self.method { map.serialize_entry("method", method)?; }
+        if let Some(ref next) = self.next { map.serialize_entry("next", next)?; }
+        if let Some(ref page) = self.page { map.serialize_entry("page", page)?; }
+        if let Some(ref per_page) = self.per_page { map.serialize_entry("per_page", per_page)?; }
+        if let Some(ref prev) = self.prev { map.serialize_entry("prev", prev)?; }
+        if let Some(ref request_id) = self.request_id { map.serialize_entry("request_id", request_id)?; }
+        if let Some(ref resource) = self.resource { map.serialize_entry("resource", resource)?; }
+        map.serialize_entry("success", &self.success)?;
+        if let Some(ref total) = self.total { map.serialize_entry("total", total)?; }
+        if let Some(ref total_pages) = self.total_pages { map.serialize_entry("total_pages", total_pages)?; }
+        if !self.warnings.is_empty() { map.serialize_entry("warnings", &self.warnings)?; }
+        map.end()
     }
 }
 
@@ -241,9 +703,21 @@ impl FromData for ResponseJSON {
 /// Serializes the wrapped value into a ResponseJSON. Returns a response with Content-Type
 /// JSON and a fixed-size body with the serialized value. If serialization
 /// fails, an `Err` of `Status::InternalServerError` is returned.
-impl<'r> Responder<'r> for ResponseJSON {
-    fn respond(self) -> Result<Response<'r>, Status> {
+///
+/// The status of the response is taken from `http_code` (so an error ResponseJSON with `http_code: 404`
+/// actually returns a 404 to the client)
+impl<'r, T: Serialize> Responder<'r> for ResponseJSON<T> {
+    fn respond(mut self) -> Result<Response<'r>, Status> {
+        let status = Status::from_code(self.http_code).unwrap_or(Status::new(self.http_code, "<unknown code>"));
+        let headers = mem::replace(&mut self.headers, Vec::new());
         content::JSON(self.into_string()).respond()
+            .map(|mut response| {
+                response.set_status(status);
+                for (name, value) in headers {
+                    response.adjoin_raw_header(name, value);
+                }
+                response
+            })
     }
 }
 
@@ -268,7 +742,44 @@ impl From<hyper::client::response::Response> for ResponseJSON {
     }
 }
 
-impl IsResponseJSON for ResponseJSON {
+/// Same sugar as `From<hyper::client::response::Response>` for a `reqwest::Response`
+#[cfg(feature = "rest-reqwest")]
+impl From<::reqwest::Response> for ResponseJSON {
+    fn from(response: ::reqwest::Response) -> Self {
+        ResponseJSON::from_reader(response)
+            .unwrap_or_else( |err| ResponseJSON::error().data(Value::String(format!("Error converting response into a ResponseJSON > {}", err.description()))) )
+    }
+}
+
+/// Sugar for `ResponseJSON::from_error()`
+impl From<GenericError> for ResponseJSON {
+    fn from(err: GenericError) -> Self {
+        ResponseJSON::from_error(err)
+    }
+}
+
+/// Build an OK ResponseJSON holding the serialized `T` on success, an error ResponseJSON (see `ResponseJSON::from_error()`) on failure.
+/// `T` failing to serialize is a server error, answered with a 500
+///
+/// ```rust,ignore
+/// #[get("/users/<id>")]
+/// fn user(id: u32) -> ResponseJSON { 
+///     find_user(id).into()
+/// }
+/// ```
+impl<T: Serialize> From<Result<T, GenericError>> for ResponseJSON {
+    fn from(result: Result<T, GenericError>) -> Self {
+        match result {
+            Ok(data) => match serde_json::to_value(data) {
+                Ok(data) => ResponseJSON::ok().data(data),
+                Err(serde_err) => ResponseJSON::from_error(GenericError::Serde(serde_err)).http_code(500),
+            },
+            Err(err) => ResponseJSON::from_error(err),
+        }
+    }
+}
+
+impl<T> IsResponseJSON for ResponseJSON<T> {
     /// Check if the JSON described as a String is a valid ResponseJSON
     fn is_valid_json(&self) -> bool {
         true
@@ -285,6 +796,8 @@ impl IsResponseJSON for ResponseJSON {
         self.success == true &&
         self.method.is_none() &&
         self.message.is_none() &&
+        self.error_code.is_none() &&
+        self.errors.is_empty() &&
         self.resource.is_none()
     }
 }
@@ -300,7 +813,9 @@ impl IsResponseJSON for serde_json::map::Map<String, Value> {
         self["http_code"].is_number() &&
         self["method"].is_null() &&
         self["resource"].is_null() &&
-        self["message"].is_null()
+        self["message"].is_null() &&
+        self["error_code"].is_null() &&
+        self["errors"].is_null()
     }
 
     fn is_error_json(&self) -> bool {
@@ -308,6 +823,8 @@ impl IsResponseJSON for serde_json::map::Map<String, Value> {
         self["success"] == Value::Bool(false) &&
         self["http_code"].is_number() &&
         (self.get("message").is_none() || self["message"].is_string()) &&
+        (self.get("error_code").is_none() || self["error_code"].is_string()) &&
+        (self.get("errors").is_none() || self["errors"].is_array()) &&
         (self.get("resource").is_none() || self["resource"].is_string()) &&
         (self.get("method").is_none() || self["method"].is_string())
     }
@@ -324,7 +841,9 @@ impl IsResponseJSON for Value {
         self["http_code"].is_number() &&
         self["method"].is_null() &&
         self["resource"].is_null() &&
-        self["message"].is_null()
+        self["message"].is_null() &&
+        self["error_code"].is_null() &&
+        self["errors"].is_null()
     }
 
     fn is_error_json(&self) -> bool {
@@ -332,6 +851,8 @@ impl IsResponseJSON for Value {
         self["success"] == Value::Bool(false) &&
         self["http_code"].is_number() &&
         (self.get("message").is_none() || self["message"].is_string()) &&
+        (self.get("error_code").is_none() || self["error_code"].is_string()) &&
+        (self.get("errors").is_none() || self["errors"].is_array()) &&
         (self.get("resource").is_none() || self["resource"].is_string()) &&
         (self.get("method").is_none() || self["method"].is_string())
     }
@@ -395,8 +916,8 @@ impl IsResponseJSON for str {
     }
 }
 
-impl<T: ToString> PartialEq<T> for ResponseJSON {
-    fn eq(&self, other: &T) -> bool {
+impl<T: Serialize, U: ToString> PartialEq<U> for ResponseJSON<T> {
+    fn eq(&self, other: &U) -> bool {
         self.to_string() == other.to_string()
     }
 }
@@ -442,6 +963,10 @@ mod tests {
 
     use super::ResponseJSON;
     use super::IsResponseJSON;
+    use super::FieldError;
+    use super::ParseMode;
+
+    use std::error::Error;
 
     use serde_json;
     use serde_json::Value;
@@ -449,8 +974,10 @@ mod tests {
     use rocket;
     use rocket::testing::MockRequest;
     use rocket::http::{ ContentType, Method, Status };
+    use rocket::response::Responder;
 
     use contrib::rocket::FormHashMap;
+    use error::GenericError;
 
     #[test]
     fn ResponseJSON_test_IsResponseJSON_implem() {
@@ -788,6 +1315,377 @@ mod tests {
         assert_eq!(json.is_ok_json(), true); // ensure value is not moved
     }
 
+    #[test]
+    fn ResponseJSON_test_typed_data() {
+        let json : ResponseJSON<Vec<u32>> = ResponseJSON::ok()
+            .http_code(201)
+            .with_data(vec![1, 2, 3]);
+        assert_eq!(json.data, vec![1, 2, 3]);
+        assert_eq!(json.is_ok_json(), true);
+
+        let ref_json : Value = json!({
+            "success": true, 
+            "http_code": 201, 
+            "data": [1, 2, 3]
+        });
+        assert_eq!(json, ref_json);
+        assert_eq!(json.into_string(), ref_json.to_string());
+    }
+
+    #[test]
+    fn ResponseJSON_test_serde() {
+        let json : ResponseJSON = serde_json::from_value(json!({
+            "success": false,
+            "http_code": 404,
+            "resource": "/some/path",
+            "message": "not found"
+        })).unwrap();
+        assert_eq!(json.is_error_json(), true);
+        assert_eq!(json.http_code, 404);
+        assert!(json.data.is_null());
+        assert_eq!(json.resource, Some("/some/path".to_string()));
+        assert_eq!(json.message, Some("not found".to_string()));
+        assert_eq!(json.method, None);
+
+        let string = serde_json::to_string(&json).unwrap();
+        let round_trip : ResponseJSON = serde_json::from_str(&string).unwrap();
+        assert_eq!(round_trip, json);
+
+        let json : ResponseJSON<Vec<u32>> = serde_json::from_str(r#"{
+            "success": true,
+            "http_code": 200,
+            "data": [ 1, 2, 3 ]
+        }"#).unwrap();
+        assert_eq!(json.data, vec![1, 2, 3]);
+        assert_eq!(serde_json::to_value(&json).unwrap(), json!({ "success": true, "http_code": 200, "data": [1, 2, 3] }));
+
+        // not an envelope
+        let json : Result<ResponseJSON, _> = serde_json::from_str(r#"{ "test1": "value1" }"#);
+        assert!(json.is_err());
+    }
+
+    #[test]
+    fn ResponseJSON_test_paginated() {
+        let json = ResponseJSON::ok()
+            .paginated(vec![21, 22, 23], 2, 20, 43)
+            .next("/items?page=3".to_string())
+            .prev("/items?page=1".to_string());
+        assert_eq!(json.page, Some(2));
+        assert_eq!(json.per_page, Some(20));
+        assert_eq!(json.total, Some(43));
+        assert_eq!(json.total_pages, Some(3));
+
+        let ref_json : Value = json!({
+            "success": true,
+            "http_code": 200,
+            "data": [21, 22, 23],
+            "page": 2,
+            "per_page": 20,
+            "total": 43,
+            "total_pages": 3,
+            "next": "/items?page=3",
+            "prev": "/items?page=1"
+        });
+        assert_eq!(json, ref_json);
+
+        let parsed = ResponseJSON::from_str(&json.to_string()).unwrap();
+        assert_eq!(parsed, ref_json);
+
+        // unset pagination fields are omitted
+        assert_eq!(ResponseJSON::ok().to_string(), "{\"http_code\":200,\"success\":true}");
+    }
+
+    #[test]
+    fn ResponseJSON_test_responder_status() {
+        let mut response = ResponseJSON::error().http_code(404).respond().unwrap();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.header_values("Content-Type").next(), Some("application/json"));
+        let body_str = response.body().and_then(|b| b.into_string()).unwrap();
+        assert_eq!(ResponseJSON::from_str(&body_str).unwrap(), ResponseJSON::error().http_code(404));
+
+        let response = ResponseJSON::ok().http_code(201).respond().unwrap();
+        assert_eq!(response.status(), Status::Created);
+
+        let response = ResponseJSON::ok().http_code(299).respond().unwrap();
+        assert_eq!(response.status().code, 299);
+    }
+
+    #[test]
+    fn ResponseJSON_test_headers() {
+        let json = ResponseJSON::ok()
+            .header("X-Total-Count", 42)
+            .header("Link", "</users?page=2>; rel=\"next\"")
+            .header("Link", "</users?page=5>; rel=\"last\"")
+            .with_data(vec![1, 2]);
+        assert_eq!(json.headers.len(), 3);
+        assert_eq!(json.to_string(), r#"{"data":[1,2],"http_code":200,"success":true}"#);
+
+        let response = json.respond().unwrap();
+        assert_eq!(response.header_values("X-Total-Count").collect::<Vec<_>>(), vec!["42"]);
+        assert_eq!(response.header_values("Link").count(), 2);
+        assert_eq!(response.header_values("Content-Type").next(), Some("application/json"));
+    }
+
+    #[test]
+    fn ResponseJSON_test_from_error() {
+        let json = ResponseJSON::from_error(GenericError::Basic("Test error".to_string()));
+        assert_eq!(json.is_error_json(), true);
+        assert_eq!(json.http_code, 500);
+        assert_eq!(json.message, Some("Test error".to_string()));
+
+        let err = serde_json::from_str::<Value>("{ invalid").unwrap_err();
+        let json : ResponseJSON = GenericError::new_compound("Unable to parse body", GenericError::Serde(err)).into();
+        assert_eq!(json.is_error_json(), true);
+        assert_eq!(json.http_code, 400);
+        assert!(json.message.unwrap().starts_with("Unable to parse body caused by"));
+    }
+
+    #[test]
+    fn ResponseJSON_test_map_data() {
+        let json = ResponseJSON::ok()
+            .http_code(201)
+            .warning("partial result")
+            .data(json!([1, 2, 3]))
+            .map_data(|data| json!({ "items": data }));
+        assert_eq!(json.http_code, 201);
+        assert_eq!(json.warnings, vec!["partial result"]);
+        assert_eq!(json.data, json!({ "items": [1, 2, 3] }));
+
+        let json = ResponseJSON::ok_with_data(vec![1, 2, 3]).map_data(|vect| vect.len());
+        assert_eq!(json.data, 3);
+    }
+
+    #[test]
+    fn ResponseJSON_test_and_then_data() {
+        let json = ResponseJSON::ok()
+            .request_id("abc".to_string())
+            .data(json!({ "id": 17 }))
+            .and_then_data(|data| data.get("id").cloned().ok_or(GenericError::Basic("missing id".to_string())))
+            .unwrap();
+        assert_eq!(json.request_id, Some("abc".to_string()));
+        assert_eq!(json.data, json!(17));
+
+        let err = ResponseJSON::ok()
+            .data(json!({ "name": "Boris" }))
+            .and_then_data(|data| data.get("id").cloned().ok_or(GenericError::Basic("missing id".to_string())))
+            .unwrap_err();
+        assert_eq!(err.description(), "missing id");
+    }
+
+    #[test]
+    fn ResponseJSON_test_from_result() {
+        let result : Result<Vec<u32>, GenericError> = Ok(vec![1, 2]);
+        let json : ResponseJSON = result.into();
+        assert_eq!(json.is_ok_json(), true);
+        assert_eq!(json.data, json!([1, 2]));
+
+        let result : Result<Vec<u32>, GenericError> = Err(GenericError::Basic("Test error".to_string()));
+        let json : ResponseJSON = result.into();
+        assert_eq!(json.is_error_json(), true);
+        assert_eq!(json.http_code, 500);
+        assert_eq!(json.message, Some("Test error".to_string()));
+
+        let mut map = ::std::collections::BTreeMap::new();
+        map.insert(vec![1], 1);
+        let result : Result<_, GenericError> = Ok(map);
+        let json : ResponseJSON = result.into();
+        assert_eq!(json.is_error_json(), true);
+        assert_eq!(json.http_code, 500);
+    }
+
+    #[test]
+    fn ResponseJSON_test_links() {
+        let json = ResponseJSON::ok()
+            .link("self", "/users?page=2")
+            .link("next", "/users?page=3")
+            .link("author", "/users/1")
+            .link("author", "/users/2");
+        assert_eq!(json.links["self"], json!({ "href": "/users?page=2" }));
+        assert_eq!(json.links["author"], json!([{ "href": "/users/1" }, { "href": "/users/2" }]));
+
+        let json_str = json.to_string();
+        assert!(json_str.contains(r#""_links":{"#));
+        let parsed = ResponseJSON::from_str(&json_str).unwrap();
+        assert_eq!(parsed.links, json.links);
+        assert_eq!(ResponseJSON::from_str_strict(&json_str).unwrap().links, json.links);
+
+        assert!(!ResponseJSON::ok().to_string().contains("_links"));
+    }
+
+    #[test]
+    fn ResponseJSON_test_field_errors() {
+        let json = ResponseJSON::error()
+            .http_code(422)
+            .message("Invalid form".to_string())
+            .field_error("email", "INVALID_FORMAT", "not a valid email address")
+            .field_error("password", "TOO_SHORT", "must be at least 8 characters long");
+        assert_eq!(json.errors.len(), 2);
+        assert_eq!(json.errors[1], FieldError { field: "password".to_string(), code: "TOO_SHORT".to_string(), message: "must be at least 8 characters long".to_string() });
+
+        let json_str = json.to_string();
+        assert!(json_str.contains(r#""errors":[{"field":"email","code":"INVALID_FORMAT","message":"not a valid email address"}"#));
+        assert_eq!(ResponseJSON::from_str(&json_str).unwrap().errors, json.errors);
+        assert_eq!(ResponseJSON::from_str_strict(&json_str).unwrap().errors, json.errors);
+        assert!(ResponseJSON::from_str_strict(r#"{ "success": false, "http_code": 422, "errors": [{ "field": "email" }] }"#).is_err());
+        assert_eq!(json!({ "success": true, "http_code": 200, "errors": [] }).is_ok_json(), false);
+        assert_eq!(json!({ "success": false, "http_code": 422, "errors": "email" }).is_error_json(), false);
+
+        // ignored on OK JSON
+        assert!(ResponseJSON::ok().field_error("email", "INVALID_FORMAT", "not a valid email address").errors.is_empty());
+        assert!(!ResponseJSON::ok().to_string().contains("errors"));
+    }
+
+    #[test]
+    fn ResponseJSON_test_serialize_to_writer() {
+        let json = ResponseJSON::ok()
+            .link("self", "/users")
+            .paginated(vec![1, 2], 1, 2, 3)
+            .request_id("abc".to_string())
+            .warning("partial result");
+
+        let mut buffer = Vec::new();
+        serde_json::to_writer(&mut buffer, &json).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), json.to_string());
+        assert_eq!(json.to_string(), r#"{"_links":{"self":{"href":"/users"}},"data":[1,2],"http_code":200,"page":1,"per_page":2,"request_id":"abc","success":true,"total":3,"total_pages":2,"warnings":["partial result"]}"#);
+
+        assert_eq!(ResponseJSON::ok().with_data(()).to_string(), r#"{"http_code":200,"success":true}"#);
+        assert_eq!(ResponseJSON::ok().with_data(None::<u32>).to_string(), r#"{"http_code":200,"success":true}"#);
+    }
+
+    #[test]
+    fn ResponseJSON_test_data_as() {
+        let json = ResponseJSON::ok().data(json!([1, 2, 3]));
+        assert_eq!(json.data_as::<Vec<u32>>().unwrap(), vec![1, 2, 3]);
+        assert!(json.data_as::<String>().is_err());
+        assert_eq!(json.into_data_as::<Vec<u8>>().unwrap(), vec![1, 2, 3]);
+
+        let json = ResponseJSON::ok().data(json!({ "a": 1, "b": 2 }));
+        let map = json.data_as::<::std::collections::BTreeMap<String, i64>>().unwrap();
+        assert_eq!(map["b"], 2);
+
+        assert_eq!(ResponseJSON::ok().data(json!([1, 2])).into_typed::<Vec<u8>>().unwrap(), vec![1, 2]);
+        match ResponseJSON::error().http_code(404).message("No such user".to_string()).into_typed::<Vec<u8>>() {
+            Err(GenericError::Upstream((404, ref message))) => assert_eq!(message, "No such user"),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ResponseJSON_test_filter_fields() {
+        let json = ResponseJSON::ok().data(json!({
+            "id": 17,
+            "name": "Boris",
+            "address": { "city": "Cotonou", "country": "Benin" },
+            "tags": [ { "id": 1, "label": "a" }, { "id": 2, "label": "b" } ]
+        }));
+
+        assert_eq!(json.clone().filter_fields(&["id", "address.city", "tags.label"]).data, json!({
+            "id": 17,
+            "address": { "city": "Cotonou" },
+            "tags": [ { "label": "a" }, { "label": "b" } ]
+        }));
+        assert_eq!(json.clone().filter_fields(&["address", "address.city"]).data, json!({
+            "address": { "city": "Cotonou", "country": "Benin" }
+        }));
+        assert_eq!(json.clone().filter_fields(&["unknown"]).data, json!({}));
+        assert_eq!(json.clone().filter_fields(&[]), json);
+
+        let json = ResponseJSON::ok().data(json!([ { "id": 1, "label": "a" }, { "id": 2, "label": "b" } ]));
+        assert_eq!(json.filter_fields(&["id"]).data, json!([ { "id": 1 }, { "id": 2 } ]));
+    }
+
+    #[test]
+    fn ResponseJSON_test_warnings() {
+        let json = ResponseJSON::ok()
+            .data(json!([1, 2]))
+            .warning("`/v1/items` is deprecated")
+            .warning("partial result".to_string());
+        assert_eq!(json.warnings, vec!["`/v1/items` is deprecated", "partial result"]);
+        assert_eq!(json.is_ok_json(), true);
+
+        let ref_json = json!({
+            "success": true,
+            "http_code": 200,
+            "data": [1, 2],
+            "warnings": [ "`/v1/items` is deprecated", "partial result" ]
+        });
+        assert_eq!(json, ref_json);
+        assert_eq!(ResponseJSON::from_str(&ref_json.to_string()).unwrap(), json);
+
+        // omitted when empty
+        assert_eq!(ResponseJSON::ok().data(json!([1, 2])).to_string(), r#"{"data":[1,2],"http_code":200,"success":true}"#);
+    }
+
+    #[test]
+    fn ResponseJSON_test_error_code() {
+        let json = ResponseJSON::error()
+            .http_code(404)
+            .message("No user with id 17".to_string())
+            .code("USER_NOT_FOUND");
+        assert_eq!(json.error_code, Some("USER_NOT_FOUND".to_string()));
+        assert_eq!(json.is_error_json(), true);
+
+        let ref_json = json!({
+            "success": false,
+            "http_code": 404,
+            "message": "No user with id 17",
+            "error_code": "USER_NOT_FOUND"
+        });
+        assert_eq!(json, ref_json);
+        assert_eq!(ref_json.is_error_json(), true);
+        assert_eq!(ResponseJSON::from_str(&ref_json.to_string()).unwrap(), json);
+
+        // ignored on Ok JSON
+        assert_eq!(ResponseJSON::ok().code("USER_NOT_FOUND").error_code, None);
+        assert_eq!(r#"{"success":true,"http_code":200,"error_code":"USER_NOT_FOUND"}"#.is_ok_json(), false);
+    }
+
+    #[test]
+    fn ResponseJSON_test_one_shot_constructors() {
+        let json = ResponseJSON::ok_with_data(vec!["a", "b"]);
+        assert_eq!(json.data, vec!["a", "b"]);
+        assert_eq!(json, json!({ "success": true, "http_code": 200, "data": ["a", "b"] }));
+
+        let json = ResponseJSON::ok_with_data(json!({ "id": 17 }));
+        assert_eq!(json.data["id"], 17);
+
+        let json = ResponseJSON::error_with_message(404, "No user with id 17");
+        assert_eq!(json.is_error_json(), true);
+        assert_eq!(json, json!({ "success": false, "http_code": 404, "message": "No user with id 17" }));
+    }
+
+    #[test]
+    fn ResponseJSON_test_from_str_strict() {
+        let json = ResponseJSON::from_str_strict(r#"{
+            "success": false,
+            "http_code": 404,
+            "message": "not found",
+            "resource": "/users/17"
+        }"#).unwrap();
+        assert_eq!(json.http_code, 404);
+        assert_eq!(json.resource, Some("/users/17".to_string()));
+
+        let json = ResponseJSON::from_str_with_mode(r#"{ "success": true, "http_code": 201, "data": [1, 2] }"#, ParseMode::Strict).unwrap();
+        assert_eq!(json.http_code, 201);
+        assert_eq!(json.data, json!([1, 2]));
+
+        fn strict_error(s: &str) -> String {
+            ResponseJSON::from_str_strict(s).unwrap_err().description().to_string()
+        }
+        assert!(strict_error(r#"[1, 2]"#).contains("must be a JSON object"));
+        assert!(strict_error(r#"{ "http_code": 200 }"#).contains("`success`: missing"));
+        assert!(strict_error(r#"{ "success": "yes", "http_code": 200 }"#).contains("`success`: must be a boolean"));
+        assert!(strict_error(r#"{ "success": true, "http_code": 70000 }"#).contains("`http_code`"));
+        assert!(strict_error(r#"{ "success": true, "http_code": 201, "message": "error message" }"#).contains("`message`: not allowed on an OK ResponseJSON"));
+        assert!(strict_error(r#"{ "success": false, "http_code": 500, "method": 12 }"#).contains("`method`: must be a string"));
+        assert!(strict_error(r#"{ "success": true, "http_code": 200, "test1": "value1" }"#).contains("`test1`: unknown field"));
+        assert!(ResponseJSON::from_str_strict("{ invalid").is_err());
+
+        // lenient mode still wraps improper envelopes
+        let json = ResponseJSON::from_str_with_mode(r#"{ "test1": "value1" }"#, ParseMode::Lenient).unwrap();
+        assert_eq!(json.data["test1"], "value1");
+    }
+
     #[test]
     fn ResponseJSON_test_eq() {
         let json = ResponseJSON::ok()
@@ -0,0 +1,244 @@
+//! File holding the StreamedResponseJSON type and associated tests
+//!
+//! Author: [Boris](mailto:boris@humanenginuity.com)
+//! Version: 1.1
+//!
+//! ## Release notes
+//! - v1.1 : the custom headers of the ResponseJSON are added to the response
+//! - v1.0 : creation
+
+// =======================================================================
+// LIBRARY IMPORTS
+// =======================================================================
+use std::cmp;
+use std::io;
+use std::io::Read;
+use std::mem;
+
+use rocket::Response;
+use rocket::http::{ ContentType, Status };
+use rocket::response::{ Responder, Stream };
+use serde::Serialize;
+use serde_json;
+
+use types::ResponseJSON;
+
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// A ResponseJSON whose `data` is an array streamed from an iterator.
+/// Usually built with `ResponseJSON::stream_from_iter()`
+///
+/// It implements `Read`, producing the envelope and serializing the elements of the array one at a time, 
+/// so the whole body is never held in memory.
+/// It derives Rocket's [Responder trait](https://api.rocket.rs/rocket/response/trait.Responder.html) and responds
+/// with a chunked JSON body and the `http_code` of the envelope as HTTP status
+///
+/// ```rust,ignore
+/// #[get("/export")]
+/// fn export() -> StreamedResponseJSON<impl Iterator<Item = Value>> { 
+///     ResponseJSON::ok().stream_from_iter(database.rows().map(|row| row.to_json()))
+/// }
+/// ```
+pub struct StreamedResponseJSON<I> {
+    http_code: u16,
+    headers: Vec<(String, String)>,
+    items: I,
+    suffix: String,
+    state: StreamState,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+// Next chunk to be written into the buffer
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StreamState {
+    Prefix,
+    FirstItem,
+    Items,
+    Suffix,
+    Done,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl<T> ResponseJSON<T> {
+    /// Stream this ResponseJSON with `data` replaced by an array of the elements of `items`
+    pub fn stream_from_iter<I>(mut self, items: I) -> StreamedResponseJSON<I::IntoIter>
+        where 
+            I: IntoIterator,
+            I::Item: Serialize
+    {
+        // The envelope without data always starts with '{' => keep everything after it
+        let http_code = self.http_code;
+        let headers = mem::replace(&mut self.headers, Vec::new());
+        let envelope = self.with_data(()).to_string();
+
+        StreamedResponseJSON {
+            http_code: http_code,
+            headers: headers,
+            items: items.into_iter(),
+            suffix: format!(",{}", &envelope[1..]),
+            state: StreamState::Prefix,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl<I> StreamedResponseJSON<I> 
+    where
+        I: Iterator,
+        I::Item: Serialize
+{
+    // Refill the buffer with the next chunk of the body
+    fn next_chunk(&mut self) -> io::Result<()> {
+        self.buffer.clear();
+        self.position = 0;
+
+        match self.state {
+            StreamState::Prefix => {
+                self.buffer.extend_from_slice(b"{\"data\":[");
+                self.state = StreamState::FirstItem;
+            },
+            StreamState::FirstItem | StreamState::Items => {
+                match self.items.next() {
+                    Some(item) => {
+                        if self.state == StreamState::Items {
+                            self.buffer.push(b',');
+                        }
+                        serde_json::to_writer(&mut self.buffer, &item)
+                            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                        self.state = StreamState::Items;
+                    },
+                    None => {
+                        self.buffer.push(b']');
+                        self.state = StreamState::Suffix;
+                    },
+                }
+            },
+            StreamState::Suffix => {
+                self.buffer.extend_from_slice(self.suffix.as_bytes());
+                self.state = StreamState::Done;
+            },
+            StreamState::Done => (),
+        }
+        Ok(())
+    }
+}
+
+// =======================================================================
+// TRAIT IMPLEMENTATION
+// =======================================================================
+impl<I> Read for StreamedResponseJSON<I> 
+    where
+        I: Iterator,
+        I::Item: Serialize
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position >= self.buffer.len() {
+            if self.state == StreamState::Done {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+
+        let size = cmp::min(buf.len(), self.buffer.len() - self.position);
+        buf[..size].copy_from_slice(&self.buffer[self.position..self.position + size]);
+        self.position += size;
+        Ok(size)
+    }
+}
+
+/// Responds with a chunked JSON body, the `http_code` of the envelope as HTTP status & its custom headers
+impl<'r, I> Responder<'r> for StreamedResponseJSON<I> 
+    where
+        I: Iterator + 'r,
+        I::Item: Serialize
+{
+    fn respond(mut self) -> Result<Response<'r>, Status> {
+        let status = Status::from_code(self.http_code).unwrap_or(Status::new(self.http_code, "<unknown code>"));
+        let headers = mem::replace(&mut self.headers, Vec::new());
+        Stream::from(self).respond()
+            .map(|mut response| {
+                response.set_header(ContentType::JSON);
+                response.set_status(status);
+                for (name, value) in headers {
+                    response.adjoin_raw_header(name, value);
+                }
+                response
+            })
+    }
+}
+
+// =======================================================================
+// UNIT TESTS
+// =======================================================================
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
+
+    use std::io::Read;
+
+    use rocket::http::Status;
+    use rocket::response::Responder;
+    use serde_json::Value;
+
+    use types::ResponseJSON;
+
+    #[test]
+    fn StreamedResponseJSON_test_read() {
+        let mut body = String::new();
+        ResponseJSON::ok()
+            .http_code(206)
+            .stream_from_iter((1..4).map(|i| json!({ "id": i })))
+            .read_to_string(&mut body)
+            .unwrap();
+
+        assert_eq!(body, r#"{"data":[{"id":1},{"id":2},{"id":3}],"http_code":206,"success":true}"#);
+        assert_eq!(ResponseJSON::from_str(&body).unwrap().data, json!([ { "id": 1 }, { "id": 2 }, { "id": 3 } ]));
+    }
+
+    #[test]
+    fn StreamedResponseJSON_test_small_buffer() {
+        let mut stream = ResponseJSON::ok().stream_from_iter(vec!["a", "b"]);
+        let mut body = Vec::new();
+        let mut buf = [0u8; 3];
+        loop {
+            let size = stream.read(&mut buf).unwrap();
+            if size == 0 { break; }
+            body.extend_from_slice(&buf[..size]);
+        }
+        assert_eq!(String::from_utf8(body).unwrap(), r#"{"data":["a","b"],"http_code":200,"success":true}"#);
+    }
+
+    #[test]
+    fn StreamedResponseJSON_test_empty() {
+        let mut body = String::new();
+        ResponseJSON::ok()
+            .stream_from_iter(Vec::<Value>::new())
+            .read_to_string(&mut body)
+            .unwrap();
+        assert_eq!(body, r#"{"data":[],"http_code":200,"success":true}"#);
+    }
+
+    #[test]
+    fn StreamedResponseJSON_test_responder() {
+        let mut response = ResponseJSON::ok().stream_from_iter(vec![1, 2, 3]).respond().unwrap();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.header_values("Content-Type").next(), Some("application/json"));
+
+        let body_str = response.body().and_then(|b| b.into_string()).unwrap();
+        assert_eq!(body_str, r#"{"data":[1,2,3],"http_code":200,"success":true}"#);
+
+        let response = ResponseJSON::ok()
+            .header("X-Total-Count", "3")
+            .header("Link", "</page/2>; rel=\"next\"")
+            .stream_from_iter(vec![1, 2, 3])
+            .respond()
+            .unwrap();
+        assert_eq!(response.header_values("X-Total-Count").next(), Some("3"));
+        assert_eq!(response.header_values("Link").next(), Some("</page/2>; rel=\"next\""));
+    }
+}
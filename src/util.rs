@@ -2,10 +2,26 @@
 // LIBRARY IMPORTS
 // =======================================================================
 use serde_json;
+use serde_json::Value;
+use serde_json::map::Map;
 
 use std::borrow::Borrow;
+use std::cmp;
 use std::hash::Hash;
 
+use hyper::Url;
+
+#[cfg(feature = "amiwo_rocket")]
+use contrib::rocket::FormHashMap;
+use error::GenericError;
+use traits::Pushable;
+
+// =======================================================================
+// CONSTANTS
+// =======================================================================
+/// Maximum number of `[...]` segments of a key using the bracket notation (e.g. 2 for `user[tags][]`)
+pub const MAX_KEY_DEPTH: usize = 32;
+
 // =======================================================================
 // TRAIT DECLARATION
 // =======================================================================
@@ -17,6 +33,314 @@ pub trait ContainsKeys<K> {
             Q: Hash + Eq + Ord;
 }
 
+// =======================================================================
+// STRUCT DEFINITION
+// =======================================================================
+/// Builder of an URL from a base, path segments & query parameters, all percent encoded
+///
+/// ```rust,ignore
+/// let url = UrlBuilder::new("http://users.internal/api")
+///     .segment("users")
+///     .segment(user_name)          // `Boris B/1` => `Boris%20B%2F1`
+///     .query("fields", "name,email")
+///     .query_map(&filters)         // `{ "tags": ["a", "b"] }` => `tags[]=a&tags[]=b`
+///     .build()?;
+/// let rjson = RequestBuilder::new().url_builder(UrlBuilder::new(BASE_URL).segment("users")).send()?;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct UrlBuilder {
+    base: String,
+    segments: Vec<String>,
+    pairs: Vec<String>,
+}
+
+// =======================================================================
+// STRUCT IMPLEMENTATION
+// =======================================================================
+impl UrlBuilder {
+    /// Start from `base`, which may already hold a path & a query string
+    pub fn new(base: &str) -> UrlBuilder {
+        UrlBuilder {
+            base: base.to_string(),
+            segments: Vec::new(),
+            pairs: Vec::new(),
+        }
+    }
+
+    /// Append the path segment `segment`, percent encoded (`/` included, as well as the dots of `.` & `..`)
+    pub fn segment<S: ToString>(mut self, segment: S) -> UrlBuilder {
+        self.segments.push(encode_segment(&segment.to_string()));
+        self
+    }
+
+    /// Append the `key=value` pair to the query string, percent encoded
+    pub fn query<K: ToString, V: ToString>(mut self, key: K, value: V) -> UrlBuilder {
+        self.pairs.push(format!("{}={}", url_encode(&key.to_string()), url_encode(&value.to_string())));
+        self
+    }
+
+    /// Append the parameters of `params` to the query string, nested values using the bracket notation and arrays the `[]` suffix
+    /// (see `FormHashMap::to_query_string()`)
+    pub fn query_map(mut self, params: &Map<String, Value>) -> UrlBuilder {
+        for (key, value) in params.iter() {
+            push_query_pairs(&mut self.pairs, url_encode(key), value);
+        }
+        self
+    }
+
+    /// Append the parameters of `params` to the query string (see `FormHashMap::to_query_string()`)
+    #[cfg(feature = "amiwo_rocket")]
+    pub fn query_form<'s>(mut self, params: &FormHashMap<'s>) -> UrlBuilder {
+        let query = params.to_query_string();
+        if !query.is_empty() {
+            self.pairs.push(query);
+        }
+        self
+    }
+
+    /// Build the URL, returns an error if it isn't a valid URL or if a `.` / `..` segment would be resolved by the URL parser
+    /// (which treats `%2E` as a dot), removing the segments appended before it
+    pub fn build(&self) -> Result<String, GenericError> {
+        let (base, fragment) = match self.base.find('#') {
+            Some(index) => (&self.base[..index], &self.base[index..]),
+            None => (&self.base[..], ""),
+        };
+        let (path, query) = match base.find('?') {
+            Some(index) => (&base[..index], &base[index + 1..]),
+            None => (base, ""),
+        };
+
+        let mut url = path.to_string();
+        for segment in &self.segments {
+            if !url.ends_with('/') {
+                url.push('/');
+            }
+            url.push_str(segment);
+        }
+
+        let mut pairs : Vec<&str> = if query.is_empty() { Vec::new() } else { vec![query] };
+        pairs.extend(self.pairs.iter().map(|pair| pair.as_str()));
+        if !pairs.is_empty() {
+            url.push('?');
+            url.push_str(&pairs.join("&"));
+        }
+        url.push_str(fragment);
+
+        let parsed = match Url::parse(&url) {
+            Ok(parsed) => parsed,
+            Err(err) => return amiwo_error!(format!("::AMIWO::UTIL::URL_BUILDER::BUILD::ERROR Invalid URL {}", self.base), GenericError::from(err)),
+        };
+        if !parsed.path().ends_with(&self.segments.join("/")) {
+            return amiwo_error!(format!("::AMIWO::UTIL::URL_BUILDER::BUILD::ERROR Dot segment in the path of {}", url));
+        }
+        Ok(url)
+    }
+}
+
+// =======================================================================
+// PUBLIC FUNCTIONS
+// =======================================================================
+/// Parse an `application/x-www-form-urlencoded` string (e.g. `a=b1&a=b2&user[name]=bob`) into a map,
+/// with the same semantics as `FormHashMap`:
+/// - keys & values are URL decoded (`+` being a space)
+/// - repeated keys become an array only if there is more than one value (`a=b1&a=b2` => `{ "a": ["b1", "b2"] }`)
+/// - a key with a `[]` suffix is always an array (`ids[]=1` => `{ "ids": ["1"] }`)
+/// - keys using the bracket notation are parsed into nested values (`user[name]=bob` => `{ "user": { "name": "bob" } }`),
+///   up to `MAX_KEY_DEPTH` levels
+///
+/// Parsing stops at the first malformed pair (missing `=` or empty key), the pairs before being kept.
+/// Returns an error if a key or a value isn't valid percent encoded UTF-8, if a key is nested deeper than `MAX_KEY_DEPTH`
+/// or if a parameter conflicts with a previous one (e.g. `a=1&a[b]=2`)
+pub fn parse_query_string(query: &str) -> Result<Map<String, Value>, GenericError> {
+    let mut map = Map::new();
+    let mut rest = query;
+
+    while !rest.is_empty() {
+        let (key, value, next) = match rest.find(|c| c == '=' || c == '&') {
+            Some(idx) if rest.as_bytes()[idx] == b'=' && idx > 0 => {
+                let value_end = rest[idx + 1..].find('&').map_or(rest.len(), |end| idx + 1 + end);
+                (&rest[..idx], &rest[idx + 1..value_end], &rest[cmp::min(value_end + 1, rest.len())..])
+            },
+            _ => {
+                warn!("::AMIWO::UTIL::PARSE_QUERY_STRING::WARNING Query string {} couldn't be completely parsed", query);
+                break;
+            },
+        };
+
+        let key = url_decode(key)?;
+        let value = url_decode(value)?;
+        insert_param(&mut map, &key, Value::String(value))?;
+        rest = next;
+    }
+
+    Ok(map)
+}
+
+/// Decode a percent encoded `application/x-www-form-urlencoded` component, `+` being a space.
+/// Invalid escape sequences (e.g. `%zz`) are kept as is, an error is returned if the result isn't valid UTF-8
+pub fn url_decode(encoded: &str) -> Result<String, GenericError> {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'+' => decoded.push(b' '),
+            b'%' if idx + 2 < bytes.len() && hex_value(bytes[idx + 1]).is_some() && hex_value(bytes[idx + 2]).is_some() => {
+                decoded.push(hex_value(bytes[idx + 1]).unwrap() * 16 + hex_value(bytes[idx + 2]).unwrap());
+                idx += 2;
+            },
+            byte => decoded.push(byte),
+        }
+        idx += 1;
+    }
+
+    String::from_utf8(decoded)
+        .or_else(|_| amiwo_error!(format!("::AMIWO::UTIL::URL_DECODE::ERROR {} isn't valid percent encoded UTF-8", encoded)))
+}
+
+/// Percent encode `string` for an `application/x-www-form-urlencoded` string, a space being encoded as `+`
+pub fn url_encode(string: &str) -> String {
+    let mut encoded = String::with_capacity(string.len());
+    for byte in string.bytes() {
+        match byte {
+            b'A' ... b'Z' | b'a' ... b'z' | b'0' ... b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// =======================================================================
+// PRIVATE FUNCTIONS
+// =======================================================================
+// Append the `key=value` pairs of `value` to `pairs`, using the bracket notation for nested values
+pub(crate) fn push_query_pairs(pairs: &mut Vec<String>, key: String, value: &Value) {
+    match *value {
+        Value::Object(ref map) => for (sub_key, sub_value) in map.iter() {
+            push_query_pairs(pairs, format!("{}[{}]", key, url_encode(sub_key)), sub_value);
+        },
+        Value::Array(ref vect) => for item in vect.iter() {
+            push_query_pairs(pairs, format!("{}[]", key), item);
+        },
+        Value::Null => pairs.push(format!("{}=", key)),
+        Value::String(ref string) => pairs.push(format!("{}={}", key, url_encode(string))),
+        ref scalar => pairs.push(format!("{}={}", key, url_encode(&scalar.to_string()))),
+    }
+}
+
+// Percent encode the path segment `segment`, keeping the characters allowed in a segment except `/`,
+// and the dots of a dot segment (`.`, `..`) which would move up the path
+fn encode_segment(segment: &str) -> String {
+    if !segment.is_empty() && segment.bytes().all(|byte| byte == b'.') {
+        return segment.replace(".", "%2E");
+    }
+
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A' ... b'Z' | b'a' ... b'z' | b'0' ... b'9' | b'-' | b'_' | b'.' | b'~'
+                | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':' | b'@' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// Value of the hexadecimal digit `byte`
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0' ... b'9' => Some(byte - b'0'),
+        b'a' ... b'f' => Some(byte - b'a' + 10),
+        b'A' ... b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+// Segment of a key using the bracket notation
+#[derive(Debug, PartialEq)]
+enum KeySegment {
+    Key(String), // `[name]`
+    Append,      // `[]`
+}
+
+// Split `user[tags][]` into `("user", [Key("tags"), Append])`, `None` if `key` doesn't use the bracket notation
+fn parse_key(key: &str) -> Option<(&str, Vec<KeySegment>)> {
+    let start = match key.find('[') {
+        Some(0) | None => return None,
+        Some(start) => start,
+    };
+
+    let mut segments = Vec::new();
+    let mut rest = &key[start..];
+    while !rest.is_empty() {
+        if !rest.starts_with('[') { return None; }
+        let end = match rest.find(']') {
+            Some(end) => end,
+            None => return None,
+        };
+        let segment = &rest[1..end];
+        if segment.contains('[') { return None; }
+        segments.push(if segment.is_empty() { KeySegment::Append } else { KeySegment::Key(segment.to_string()) });
+        rest = &rest[end + 1..];
+    }
+
+    Some((&key[..start], segments))
+}
+
+// Insert `value` at `key` in `map`, following the bracket notation. Repeated plain keys become arrays.
+// Keys nested deeper than `MAX_KEY_DEPTH` are rejected, `insert_at()` recursing once per level
+pub(crate) fn insert_param(map: &mut Map<String, Value>, key: &str, value: Value) -> Result<(), GenericError> {
+    match parse_key(key) {
+        Some((root, ref segments)) if segments.len() > MAX_KEY_DEPTH => {
+            amiwo_error!(format!("::AMIWO::UTIL::INSERT_PARAM::ERROR Parameter `{}` is nested deeper than {} levels", root, MAX_KEY_DEPTH))
+        },
+        Some((root, segments)) => insert_at(map.entry(root).or_insert(Value::Null), &segments, value)
+            .or_else(|_| amiwo_error!(format!("::AMIWO::UTIL::INSERT_PARAM::ERROR Parameter `{}` conflicts with a previous parameter", key))),
+        None => {
+            map.entry(key).or_insert(Value::Null).push(value);
+            Ok(())
+        },
+    }
+}
+
+// Insert `value` in `target` following `segments`, `Err` if `target` isn't of the expected type (e.g. `a=1&a[b]=2`)
+fn insert_at(target: &mut Value, segments: &[KeySegment], value: Value) -> Result<(), ()> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            target.push(value);
+            return Ok(());
+        },
+    };
+
+    if target.is_null() {
+        *target = match *segment {
+            KeySegment::Key(_) => Value::Object(Map::new()),
+            KeySegment::Append => Value::Array(Vec::new()),
+        };
+    }
+
+    match (segment, target) {
+        (&KeySegment::Key(ref key), &mut Value::Object(ref mut map)) => insert_at(map.entry(key.as_str()).or_insert(Value::Null), rest, value),
+        (&KeySegment::Append, &mut Value::Array(ref mut vect)) => {
+            // `list[][name]=a&list[][id]=1&list[][name]=b` => `[{ "name": "a", "id": "1" }, { "name": "b" }]`
+            let reuse_last = match (rest.first(), vect.last()) {
+                (Some(&KeySegment::Key(ref key)), Some(&Value::Object(ref last))) => !last.contains_key(key),
+                _ => false,
+            };
+            if !reuse_last {
+                vect.push(Value::Null);
+            }
+            let last = vect.len() - 1;
+            insert_at(&mut vect[last], rest, value)
+        },
+        _ => Err(()),
+    }
+}
+
+
 // =======================================================================
 // TRAIT IMPLEMENTATION
 // =======================================================================
@@ -49,7 +373,7 @@ impl ContainsKeys<String> for serde_json::map::Map<String, serde_json::Value> {
 #[cfg(test)]
 mod tests {
     use serde_json;
-    use super::ContainsKeys;
+    use super::{ encode_segment, parse_query_string, url_decode, url_encode, ContainsKeys, UrlBuilder, MAX_KEY_DEPTH };
 
     #[test]
     fn contains_keys() {
@@ -57,4 +381,70 @@ mod tests {
         assert_eq!(obj.contains_keys(&["a", "b"]), true); 
         assert_eq!(obj.contains_keys(&["a", "b", "c"]), false);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parse_query_string_test() {
+        let map = parse_query_string("a=b1&a=b2&b=c+d&ids[]=1&user[name]=bob%20b&user%5Btags%5D[]=x").unwrap();
+        assert_eq!(map.get("a"), Some(&json!(["b1", "b2"])));
+        assert_eq!(map.get("b"), Some(&json!("c d")));
+        assert_eq!(map.get("ids"), Some(&json!(["1"])));
+        assert_eq!(map.get("user"), Some(&json!({ "name": "bob b", "tags": ["x"] })));
+
+        assert_eq!(parse_query_string("").unwrap().len(), 0);
+        assert_eq!(parse_query_string("a=&b=1&c&d=2").unwrap(), json!({ "a": "", "b": "1" }).as_object().unwrap().clone());
+        assert!(parse_query_string("a=%FF").is_err());
+        assert!(parse_query_string("a=1&a[b]=2").is_err());
+
+        let key = format!("a{}", "[a]".repeat(MAX_KEY_DEPTH));
+        assert_eq!(parse_query_string(&format!("{}=1", key)).unwrap()["a"].pointer(&"/a".repeat(MAX_KEY_DEPTH)), Some(&json!("1")));
+        assert!(parse_query_string(&format!("{}[a]=1", key)).is_err());
+        assert!(parse_query_string(&format!("a{}=1", "[a]".repeat(10000))).is_err());
+    }
+
+    #[test]
+    fn url_decode_test() {
+        assert_eq!(url_decode("a+b%20c%C3%A9").unwrap(), "a b cé");
+        assert_eq!(url_decode("100%").unwrap(), "100%");
+        assert_eq!(url_decode("%zz%4").unwrap(), "%zz%4");
+        assert!(url_decode("%C3").is_err());
+    }
+
+    #[test]
+    fn url_encode_test() {
+        assert_eq!(url_encode("a b&c=d/é~"), "a+b%26c%3Dd%2F%C3%A9~");
+        assert_eq!(url_decode(&url_encode("100% sure?")).unwrap(), "100% sure?");
+    }
+
+    #[test]
+    fn url_builder_test() {
+        let params = json!({ "tags": ["a", "b c"], "filter": { "status": "active" }, "page": 2 });
+        let url = UrlBuilder::new("http://users.internal/api/")
+            .segment("users")
+            .segment("Boris B/1?")
+            .query("fields", "name,email")
+            .query_map(params.as_object().unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(url, "http://users.internal/api/users/Boris%20B%2F1%3F?fields=name%2Cemail&filter[status]=active&page=2&tags[]=a&tags[]=b+c");
+
+        let url = UrlBuilder::new("http://users.internal/api?key=abc#top").segment(17).query("q", "a&b").build().unwrap();
+        assert_eq!(url, "http://users.internal/api/17?key=abc&q=a%26b#top");
+        assert_eq!(UrlBuilder::new("http://users.internal").build().unwrap(), "http://users.internal");
+        assert!(UrlBuilder::new("users.internal").segment("users").build().is_err());
+    }
+
+    #[test]
+    fn url_builder_test_dot_segments() {
+        assert_eq!(encode_segment("."), "%2E");
+        assert_eq!(encode_segment(".."), "%2E%2E");
+        assert_eq!(encode_segment("..."), "%2E%2E%2E");
+        assert_eq!(encode_segment("a..b"), "a..b");
+        assert_eq!(encode_segment(""), "");
+
+        assert_eq!(UrlBuilder::new("http://users.internal/api").segment("users").segment("...").build().unwrap(), "http://users.internal/api/users/%2E%2E%2E");
+        assert_eq!(UrlBuilder::new("http://users.internal/api").segment("v1.2").segment("a..b").build().unwrap(), "http://users.internal/api/v1.2/a..b");
+        // the URL parser resolves `%2E%2E` like `..`
+        assert!(UrlBuilder::new("http://users.internal/api").segment("users").segment("..").segment("admin").build().is_err());
+        assert!(UrlBuilder::new("http://users.internal/api").segment("users").segment(".").build().is_err());
+    }
+}